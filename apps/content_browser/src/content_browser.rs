@@ -6,12 +6,14 @@ use nrg_graphics::{
 };
 use nrg_gui::{
     BaseWidget, DialogEvent, FolderDialog, Gui, HorizontalAlignment, Screen, VerticalAlignment,
-    WidgetCreator,
+    WidgetCreator, DIALOG_REQUESTER_ARG,
 };
-use nrg_messenger::{read_messages, Message, MessageChannel, MessengerRw};
+use nrg_messenger::{read_messages, Message, MessageChannel, MessengerBridge, MessengerRw};
 use nrg_platform::{WindowEvent, DEFAULT_DPI};
-use nrg_resources::{ConfigBase, DataTypeResource, FileResource, SharedDataRw};
-use nrg_serialize::{deserialize_from_file, Uid, INVALID_UID};
+use nrg_resources::{
+    read_versioned_file, ConfigBase, DataTypeResource, FileResource, SharedDataRw,
+};
+use nrg_serialize::{Uid, INVALID_UID};
 
 use crate::config::Config;
 
@@ -61,6 +63,7 @@ struct ContentBrowserSystem {
     render_passes: Vec<RenderPassRc>,
     fonts: Vec<FontRc>,
     folder_dialog_id: Uid,
+    bridge: Option<MessengerBridge>,
 }
 
 impl ContentBrowserSystem {
@@ -82,6 +85,12 @@ impl ContentBrowserSystem {
             .unwrap()
             .register_messagebox::<WindowEvent>(message_channel.get_messagebox())
             .register_messagebox::<DialogEvent>(message_channel.get_messagebox());
+
+        //The process that opened the content browser receives the result of the dialog
+        let mut bridge = MessengerBridge::connect_from_args(&global_messenger);
+        if let Some(bridge) = &mut bridge {
+            bridge.forward::<DialogEvent>();
+        }
         Self {
             id: SystemId::new(),
             config: Config::default(),
@@ -93,6 +102,7 @@ impl ContentBrowserSystem {
             render_passes: Vec::new(),
             fonts: Vec::new(),
             folder_dialog_id: INVALID_UID,
+            bridge,
         }
     }
 
@@ -142,6 +152,13 @@ impl ContentBrowserSystem {
         self.send_event(WindowEvent::RequestChangeVisible(true).as_boxed());
     }
 
+    fn close(&mut self) {
+        if let Some(bridge) = &mut self.bridge {
+            bridge.update();
+        }
+        self.send_event(WindowEvent::Close.as_boxed());
+    }
+
     fn process_messages(&mut self) {
         read_messages(self.message_channel.get_listener(), |msg| {
             if msg.type_id() == TypeId::of::<WindowEvent>() {
@@ -160,16 +177,14 @@ impl ContentBrowserSystem {
             } else if msg.type_id() == TypeId::of::<DialogEvent>() {
                 let event = msg.as_any().downcast_ref::<DialogEvent>().unwrap();
                 match &event {
-                    DialogEvent::Confirmed(widget_id, _requester_uid, text) => {
+                    DialogEvent::Confirmed(widget_id, _requester_uid, _text) => {
                         if *widget_id == self.folder_dialog_id {
-                            self.send_event(WindowEvent::Close.as_boxed());
-                            println!("[[[{}]]]", text.to_str().unwrap());
+                            self.close();
                         }
                     }
                     DialogEvent::Canceled(widget_id) => {
                         if *widget_id == self.folder_dialog_id {
-                            self.send_event(WindowEvent::Close.as_boxed());
-                            println!("[[[CANCEL]]]");
+                            self.close();
                         }
                     }
                 }
@@ -179,11 +194,18 @@ impl ContentBrowserSystem {
 
     fn add_content(&mut self) -> &mut Self {
         let args: Vec<String> = env::args().collect();
+        let requester_uid = args
+            .iter()
+            .position(|a| a == DIALOG_REQUESTER_ARG)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|uid| Uid::parse_str(uid).ok())
+            .unwrap_or(INVALID_UID);
 
         let mut folder_dialog = FolderDialog::new(&self.shared_data, &self.global_messenger);
         folder_dialog
             .vertical_alignment(VerticalAlignment::Stretch)
             .horizontal_alignment(HorizontalAlignment::Stretch)
+            .set_requester_uid(requester_uid)
             .set_title(args[1].as_str())
             .set_folder(PathBuf::from(args[2].as_str()).as_path())
             .editable(false);
@@ -209,7 +231,10 @@ impl System for ContentBrowserSystem {
     }
     fn init(&mut self) {
         let path = self.config.get_filepath();
-        deserialize_from_file(&mut self.config, path);
+        match read_versioned_file(path.as_path()) {
+            Ok(config) => self.config = config,
+            Err(e) => eprintln!("Unable to load config {}", e),
+        }
 
         self.window_init();
         self.load_pipelines();
@@ -224,6 +249,9 @@ impl System for ContentBrowserSystem {
     }
 
     fn run(&mut self) -> bool {
+        if let Some(bridge) = &mut self.bridge {
+            bridge.update();
+        }
         self.process_messages();
 
         Gui::update_widgets(&self.job_handler, false);
@@ -11,7 +11,9 @@ fn main() {
 
     for name in plugins.iter() {
         let path = PathBuf::from(library_filename(*name));
-        app.add_plugin(path);
+        if let Err(error) = app.add_plugin(path) {
+            eprintln!("{}", error);
+        }
     }
 
     let mut content_browser = ContentBrowser::default();
@@ -1,6 +1,6 @@
 use super::config::*;
 use nrg_core::*;
-use nrg_resources::ConfigBase;
+use nrg_resources::{read_versioned_file, ConfigBase};
 use nrg_serialize::*;
 
 use super::editor_updater::*;
@@ -30,7 +30,10 @@ unsafe impl Sync for Editor {}
 impl Plugin for Editor {
     fn prepare(&mut self, app: &mut App) {
         let path = self.config.get_filepath();
-        deserialize_from_file(&mut self.config, path);
+        match read_versioned_file(path.as_path()) {
+            Ok(config) => self.config = config,
+            Err(e) => eprintln!("Unable to load config {}", e),
+        }
 
         let mut update_phase = PhaseWithSystems::new(EDITOR_UPDATE_PHASE);
         let system = EditorUpdater::new(
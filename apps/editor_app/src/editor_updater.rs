@@ -11,6 +11,7 @@ use super::widgets::*;
 
 use nrg_camera::Camera;
 use nrg_core::*;
+use nrg_events::{EventsHistory, EventsHistoryOperation, SessionJournal};
 use nrg_graphics::{
     FontInstance, FontRc, MaterialInstance, MaterialRc, MeshData, MeshInstance, PipelineInstance,
     PipelineRc, RenderPassInstance, RenderPassRc, ViewInstance,
@@ -20,12 +21,13 @@ use nrg_math::{
     compute_distance_between_ray_and_oob, InnerSpace, MatBase, Matrix4, SquareMatrix, Vector2,
     Vector3, Vector4, Zero,
 };
-use nrg_messenger::{read_messages, Message, MessageChannel, MessengerRw};
+use nrg_messenger::{read_messages, Message, MessageChannel, MessengerBridge, MessengerRw};
 use nrg_platform::*;
 use nrg_resources::{
-    DataTypeResource, FileResource, ResourceRef, SerializableResource, SharedData, SharedDataRw,
+    DataTypeResource, FileResource, LoadState, ResourceLoadRef, ResourceRef, SharedData,
+    SharedDataRw,
 };
-use nrg_scene::{Object, ObjectId, Scene, SceneRc};
+use nrg_scene::{Object, ObjectId, Scene, SceneRc, Transform};
 use nrg_serialize::*;
 
 pub struct EditorUpdater {
@@ -41,15 +43,20 @@ pub struct EditorUpdater {
     graph_id: Uid,
     main_menu_id: Uid,
     message_channel: MessageChannel,
+    bridge: Option<MessengerBridge>,
+    history: EventsHistory,
+    edited_file: Option<PathBuf>,
     nodes_registry: WidgetRegistry,
     camera: Camera,
     move_camera_with_mouse: bool,
     last_mouse_pos: Vector2,
+    is_ctrl_pressed: bool,
     pipelines: Vec<PipelineRc>,
     render_passes: Vec<RenderPassRc>,
     fonts: Vec<FontRc>,
     grid_material: MaterialRc,
     scene: SceneRc,
+    loading_object: Option<ResourceLoadRef<Object>>,
     selected_object: ObjectId,
 }
 
@@ -66,16 +73,35 @@ impl EditorUpdater {
             job_handler.clone(),
         );
 
+        //All the events are read from a single channel to handle them in the order they were sent
         let message_channel = MessageChannel::default();
-
-        global_messenger
-            .write()
-            .unwrap()
-            .register_messagebox::<KeyEvent>(message_channel.get_messagebox());
         global_messenger
             .write()
             .unwrap()
-            .register_messagebox::<WidgetEvent>(message_channel.get_messagebox());
+            .register_messagebox::<KeyEvent>(message_channel.get_messagebox())
+            .register_messagebox::<MouseEvent>(message_channel.get_messagebox())
+            .register_messagebox::<WindowEvent>(message_channel.get_messagebox())
+            .register_messagebox::<WidgetEvent>(message_channel.get_messagebox())
+            .register_messagebox::<DialogEvent>(message_channel.get_messagebox())
+            .register_messagebox::<NodesEvent>(message_channel.get_messagebox());
+
+        //Processes started by the editor, like the content browser, send back their dialog events
+        let bridge = match MessengerBridge::host(&global_messenger, 0) {
+            Ok(mut bridge) => {
+                bridge.forward::<DialogEvent>();
+                Some(bridge)
+            }
+            Err(e) => {
+                eprintln!("Unable to create messenger bridge: {}", e);
+                None
+            }
+        };
+
+        //The journal is saved together with the edited file to restore its undo history
+        let mut history = EventsHistory::default();
+        history
+            .register_event_as_persistent::<TextEvent>(&global_messenger)
+            .register_event_as_persistent::<CheckboxEvent>(&global_messenger);
 
         let mut camera = Camera::new([20., 20., -20.].into(), [0., 0., 0.].into(), true);
         camera.set_projection(45., Screen::get_size().x, Screen::get_size().y, 0.1, 1000.);
@@ -88,6 +114,10 @@ impl EditorUpdater {
             show_fps: false,
             frame_seconds: VecDeque::default(),
             nodes_registry: WidgetRegistry::new(&shared_data, &global_messenger),
+            message_channel,
+            bridge,
+            history,
+            edited_file: None,
             shared_data,
             global_messenger,
             job_handler,
@@ -96,12 +126,13 @@ impl EditorUpdater {
             properties_id: INVALID_UID,
             graph_id: INVALID_UID,
             main_menu_id: INVALID_UID,
-            message_channel,
             camera,
             move_camera_with_mouse: false,
             last_mouse_pos: Vector2::zero(),
+            is_ctrl_pressed: false,
             grid_material: ResourceRef::default(),
             scene: ResourceRef::default(),
+            loading_object: None,
             selected_object: INVALID_UID,
         }
     }
@@ -152,16 +183,6 @@ impl System for EditorUpdater {
         self.create_screen();
         self.register_nodes();
 
-        self.global_messenger
-            .write()
-            .unwrap()
-            .register_messagebox::<KeyEvent>(self.message_channel.get_messagebox())
-            .register_messagebox::<MouseEvent>(self.message_channel.get_messagebox())
-            .register_messagebox::<WindowEvent>(self.message_channel.get_messagebox())
-            .register_messagebox::<WidgetEvent>(self.message_channel.get_messagebox())
-            .register_messagebox::<DialogEvent>(self.message_channel.get_messagebox())
-            .register_messagebox::<NodesEvent>(self.message_channel.get_messagebox());
-
         self.create_main_menu()
             .create_fps_counter()
             .create_properties_panel()
@@ -173,10 +194,15 @@ impl System for EditorUpdater {
     }
 
     fn run(&mut self) -> bool {
+        if let Some(bridge) = &mut self.bridge {
+            bridge.update();
+        }
         self.update_events()
+            .update_loading_object()
             .update_camera()
             .update_fps_counter()
-            .update_widgets();
+            .update_widgets()
+            .update_history();
 
         true
     }
@@ -205,7 +231,14 @@ impl EditorUpdater {
     fn create_main_menu(&mut self) -> &mut Self {
         let mut main_menu = MainMenu::new(&self.shared_data, &self.global_messenger);
         self.main_menu_id = main_menu.id();
-        main_menu.fill_nodes_from_registry(&self.nodes_registry);
+        main_menu
+            .fill_nodes_from_registry(&self.nodes_registry)
+            .set_bridge_args(
+                self.bridge
+                    .as_ref()
+                    .and_then(|b| b.connect_args())
+                    .unwrap_or_default(),
+            );
         Gui::get()
             .write()
             .unwrap()
@@ -251,6 +284,9 @@ impl EditorUpdater {
         self
     }
     fn create_scene(&mut self) -> &mut Self {
+        //Objects removed from the scene are unloaded at the next flush
+        SharedData::set_memory_budget::<Object>(&self.shared_data, Some(0));
+        SharedData::set_memory_budget::<Transform>(&self.shared_data, Some(0));
         self.scene = SharedData::add_resource::<Scene>(&self.shared_data, Scene::default());
         self
     }
@@ -268,8 +304,11 @@ impl EditorUpdater {
             let view = views.first().unwrap();
             let view_matrix = self.camera.get_view_matrix();
             let proj_matrix = self.camera.get_proj_matrix();
-            view.resource().get_mut().update_view(view_matrix);
-            view.resource().get_mut().update_proj(proj_matrix);
+            if let Some(view) = view.resource() {
+                let mut view = view.get_mut();
+                view.update_view(view_matrix);
+                view.update_proj(proj_matrix);
+            }
         }
         self
     }
@@ -350,19 +389,62 @@ impl EditorUpdater {
             let mut mesh_data = MeshData::default();
             mesh_data.add_quad_default([-1., -1., 1., 1.].into(), 0.);
             let mesh = MeshInstance::create_from_data(&self.shared_data, mesh_data);
-            self.grid_material.resource().get_mut().add_mesh(mesh);
+            self.grid_material
+                .resource()
+                .unwrap()
+                .get_mut()
+                .add_mesh(mesh);
         }
     }
 
     fn load_object(&mut self, filename: &Path) {
         if !filename.is_dir() && filename.exists() {
-            self.scene.resource().get_mut().clear();
-            let object = Object::create_from_file(&self.shared_data, filename);
-            self.scene.resource().get_mut().add_object(object);
-            self.scene
-                .resource()
-                .get_mut()
-                .update_hierarchy(&self.shared_data);
+            self.scene.resource().unwrap().get_mut().clear();
+            let (load, _job) = Object::create_from_file_async(
+                &self.shared_data,
+                &self.job_handler,
+                &self.global_messenger,
+                filename,
+            );
+            self.loading_object = Some(load);
+        }
+    }
+
+    fn update_loading_object(&mut self) -> &mut Self {
+        if let Some(loading_object) = &self.loading_object {
+            match loading_object.state() {
+                LoadState::Loading => {}
+                LoadState::Loaded => {
+                    let object = loading_object.handle().unwrap();
+                    let scene = self.scene.resource().unwrap();
+                    scene.get_mut().add_object(object);
+                    scene.get_mut().update_hierarchy(&self.shared_data);
+                    self.loading_object = None;
+                }
+                LoadState::Failed(error) => {
+                    eprintln!(
+                        "Unable to load {} - {}",
+                        loading_object.path().to_string_lossy(),
+                        error
+                    );
+                    self.loading_object = None;
+                }
+            }
+        }
+        self
+    }
+
+    fn update_history(&mut self) -> &mut Self {
+        self.history.update();
+        self
+    }
+
+    // A journal saved without its file would restore edits that the file doesn't have
+    fn save_journal(&self) {
+        if let Some(filename) = &self.edited_file {
+            if let Err(e) = self.history.journal(filename.as_path()).save() {
+                eprintln!("Unable to save journal of {}: {}", filename.display(), e);
+            }
         }
     }
 
@@ -373,13 +455,18 @@ impl EditorUpdater {
                 .unwrap()
                 .get_root_mut()
                 .remove_child(self.graph_id);
-            let new_graph = Graph::load(&self.shared_data, &self.global_messenger, filename);
+            let new_graph =
+                Graph::load(&self.shared_data, &self.global_messenger, filename.clone());
             self.graph_id = new_graph.id();
             Gui::get()
                 .write()
                 .unwrap()
                 .get_root_mut()
                 .add_child(Box::new(new_graph));
+            //Without a journal the edits of the previous file must not be undone on this one
+            let journal = SessionJournal::load(filename.as_path()).unwrap_or_default();
+            self.history.restore_journal(journal);
+            self.edited_file = Some(filename);
         }
     }
 
@@ -394,7 +481,9 @@ impl EditorUpdater {
             if filename.extension().is_none() {
                 filename.set_extension("graph");
             }
-            serialize_to_file(graph, filename);
+            serialize_to_file(graph, filename.clone());
+            self.edited_file = Some(filename);
+            self.save_journal();
         }
     }
 
@@ -450,148 +539,180 @@ impl EditorUpdater {
         read_messages(self.message_channel.get_listener(), |msg| {
             if msg.type_id() == TypeId::of::<NodesEvent>() {
                 let event = msg.as_any().downcast_ref::<NodesEvent>().unwrap();
-                let NodesEvent::Create(widget_name) = event;
-                if let Some(graph) = Gui::get()
-                    .read()
-                    .unwrap()
-                    .get_root()
-                    .get_child_mut::<Graph>(self.graph_id)
-                {
-                    let mut widget = self.nodes_registry.create_from_name(widget_name.clone());
-                    widget
-                        .get_global_messenger()
-                        .write()
-                        .unwrap()
-                        .register_messagebox::<WidgetEvent>(widget.get_messagebox())
-                        .register_messagebox::<MouseEvent>(widget.get_messagebox());
-
-                    widget
-                        .state_mut()
-                        .set_draggable(true)
-                        .set_selectable(true)
-                        .set_horizontal_alignment(HorizontalAlignment::Center)
-                        .set_vertical_alignment(VerticalAlignment::Center);
-                    graph.add_child(widget);
-                }
+                self.on_nodes_event(event);
             } else if msg.type_id() == TypeId::of::<DialogEvent>() {
                 let event = msg.as_any().downcast_ref::<DialogEvent>().unwrap();
-                if let DialogEvent::Confirmed(_widget_id, requester_uid, filename) = event {
-                    let mut should_load = false;
-                    let mut should_save = false;
-                    if let Some(menu) = Gui::get()
-                        .read()
-                        .unwrap()
-                        .get_root()
-                        .get_child_mut::<MainMenu>(self.main_menu_id)
-                    {
-                        should_load = menu.is_open_uid(*requester_uid);
-                        should_save = menu.is_save_uid(*requester_uid);
-                    }
-                    let extension = filename.extension().unwrap().to_str().unwrap();
-                    if should_load {
-                        println!("Loading {:?}", filename);
-                        if extension.contains("widget") {
-                            self.load_graph(filename.clone());
-                        } else if extension.contains("object_data") {
-                            self.load_object(filename.as_path());
-                        }
-                    } else if should_save {
-                        println!("Saving {:?}", filename);
-                        if extension.contains("widget") {
-                            self.save_graph(filename.clone());
-                        } else if extension.contains("object_data") {
-                        }
-                    }
-                }
+                self.on_dialog_event(event);
             } else if msg.type_id() == TypeId::of::<MouseEvent>() {
                 let event = msg.as_any().downcast_ref::<MouseEvent>().unwrap();
-                if event.state == MouseState::Down && event.button == MouseButton::Left {
-                    self.move_camera_with_mouse = true;
-                    self.last_mouse_pos = [event.x as f32, event.y as f32].into();
-                } else if event.state == MouseState::Up && event.button == MouseButton::Left {
-                    let mouse_pos = [event.x as f32, event.y as f32].into();
-                    self.update_selected_object(&mouse_pos);
-
-                    self.move_camera_with_mouse = false;
-                    self.last_mouse_pos = mouse_pos;
-                }
-                if event.state == MouseState::Move && self.move_camera_with_mouse {
-                    let mut rotation_angle = Vector3::zero();
-
-                    rotation_angle.x = event.y as f32 - self.last_mouse_pos.y;
-                    rotation_angle.y = self.last_mouse_pos.x - event.x as f32;
-
-                    self.camera.rotate(rotation_angle * 0.01);
-
-                    self.last_mouse_pos = [event.x as f32, event.y as f32].into();
-                }
+                self.on_mouse_event(event);
             } else if msg.type_id() == TypeId::of::<KeyEvent>() {
                 let event = msg.as_any().downcast_ref::<KeyEvent>().unwrap();
-
-                if event.code == Key::F1 && event.state == InputState::JustPressed {
-                    self.show_fps(!self.show_fps);
-                }
-
-                let mut movement = Vector3::zero();
-                if event.code == Key::W {
-                    movement.z += 1.;
-                } else if event.code == Key::S {
-                    movement.z -= 1.;
-                } else if event.code == Key::A {
-                    movement.x += 1.;
-                } else if event.code == Key::D {
-                    movement.x -= 1.;
-                }
-                self.camera.translate(movement);
+                self.on_key_event(event);
             } else if msg.type_id() == TypeId::of::<WindowEvent>() {
                 let event = msg.as_any().downcast_ref::<WindowEvent>().unwrap();
-                match *event {
-                    WindowEvent::SizeChanged(width, height) => {
-                        Screen::change_size(width, height);
-                        self.camera.set_projection(
-                            45.,
-                            Screen::get_size().x,
-                            Screen::get_size().y,
-                            0.1,
-                            1000.,
-                        );
-                        Gui::invalidate_all_widgets();
-                    }
-                    WindowEvent::DpiChanged(x, _y) => {
-                        Screen::change_scale_factor(x / DEFAULT_DPI);
-                        Gui::invalidate_all_widgets();
-                    }
-                    _ => {}
-                }
+                self.on_window_event(event);
             } else if msg.type_id() == TypeId::of::<WidgetEvent>() {
-                self.move_camera_with_mouse = false;
                 let event = msg.as_any().downcast_ref::<WidgetEvent>().unwrap();
-                if let WidgetEvent::Released(widget_uid, _mouse) = *event {
-                    self.global_messenger
-                        .write()
-                        .unwrap()
-                        .get_dispatcher()
-                        .write()
-                        .unwrap()
-                        .send(PropertiesEvent::GetProperties(widget_uid).as_boxed())
-                        .ok();
-
-                    if let Some(properties) = Gui::get()
-                        .write()
-                        .unwrap()
-                        .get_root_mut()
-                        .get_child_mut::<PropertiesPanel>(self.properties_id)
-                    {
-                        properties.reset();
-                        properties.add_string(
-                            "UID:",
-                            widget_uid.to_simple().to_string().as_str(),
-                            false,
-                        );
-                    }
-                }
+                self.on_widget_event(event);
             }
         });
         self
     }
+
+    fn on_nodes_event(&mut self, event: &NodesEvent) {
+        let NodesEvent::Create(widget_name) = event;
+        if let Some(graph) = Gui::get()
+            .read()
+            .unwrap()
+            .get_root()
+            .get_child_mut::<Graph>(self.graph_id)
+        {
+            let mut widget = self.nodes_registry.create_from_name(widget_name.clone());
+            widget
+                .get_global_messenger()
+                .write()
+                .unwrap()
+                .register_messagebox::<WidgetEvent>(widget.get_messagebox())
+                .register_messagebox::<MouseEvent>(widget.get_messagebox());
+
+            widget
+                .state_mut()
+                .set_draggable(true)
+                .set_selectable(true)
+                .set_horizontal_alignment(HorizontalAlignment::Center)
+                .set_vertical_alignment(VerticalAlignment::Center);
+            graph.add_child(widget);
+        }
+    }
+
+    fn on_dialog_event(&mut self, event: &DialogEvent) {
+        if let DialogEvent::Confirmed(_widget_id, requester_uid, filename) = event {
+            let mut should_create = false;
+            let mut should_load = false;
+            let mut should_save = false;
+            if let Some(menu) = Gui::get()
+                .read()
+                .unwrap()
+                .get_root()
+                .get_child_mut::<MainMenu>(self.main_menu_id)
+            {
+                should_create = menu.is_new_uid(*requester_uid);
+                should_load = menu.is_open_uid(*requester_uid);
+                should_save = menu.is_save_uid(*requester_uid);
+            }
+            if should_create {
+                println!("New file from {:?}", filename);
+                return;
+            }
+            let extension = filename.extension().unwrap().to_str().unwrap();
+            if should_load {
+                println!("Loading {:?}", filename);
+                if extension.contains("widget") {
+                    self.load_graph(filename.clone());
+                } else if extension.contains("object_data") {
+                    self.load_object(filename.as_path());
+                }
+            } else if should_save {
+                println!("Saving {:?}", filename);
+                if extension.contains("widget") {
+                    self.save_graph(filename.clone());
+                } else if extension.contains("object_data") {
+                }
+            }
+        }
+    }
+
+    fn on_mouse_event(&mut self, event: &MouseEvent) {
+        if event.state == MouseState::Down && event.button == MouseButton::Left {
+            self.move_camera_with_mouse = true;
+            self.last_mouse_pos = [event.x as f32, event.y as f32].into();
+        } else if event.state == MouseState::Up && event.button == MouseButton::Left {
+            let mouse_pos = [event.x as f32, event.y as f32].into();
+            self.update_selected_object(&mouse_pos);
+
+            self.move_camera_with_mouse = false;
+            self.last_mouse_pos = mouse_pos;
+        }
+        if event.state == MouseState::Move && self.move_camera_with_mouse {
+            let mut rotation_angle = Vector3::zero();
+
+            rotation_angle.x = event.y as f32 - self.last_mouse_pos.y;
+            rotation_angle.y = self.last_mouse_pos.x - event.x as f32;
+
+            self.camera.rotate(rotation_angle * 0.01);
+
+            self.last_mouse_pos = [event.x as f32, event.y as f32].into();
+        }
+    }
+
+    fn on_key_event(&mut self, event: &KeyEvent) {
+        if event.code == Key::F1 && event.state == InputState::JustPressed {
+            self.show_fps(!self.show_fps);
+        }
+        if event.code == Key::F2 && event.state == InputState::JustPressed {
+            println!("{}", SharedData::memory_report(&self.shared_data));
+        }
+        if event.code == Key::Control {
+            self.is_ctrl_pressed =
+                event.state == InputState::JustPressed || event.state == InputState::Pressed;
+        }
+        if self.is_ctrl_pressed && event.state == InputState::JustPressed {
+            if event.code == Key::Z {
+                self.history.push(EventsHistoryOperation::Undo);
+            } else if event.code == Key::Y {
+                self.history.push(EventsHistoryOperation::Redo);
+            }
+        }
+
+        let mut movement = Vector3::zero();
+        if event.code == Key::W {
+            movement.z += 1.;
+        } else if event.code == Key::S {
+            movement.z -= 1.;
+        } else if event.code == Key::A {
+            movement.x += 1.;
+        } else if event.code == Key::D {
+            movement.x -= 1.;
+        }
+        self.camera.translate(movement);
+    }
+
+    fn on_window_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::SizeChanged(width, height) => {
+                Screen::change_size(width, height);
+                self.camera.set_projection(
+                    45.,
+                    Screen::get_size().x,
+                    Screen::get_size().y,
+                    0.1,
+                    1000.,
+                );
+                Gui::invalidate_all_widgets();
+            }
+            WindowEvent::DpiChanged(x, _y) => {
+                Screen::change_scale_factor(x / DEFAULT_DPI);
+                Gui::invalidate_all_widgets();
+            }
+            _ => {}
+        }
+    }
+
+    fn on_widget_event(&mut self, event: &WidgetEvent) {
+        self.move_camera_with_mouse = false;
+        if let WidgetEvent::Released(widget_uid, _mouse) = *event {
+            self.send_event(PropertiesEvent::GetProperties(widget_uid).as_boxed());
+
+            if let Some(properties) = Gui::get()
+                .write()
+                .unwrap()
+                .get_root_mut()
+                .get_child_mut::<PropertiesPanel>(self.properties_id)
+            {
+                properties.reset();
+                properties.add_string("UID:", widget_uid.to_simple().to_string().as_str(), false);
+            }
+        }
+    }
 }
@@ -1,22 +1,54 @@
-use std::path::PathBuf;
+use std::{env, path::PathBuf};
 
 use nrg_binarizer::Binarizer;
 use nrg_core::*;
 use nrg_dynamic_library::library_filename;
 use nrg_editor::editor::Editor;
-use nrg_resources::{DATA_FOLDER, DATA_RAW_FOLDER};
+use nrg_resources::{DATA_FOLDER, DATA_PACK_FILE, DATA_RAW_FOLDER};
+
+// -cook [pack path] [root files relative to the data folder...]
+fn cook(binarizer: &Binarizer, args: &[String]) {
+    let pack_path = PathBuf::from(args.first().map_or(DATA_PACK_FILE, |a| a.as_str()));
+    let roots: Vec<PathBuf> = args.iter().skip(1).map(PathBuf::from).collect();
+    match binarizer.cook(pack_path.as_path(), &roots, env!("CARGO_PKG_VERSION")) {
+        Ok(manifest) => {
+            println!(
+                "Cooked {} files into {}",
+                manifest.files.len(),
+                pack_path.to_string_lossy()
+            );
+            for missing in manifest.missing.iter() {
+                eprintln!(
+                    "Missing {} referenced by {}",
+                    missing.path.to_string_lossy(),
+                    missing.referenced_by.to_string_lossy()
+                );
+            }
+        }
+        Err(e) => eprintln!("Unable to cook {}: {}", pack_path.to_string_lossy(), e),
+    }
+}
 
 fn main() {
     let mut app = App::new();
 
     let mut binarizer = Binarizer::new(app.get_global_messenger(), DATA_RAW_FOLDER, DATA_FOLDER);
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(|a| a.as_str()) == Some("-cook") {
+        cook(&binarizer, &args[1..]);
+        return;
+    }
+
     binarizer.start();
 
     let plugins = ["nrg_window"];
 
     for name in plugins.iter() {
         let path = PathBuf::from(library_filename(*name));
-        app.add_plugin(path);
+        if let Err(error) = app.add_plugin(path) {
+            eprintln!("{}", error);
+        }
     }
 
     let mut editor = Editor::default();
@@ -25,6 +57,10 @@ fn main() {
     loop {
         let can_continue = app.run_once();
 
+        for error in app.take_plugin_errors() {
+            eprintln!("{}", error);
+        }
+
         if !can_continue {
             break;
         }
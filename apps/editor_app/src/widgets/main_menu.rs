@@ -2,7 +2,7 @@ use std::{any::TypeId, path::PathBuf, process::Command};
 
 use nrg_gui::{
     implement_widget_with_custom_members, Button, DialogEvent, FolderDialog, InternalWidget, Menu,
-    RefcountedWidget, ScrollableItem, WidgetData, WidgetEvent,
+    RefcountedWidget, ScrollableItem, WidgetData, WidgetEvent, DIALOG_REQUESTER_ARG,
 };
 use nrg_math::{Vector2, Vector4};
 use nrg_messenger::Message;
@@ -37,6 +37,8 @@ pub struct MainMenu {
     nodes_list_id: Uid,
     #[serde(skip)]
     filename_dialog: Option<FolderDialog>,
+    #[serde(skip)]
+    bridge_args: Vec<String>,
 }
 implement_widget_with_custom_members!(MainMenu {
     menu: None,
@@ -49,7 +51,8 @@ implement_widget_with_custom_members!(MainMenu {
     nodes_id: INVALID_UID,
     nodes_add_id: INVALID_UID,
     nodes_list_id: INVALID_UID,
-    filename_dialog: None
+    filename_dialog: None,
+    bridge_args: Vec::new()
 });
 
 impl MainMenu {
@@ -62,6 +65,11 @@ impl MainMenu {
     pub fn get_size(&self) -> Vector2 {
         self.menu().state().get_size()
     }
+    // The content browser sends back its DialogEvent through the messenger bridge with these args
+    pub fn set_bridge_args(&mut self, args: Vec<String>) -> &mut Self {
+        self.bridge_args = args;
+        self
+    }
     pub fn is_new_uid(&self, entry_uid: Uid) -> bool {
         self.new_id == entry_uid
     }
@@ -212,24 +220,15 @@ impl InternalWidget for MainMenu {
             if let WidgetEvent::Pressed(widget_id, _mouse_in_px) = *event {
                 if self.new_id == widget_id {
                     println!("New content browser");
-                    let result = Command::new("nrg_content_browser")
+                    let mut command = Command::new("nrg_content_browser");
+                    command
                         .arg("New File")
                         .arg(PathBuf::from(DATA_RAW_FOLDER).to_str().unwrap())
-                        .output();
-                    match result {
-                        Ok(output) => {
-                            let string = String::from_utf8(output.stdout).unwrap();
-                            if let Some(pos) = string.find("[[[") {
-                                let (_, string) = string.split_at(pos + 3);
-                                if let Some(pos) = string.find("]]]") {
-                                    let (string, _) = string.split_at(pos);
-                                    println!("Output = {}", string);
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            println!("Failed to execute process");
-                        }
+                        .arg(DIALOG_REQUESTER_ARG)
+                        .arg(self.new_id.to_string())
+                        .args(&self.bridge_args);
+                    if command.spawn().is_err() {
+                        println!("Failed to execute process");
                     }
                 } else if self.open_id == widget_id && self.filename_dialog.is_none() {
                     self.filename_dialog = Some(FolderDialog::new(
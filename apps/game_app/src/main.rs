@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{env, path::PathBuf};
 
 use nrg_core::*;
 use nrg_dynamic_library::library_filename;
@@ -23,7 +23,27 @@ fn main() {
 
     for name in plugins.iter() {
         let path = PathBuf::from(library_filename(*name));
-        app.add_plugin(path);
+        if let Err(error) = app.add_plugin(path) {
+            eprintln!("{}", error);
+        }
+    }
+
+    let args: Vec<String> = env::args().collect();
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-record" => {
+                if let Some(path) = args.next() {
+                    app.start_recording(PathBuf::from(path));
+                }
+            }
+            "-replay" => {
+                if let Some(path) = args.next() {
+                    app.start_replay(PathBuf::from(path));
+                }
+            }
+            _ => {}
+        }
     }
 
     app.run();
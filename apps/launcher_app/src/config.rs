@@ -6,6 +6,8 @@ use std::path::PathBuf;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "nrg_serialize")]
 pub struct Config {
+    #[serde(default)]
+    version: u32,
     pub title: String,
     pub pos_x: u32,
     pub pos_y: u32,
@@ -20,6 +22,7 @@ pub struct Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: Self::VERSION,
             title: String::new(),
             pos_x: 0,
             pos_y: 0,
@@ -33,6 +36,15 @@ impl Default for Config {
     }
 }
 
+// Pipelines are versioned on their own and upgraded together with the config
+impl Versioned for Config {
+    const VERSION: u32 = 1;
+
+    fn migrate_nested(value: &mut SerializedValue) -> SerializeResult<()> {
+        migrate_all::<PipelineData>(value, "pipelines")
+    }
+}
+
 impl Data for Config {}
 impl ConfigBase for Config {
     fn get_filename(&self) -> &'static str {
@@ -15,8 +15,10 @@ use nrg_gui::{
 use nrg_math::Vector2;
 use nrg_messenger::{read_messages, Message, MessageChannel, MessengerRw};
 use nrg_platform::{WindowEvent, DEFAULT_DPI};
-use nrg_resources::{ConfigBase, DataTypeResource, FileResource, SharedDataRw};
-use nrg_serialize::{deserialize_from_file, Uid, INVALID_UID};
+use nrg_resources::{
+    read_versioned_file, ConfigBase, DataTypeResource, FileResource, SharedDataRw,
+};
+use nrg_serialize::{Uid, INVALID_UID};
 
 use crate::config::Config;
 
@@ -248,7 +250,10 @@ impl System for LauncherSystem {
     }
     fn init(&mut self) {
         let path = self.config.get_filepath();
-        deserialize_from_file(&mut self.config, path);
+        match read_versioned_file(path.as_path()) {
+            Ok(config) => self.config = config,
+            Err(e) => eprintln!("Unable to load config {}", e),
+        }
 
         self.window_init();
         self.load_pipelines();
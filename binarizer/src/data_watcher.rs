@@ -1,5 +1,6 @@
 use std::{
     fs::create_dir_all,
+    io,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -11,7 +12,9 @@ use std::{
 use crate::{ConfigCompiler, FontCompiler, GltfCompiler, ImageCompiler, ShaderCompiler};
 use nrg_messenger::MessengerRw;
 use nrg_platform::{FileEvent, FileWatcher};
-use nrg_resources::convert_from_local_path;
+use nrg_resources::{
+    convert_from_local_path, AssetMeta, CookManifest, Cooker, CONFIG_FOLDER, SCENE_FOLDER,
+};
 
 pub trait ExtensionHandler {
     fn on_changed(&mut self, path: &Path);
@@ -65,22 +68,11 @@ impl Binarizer {
     pub fn start(&mut self) {
         let mut binarizer = DataWatcher {
             filewatcher: FileWatcher::new(self.data_raw_folder.clone()),
-            handlers: Vec::new(),
+            handlers: create_handlers(&self.global_messenger),
             data_raw_folder: self.data_raw_folder.clone(),
             data_folder: self.data_folder.clone(),
         };
 
-        let shader_compiler = ShaderCompiler::new(self.global_messenger.clone());
-        let config_compiler = ConfigCompiler::new(self.global_messenger.clone());
-        let font_compiler = FontCompiler::new(self.global_messenger.clone());
-        let image_compiler = ImageCompiler::new(self.global_messenger.clone());
-        let gltf_compiler = GltfCompiler::new(self.global_messenger.clone());
-        binarizer.add_handler(config_compiler);
-        binarizer.add_handler(shader_compiler);
-        binarizer.add_handler(font_compiler);
-        binarizer.add_handler(image_compiler);
-        binarizer.add_handler(gltf_compiler);
-
         self.is_running.store(true, Ordering::SeqCst);
         let can_continue = self.is_running.clone();
         let builder = thread::Builder::new().name("Data Binarizer".to_string());
@@ -110,6 +102,72 @@ impl Binarizer {
             self.thread_handle = None;
         }
     }
+
+    // Binarizes the whole data_raw folder and packs every file reachable
+    // from the configs, the scenes and the given roots, relative to the data folder
+    pub fn cook(
+        &self,
+        pack_path: &Path,
+        roots: &[PathBuf],
+        version: &str,
+    ) -> io::Result<CookManifest> {
+        let mut handlers = create_handlers(&self.global_messenger);
+        binarize_folder(
+            &mut handlers,
+            self.data_raw_folder.as_path(),
+            self.data_raw_folder.as_path(),
+        );
+
+        let mut cooker = Cooker::new(self.data_folder.as_path(), version);
+        cooker
+            .add_folder_roots(Path::new(CONFIG_FOLDER))
+            .add_folder_roots(Path::new(SCENE_FOLDER));
+        for root in roots.iter() {
+            cooker.add_root(root.as_path());
+        }
+        cooker.cook(pack_path)
+    }
+}
+
+fn create_handlers(global_messenger: &MessengerRw) -> Vec<Box<dyn ExtensionHandler>> {
+    vec![
+        Box::new(ConfigCompiler::new(global_messenger.clone())),
+        Box::new(ShaderCompiler::new(global_messenger.clone())),
+        Box::new(FontCompiler::new(global_messenger.clone())),
+        Box::new(ImageCompiler::new(global_messenger.clone())),
+        Box::new(GltfCompiler::new(global_messenger.clone())),
+    ]
+}
+
+fn binarize_file(handlers: &mut [Box<dyn ExtensionHandler>], data_raw_folder: &Path, path: &Path) {
+    if AssetMeta::is_meta_file(path) {
+        return;
+    }
+    let absolute_path = convert_from_local_path(data_raw_folder, path);
+    //Every source asset gets its uuid the first time it is seen
+    AssetMeta::read_or_create(absolute_path.as_path());
+    for handler in handlers.iter_mut() {
+        handler.on_changed(absolute_path.as_path());
+    }
+}
+
+fn binarize_folder(
+    handlers: &mut [Box<dyn ExtensionHandler>],
+    data_raw_folder: &Path,
+    path: &Path,
+) {
+    if let Ok(dir) = std::fs::read_dir(path) {
+        dir.for_each(|entry| {
+            if let Ok(dir_entry) = entry {
+                let path = dir_entry.path();
+                if !path.is_dir() {
+                    binarize_file(handlers, data_raw_folder, path.as_path());
+                } else {
+                    binarize_folder(handlers, data_raw_folder, path.as_path());
+                }
+            }
+        });
+    }
 }
 
 impl DataWatcher {
@@ -134,25 +192,11 @@ impl DataWatcher {
     }
 
     fn binarize_file(&mut self, path: &Path) {
-        let absolute_path = convert_from_local_path(self.data_raw_folder.as_path(), path);
-        for handler in self.handlers.iter_mut() {
-            handler.on_changed(absolute_path.as_path());
-        }
+        binarize_file(&mut self.handlers, self.data_raw_folder.as_path(), path);
     }
 
     fn binarize_folder(&mut self, path: &Path) {
-        if let Ok(dir) = std::fs::read_dir(path) {
-            dir.for_each(|entry| {
-                if let Ok(dir_entry) = entry {
-                    let path = dir_entry.path();
-                    if !path.is_dir() {
-                        self.binarize_file(path.as_path());
-                    } else {
-                        self.binarize_folder(path.as_path());
-                    }
-                }
-            });
-        }
+        binarize_folder(&mut self.handlers, self.data_raw_folder.as_path(), path);
     }
 }
 
@@ -15,9 +15,11 @@ use gltf::{
 use nrg_graphics::{MaterialData, MeshData, VertexData};
 use nrg_math::{Vector2, Vector3, Vector4};
 use nrg_messenger::MessengerRw;
-use nrg_resources::{convert_in_local_path, DATA_FOLDER, DATA_RAW_FOLDER};
+use nrg_resources::{
+    convert_in_local_path, register_asset, AssetMeta, DATA_FOLDER, DATA_RAW_FOLDER,
+};
 use nrg_scene::ObjectData;
-use nrg_serialize::serialize_to_file;
+use nrg_serialize::{serialize_to_file_with_format, SerializationFormat};
 
 const GLTF_EXTENSION: &str = "gltf";
 const MESH_DATA_EXTENSION: &str = "mesh_data";
@@ -230,14 +232,21 @@ impl GltfCompiler {
         let mut mesh_data = MeshData::default();
         mesh_data.append_mesh(vertices.as_slice(), indices.as_slice());
 
-        Self::create_file(path, &mesh_data, mesh_name, MESH_DATA_EXTENSION)
+        //Meshes are big and never edited by hand
+        Self::create_file(
+            path,
+            &mesh_data,
+            mesh_name,
+            MESH_DATA_EXTENSION,
+            SerializationFormat::Binary,
+        )
     }
     fn process_material_data(path: &Path, primitive: &Primitive, mesh_path: PathBuf) -> PathBuf {
         let mut material_data = MaterialData::default();
 
-        let mesh_path =
-            convert_in_local_path(mesh_path.as_path(), PathBuf::from(DATA_FOLDER).as_path());
-        material_data.meshes.push(mesh_path);
+        material_data
+            .meshes
+            .push(Self::asset_reference(mesh_path.as_path()));
 
         let material = primitive.material().pbr_metallic_roughness();
         material_data.diffuse_color = material.base_color_factor().into();
@@ -250,10 +259,14 @@ impl GltfCompiler {
                     if let Some(parent_folder) = path.parent() {
                         let parent_path = parent_folder.to_str().unwrap().to_string();
                         let filepath = PathBuf::from(parent_path).join(uri);
-                        let path = convert_in_local_path(
-                            filepath.as_path(),
-                            PathBuf::from(DATA_RAW_FOLDER).as_path(),
-                        );
+                        let path = if filepath.is_file() {
+                            AssetMeta::read_or_create(filepath.as_path()).asset_path()
+                        } else {
+                            convert_in_local_path(
+                                filepath.as_path(),
+                                PathBuf::from(DATA_RAW_FOLDER).as_path(),
+                            )
+                        };
                         material_data.textures.push(path);
                     }
                 }
@@ -269,6 +282,7 @@ impl GltfCompiler {
             &material_data,
             primitive.material().name().unwrap_or_else(|| name.as_str()),
             MATERIAL_DATA_EXTENSION,
+            SerializationFormat::Json,
         )
     }
 
@@ -286,11 +300,7 @@ impl GltfCompiler {
                     &primitive,
                 );
                 let material_path = Self::process_material_data(path, &primitive, mesh_path);
-                let material_path = convert_in_local_path(
-                    material_path.as_path(),
-                    PathBuf::from(DATA_FOLDER).as_path(),
-                );
-                object_data.material = material_path;
+                object_data.material = Self::asset_reference(material_path.as_path());
             }
         }
 
@@ -298,12 +308,18 @@ impl GltfCompiler {
             let name = format!("Node_{}", child.index());
             let object_path =
                 Self::process_node(path, &child, child.name().unwrap_or_else(|| name.as_str()));
-            let object_path =
-                convert_in_local_path(object_path.as_path(), PathBuf::from(DATA_FOLDER).as_path());
-            object_data.children.push(object_path);
+            object_data
+                .children
+                .push(Self::asset_reference(object_path.as_path()));
         }
 
-        Self::create_file(path, &object_data, node_name, OBJECT_DATA_EXTENSION)
+        Self::create_file(
+            path,
+            &object_data,
+            node_name,
+            OBJECT_DATA_EXTENSION,
+            SerializationFormat::Json,
+        )
     }
 
     fn process_path(path: &Path) {
@@ -334,7 +350,13 @@ impl GltfCompiler {
         }
     }
 
-    fn create_file<T>(path: &Path, mesh_data: &T, new_name: &str, new_extension: &str) -> PathBuf
+    fn create_file<T>(
+        path: &Path,
+        mesh_data: &T,
+        new_name: &str,
+        new_extension: &str,
+        format: SerializationFormat,
+    ) -> PathBuf
     where
         T: nrg_serialize::Serialize,
     {
@@ -363,10 +385,20 @@ impl GltfCompiler {
         }
         if need_to_binarize(path, new_path.as_path()) {
             println!("Serializing {:?}", new_path);
-            serialize_to_file(mesh_data, new_path.clone());
+            serialize_to_file_with_format(mesh_data, new_path.clone(), format);
         }
+        let meta = AssetMeta::read_or_create(path).derive(destination_ext.as_str());
+        register_asset(new_path.as_path(), &meta);
         new_path
     }
+
+    // Generated files reference each other by uuid so that they can be moved around
+    fn asset_reference(path: &Path) -> PathBuf {
+        match AssetMeta::read(path) {
+            Some(meta) => meta.asset_path(),
+            None => convert_in_local_path(path, PathBuf::from(DATA_FOLDER).as_path()),
+        }
+    }
 }
 
 impl ExtensionHandler for GltfCompiler {
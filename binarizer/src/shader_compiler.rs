@@ -5,7 +5,7 @@ use std::{
     process::Command,
 };
 
-use crate::{need_to_binarize, send_reloaded_event, ExtensionHandler};
+use crate::{copy_asset_meta, need_to_binarize, send_reloaded_event, ExtensionHandler};
 use nrg_messenger::MessengerRw;
 use nrg_resources::{DATA_FOLDER, DATA_RAW_FOLDER};
 
@@ -117,11 +117,13 @@ impl ShaderCompiler {
                     .spawn()
                     .is_ok();
                 if result {
+                    copy_asset_meta(path, new_path.as_path());
                     send_reloaded_event(&self.global_messenger, new_path.as_path());
                 }
             }
             return converted;
         }
+        copy_asset_meta(path, new_path.as_path());
         true
     }
 }
@@ -4,7 +4,7 @@ use std::{
 };
 
 use nrg_messenger::{Message, MessengerRw};
-use nrg_resources::{ResourceEvent, DATA_FOLDER, DATA_RAW_FOLDER};
+use nrg_resources::{register_asset, AssetMeta, ResourceEvent, DATA_FOLDER, DATA_RAW_FOLDER};
 
 pub fn need_to_binarize(original_path: &Path, new_path: &Path) -> bool {
     let mut need_copy = false;
@@ -44,13 +44,22 @@ pub fn copy_into_data_folder(global_messenger: &MessengerRw, path: &Path) -> boo
     if need_to_binarize(path, new_path.as_path()) {
         let result = copy(path, new_path.as_path());
         if result.is_ok() {
+            copy_asset_meta(path, new_path.as_path());
             send_reloaded_event(global_messenger, new_path.as_path());
             return true;
         }
+    } else {
+        copy_asset_meta(path, new_path.as_path());
     }
     false
 }
 
+// Compiled files keep the uuid of their source so that references by uuid survive renames
+pub fn copy_asset_meta(original_path: &Path, new_path: &Path) {
+    let meta = AssetMeta::read_or_create(original_path);
+    register_asset(new_path, &meta);
+}
+
 pub fn send_reloaded_event(messenger: &MessengerRw, new_path: &Path) {
     let dispatcher = messenger.read().unwrap().get_dispatcher();
     dispatcher
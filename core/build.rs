@@ -1,6 +1,7 @@
 use std::{
     env, fs,
     path::{Path, PathBuf},
+    process::Command,
     time::{SystemTime, UNIX_EPOCH},
 };
 fn copy_all_files_with_extension(src_path: PathBuf, target_path: PathBuf, extension: &str) {
@@ -30,7 +31,7 @@ fn copy_all_files_with_extension(src_path: PathBuf, target_path: PathBuf, extens
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir)
-        .join("..\\..\\..\\")
+        .join("../../../")
         .canonicalize()
         .unwrap();
     let mut deps_path = Path::new(&out_dir).join("deps");
@@ -43,4 +44,18 @@ fn main() {
 
     copy_all_files_with_extension(deps_path, deps_build_path, "pdb");
     copy_all_files_with_extension(out_dir, in_use_build_path, "pdb");
+
+    // Plugins built by another compiler or with another profile are not abi compatible
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=NRG_RUSTC_VERSION={}", rustc_version.trim());
+    println!(
+        "cargo:rustc-env=NRG_BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
 }
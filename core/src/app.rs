@@ -2,19 +2,21 @@ use std::{
     any::TypeId,
     collections::HashMap,
     path::PathBuf,
-    sync::{
-        mpsc::{channel, Receiver},
-        Arc, Mutex, RwLock,
-    },
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, Instant},
 };
 
-use nrg_messenger::MessengerRw;
+use nrg_messenger::{Message, MessengerRw};
 use nrg_platform::{InputState, Key, KeyEvent, WindowEvent};
-use nrg_resources::SharedDataRw;
+use nrg_resources::{ResourceEvent, ResourceRef, SharedData, SharedDataRw};
 
-use crate::{Job, JobHandler, JobHandlerRw, Phase, PluginId, PluginManager, Scheduler, Worker};
+use crate::{
+    JobHandler, JobHandlerRw, JobQueue, MessageRecorder, Phase, PluginError, PluginId,
+    PluginManager, PluginResult, Scheduler, Time, Worker,
+};
 
-const NUM_WORKER_THREADS: usize = 5;
+const MIN_WORKER_THREADS: usize = 2;
 
 pub struct App {
     is_profiling: bool,
@@ -25,7 +27,14 @@ pub struct App {
     scheduler: Scheduler,
     workers: HashMap<String, Worker>,
     job_handler: Arc<RwLock<JobHandler>>,
-    receiver: Arc<Mutex<Receiver<Job>>>,
+    job_queue: Arc<JobQueue>,
+    num_worker_threads: usize,
+    time: ResourceRef<Time>,
+    frame_rate_limit: Option<u32>,
+    frame_start: Instant,
+    recorder: MessageRecorder,
+    fixed_frame_delta: Option<f32>,
+    plugin_errors: Vec<PluginError>,
 }
 
 impl Default for App {
@@ -36,6 +45,7 @@ impl Default for App {
 
 impl Drop for App {
     fn drop(&mut self) {
+        self.recorder.stop_recording();
         self.stop_worker_threads();
 
         if self.is_profiling {
@@ -51,9 +61,28 @@ impl Drop for App {
 
 impl App {
     pub fn new() -> Self {
+        //Leave one core to the main thread
+        let num_cores = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self::new_with_worker_threads(num_cores.saturating_sub(1))
+    }
+
+    // No window nor renderer is created unless a plugin does it
+    // and every frame advances the Time of a fixed delta to be deterministic
+    pub fn new_headless() -> Self {
+        let mut app = Self::new();
+        let fixed_delta = app.time.resource().unwrap().get().get_fixed_delta();
+        app.set_fixed_frame_delta(Some(fixed_delta));
+        app
+    }
+
+    pub fn new_with_worker_threads(num_worker_threads: usize) -> Self {
         nrg_profiler::create_profiler!();
 
-        let (sender, receiver) = channel();
+        let job_queue = Arc::new(JobQueue::default());
+        let shared_data = SharedDataRw::default();
+        let time = SharedData::add_resource(&shared_data, Time::default());
 
         let mut app = Self {
             is_enabled: true,
@@ -61,9 +90,16 @@ impl App {
             scheduler: Scheduler::new(),
             plugin_manager: PluginManager::new(),
             workers: HashMap::new(),
-            job_handler: JobHandler::new(sender),
-            receiver: Arc::new(Mutex::new(receiver)),
-            shared_data: SharedDataRw::default(),
+            job_handler: JobHandler::new(job_queue.clone()),
+            job_queue,
+            num_worker_threads: num_worker_threads.max(MIN_WORKER_THREADS),
+            time,
+            frame_rate_limit: None,
+            frame_start: Instant::now(),
+            recorder: MessageRecorder::default(),
+            fixed_frame_delta: None,
+            plugin_errors: Vec::new(),
+            shared_data,
             global_messenger: MessengerRw::default(),
         };
 
@@ -73,7 +109,7 @@ impl App {
     }
 
     fn setup_worker_threads(&mut self) {
-        for i in 1..NUM_WORKER_THREADS + 1 {
+        for i in 1..self.num_worker_threads + 1 {
             self.add_worker(format!("Worker{}", i).as_str());
         }
     }
@@ -85,27 +121,75 @@ impl App {
     }
 
     fn update_plugins(&mut self, plugins_to_remove: Vec<PluginId>, reload: bool) {
+        //Plugins depending on the removed ones have to go too, and before them
+        let plugins_to_remove = self.plugin_manager.compute_unload_order(&plugins_to_remove);
+        let mut plugins_to_reload = Vec::new();
         for id in plugins_to_remove.iter() {
-            if let Some(plugin_data) = self.plugin_manager.remove_plugin(id) {
-                let lib_path = plugin_data.original_path.clone();
-                PluginManager::clear_plugin_data(plugin_data, self);
+            if let Some(mut plugin_data) = self.plugin_manager.remove_plugin(id) {
                 if reload {
-                    let reloaded_plugin_data = PluginManager::create_plugin_data(lib_path, self);
-                    self.plugin_manager.add_plugin(reloaded_plugin_data);
+                    if let Some(state) = PluginManager::save_plugin_state(&mut plugin_data, self) {
+                        self.plugin_manager
+                            .store_plugin_state(&plugin_data.manifest.name, state);
+                    }
                 }
+                plugins_to_reload.push(plugin_data.original_path.clone());
+                PluginManager::clear_plugin_data(plugin_data, self);
             }
         }
+        if reload {
+            for lib_path in plugins_to_reload.into_iter().rev() {
+                if let Err(error) = self.add_plugin(lib_path) {
+                    self.plugin_errors.push(error);
+                }
+            }
+        }
+    }
+
+    fn prepare_pending_plugins(&mut self) {
+        while let Some(mut plugin_data) = self.plugin_manager.take_next_ready_plugin() {
+            PluginManager::prepare_plugin_data(&mut plugin_data, self);
+            if let Some(state) = self
+                .plugin_manager
+                .take_plugin_state(&plugin_data.manifest.name)
+            {
+                PluginManager::restore_plugin_state(&mut plugin_data, &state, self);
+            }
+            self.plugin_manager.add_plugin(plugin_data);
+        }
+    }
+
+    fn refuse_pending_plugins(&mut self) {
+        for plugin_data in self.plugin_manager.take_pending_plugins() {
+            self.plugin_manager
+                .take_plugin_state(&plugin_data.manifest.name);
+            let dependencies = self
+                .plugin_manager
+                .get_unmet_dependencies(&plugin_data.manifest)
+                .into_iter()
+                .cloned()
+                .collect();
+            self.plugin_errors.push(PluginError::UnmetDependencies {
+                name: plugin_data.manifest.name.clone(),
+                dependencies,
+            });
+            PluginManager::clear_plugin_data(plugin_data, self);
+        }
     }
 
     fn update_events(&mut self) {
         nrg_profiler::scoped_profile!("app::update_events");
 
+        self.recorder.replay_frame(&self.global_messenger);
+
         let mut is_profiling = self.is_profiling;
         let mut is_enabled = self.is_enabled;
+        let mut files_to_reload = Vec::new();
+        let recorder = &mut self.recorder;
         self.global_messenger
             .read()
             .unwrap()
             .process_messages(|msg| {
+                recorder.record(msg);
                 if msg.type_id() == TypeId::of::<KeyEvent>() {
                     let e = msg.as_any().downcast_ref::<KeyEvent>().unwrap();
                     if e.code == Key::F9 && e.state == InputState::JustPressed {
@@ -129,8 +213,14 @@ impl App {
                         }
                         _ => {}
                     }
+                } else if msg.type_id() == TypeId::of::<ResourceEvent>() {
+                    let e = msg.as_any().downcast_ref::<ResourceEvent>().unwrap();
+                    if let ResourceEvent::Reload(path) = e {
+                        files_to_reload.push(path.clone());
+                    }
                 }
             });
+        self.reload_files(files_to_reload);
         self.is_profiling = is_profiling;
         if self.is_enabled && !is_enabled {
             self.stop_worker_threads();
@@ -140,27 +230,96 @@ impl App {
         self.is_enabled = is_enabled;
     }
 
+    // Resources depending on the reloaded ones are rebuilt by SharedData too:
+    // all of them are then notified with a ResourceEvent::Changed
+    fn reload_files(&mut self, files_to_reload: Vec<PathBuf>) {
+        let dispatcher = self.global_messenger.read().unwrap().get_dispatcher();
+        for path in files_to_reload {
+            for resource_id in SharedData::reload_file(&self.shared_data, path.as_path()) {
+                dispatcher
+                    .write()
+                    .unwrap()
+                    .send(ResourceEvent::Changed(resource_id).as_boxed())
+                    .ok();
+            }
+        }
+    }
+
     pub fn run_once(&mut self) -> bool {
         nrg_profiler::scoped_profile!("app::run_frame");
 
+        self.frame_start = Instant::now();
+        //Plugins still waiting for their dependencies at this point will never be prepared
+        self.refuse_pending_plugins();
+
+        let time = {
+            let time = self.time.resource().unwrap();
+            let mut time = time.get_mut();
+            if let Some(delta) = self.recorder.get_replay_delta().or(self.fixed_frame_delta) {
+                time.update_with_delta(delta).clone()
+            } else {
+                time.update().clone()
+            }
+        };
+        self.recorder.begin_frame(&time);
+
         let can_continue = self
             .scheduler
-            .run_once(self.is_enabled, self.get_job_handler());
+            .run_once(self.is_enabled, self.get_job_handler(), &time);
 
         if !self.is_enabled {
             let plugins_to_remove = self.plugin_manager.update();
             self.update_plugins(plugins_to_remove, true);
-        } else {
-            self.job_handler.read().unwrap().clear_pending_jobs();
         }
 
         self.update_events();
 
         self.shared_data.write().unwrap().flush_resources();
 
+        self.wait_frame_rate_limit();
+
         can_continue
     }
 
+    fn wait_frame_rate_limit(&self) {
+        if let Some(frame_rate) = self.frame_rate_limit {
+            let frame_duration = Duration::from_secs_f32(1. / frame_rate.max(1) as f32);
+            let elapsed = self.frame_start.elapsed();
+            if elapsed < frame_duration {
+                thread::sleep(frame_duration - elapsed);
+            }
+        }
+    }
+
+    pub fn start_recording(&mut self, filepath: PathBuf) -> &mut Self {
+        self.recorder.start_recording(filepath);
+        self
+    }
+
+    pub fn stop_recording(&mut self) -> &mut Self {
+        self.recorder.stop_recording();
+        self
+    }
+
+    pub fn start_replay(&mut self, filepath: PathBuf) -> &mut Self {
+        self.recorder.start_replay(filepath, &self.global_messenger);
+        self
+    }
+
+    pub fn stop_replay(&mut self) -> &mut Self {
+        self.recorder.stop_replay(&self.global_messenger);
+        self
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.recorder.is_replaying()
+    }
+
+    pub fn set_frame_rate_limit(&mut self, frame_rate: Option<u32>) -> &mut Self {
+        self.frame_rate_limit = frame_rate;
+        self
+    }
+
     pub fn run(&mut self) {
         loop {
             let can_continue = self.run_once();
@@ -171,22 +330,67 @@ impl App {
         }
     }
 
-    pub fn add_plugin(&mut self, lib_path: PathBuf) {
-        let plugin_data = PluginManager::create_plugin_data(lib_path, self);
-        self.plugin_manager.add_plugin(plugin_data);
+    pub fn run_frames(&mut self, num_frames: u64) -> bool {
+        for _ in 0..num_frames {
+            if !self.run_once() {
+                return false;
+            }
+        }
+        true
     }
 
-    pub fn remove_plugin(&mut self, plugin_id: &PluginId) {
-        if let Some(plugin_data) = self.plugin_manager.remove_plugin(plugin_id) {
+    pub fn run_until<F>(&mut self, mut condition: F, max_frames: u64) -> bool
+    where
+        F: FnMut(&mut App) -> bool,
+    {
+        for _ in 0..max_frames {
+            if condition(self) {
+                return true;
+            }
+            if !self.run_once() {
+                break;
+            }
+        }
+        condition(self)
+    }
+
+    pub fn set_fixed_frame_delta(&mut self, delta: Option<f32>) -> &mut Self {
+        self.fixed_frame_delta = delta;
+        self
+    }
+
+    // Plugins are prepared as soon as all their dependencies are,
+    // whatever the order they are added in
+    pub fn add_plugin(&mut self, lib_path: PathBuf) -> PluginResult<PluginId> {
+        let plugin_data = PluginManager::create_plugin_data(lib_path)?;
+        if self
+            .plugin_manager
+            .is_plugin_loaded(&plugin_data.manifest.name)
+        {
+            let name = plugin_data.manifest.name.clone();
             PluginManager::clear_plugin_data(plugin_data, self);
+            return Err(PluginError::AlreadyLoaded(name));
         }
+        let plugin_id = plugin_data.id();
+        self.plugin_manager.add_pending_plugin(plugin_data);
+        self.prepare_pending_plugins();
+        Ok(plugin_id)
+    }
+
+    // Errors of the plugins that failed while reloading or resolving their dependencies
+    pub fn take_plugin_errors(&mut self) -> Vec<PluginError> {
+        self.plugin_errors.drain(..).collect()
+    }
+
+    pub fn remove_plugin(&mut self, plugin_id: &PluginId) {
+        self.update_plugins(vec![*plugin_id], false);
     }
     fn add_worker(&mut self, name: &str) -> &mut Worker {
         let key = String::from(name);
         let job_handler = self.get_job_handler();
         let w = self.workers.entry(key).or_insert_with(Worker::default);
         if !w.is_started() {
-            w.start(name, job_handler, self.receiver.clone());
+            w.start(name, job_handler, self.job_queue.clone(), self.time.clone());
         }
         w
     }
@@ -195,6 +399,12 @@ impl App {
         self.workers.get_mut(&key).unwrap()
     }
 
+    pub fn get_num_worker_threads(&self) -> usize {
+        self.num_worker_threads
+    }
+    pub fn get_time(&self) -> ResourceRef<Time> {
+        self.time.clone()
+    }
     pub fn get_job_handler(&self) -> JobHandlerRw {
         self.job_handler.clone()
     }
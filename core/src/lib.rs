@@ -2,10 +2,19 @@
 #![warn(clippy::all)]
 
 pub use crate::app::*;
+pub use crate::loader::*;
 pub use crate::plugins::*;
+pub use crate::recorder::*;
 pub use crate::schedule::*;
+pub use crate::time::*;
 
 pub mod app;
+pub mod loader;
 
 pub mod plugins;
+pub mod recorder;
 pub mod schedule;
+pub mod time;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use nrg_messenger::MessengerRw;
+use nrg_resources::{
+    Deserializable, FileResource, ResourceData, ResourceLoad, ResourceLoadRef, ResourceRef,
+    SerializableResource, SharedDataRw,
+};
+
+use crate::{JobHandle, JobHandlerRw, JobPriority};
+
+// Loads can last several frames so they run as background jobs:
+// the returned job handle allows to wait for the load to end
+fn load_async<T, F>(
+    job_handler: &JobHandlerRw,
+    global_messenger: &MessengerRw,
+    filepath: &Path,
+    create_fn: F,
+) -> (ResourceLoadRef<T>, JobHandle)
+where
+    T: ResourceData,
+    F: FnOnce() -> Result<ResourceRef<T>, String> + Send + Sync + 'static,
+{
+    let load = ResourceLoad::<T>::new(filepath);
+    let dispatcher = global_messenger.read().unwrap().get_dispatcher();
+    let job_load = load.clone();
+    let job_name = format!("Load {}", filepath.to_string_lossy());
+    let job = job_handler.write().unwrap().add_job_with_priority(
+        job_name.as_str(),
+        JobPriority::Background,
+        move || {
+            job_load.load(create_fn, &dispatcher);
+        },
+    );
+    (load, job)
+}
+
+pub trait SerializableResourceAsync: SerializableResource {
+    fn create_from_file_async(
+        shared_data: &SharedDataRw,
+        job_handler: &JobHandlerRw,
+        global_messenger: &MessengerRw,
+        filepath: &Path,
+    ) -> (ResourceLoadRef<Self>, JobHandle)
+    where
+        Self: Sized,
+        Self::DataType: Deserializable,
+    {
+        let shared_data = shared_data.clone();
+        let path = filepath.to_path_buf();
+        load_async(job_handler, global_messenger, filepath, move || {
+            Self::try_create_from_file(&shared_data, path.as_path()).map_err(|e| e.to_string())
+        })
+    }
+}
+
+impl<T> SerializableResourceAsync for T where T: SerializableResource {}
+
+pub trait FileResourceAsync: FileResource {
+    fn create_from_file_async(
+        shared_data: &SharedDataRw,
+        job_handler: &JobHandlerRw,
+        global_messenger: &MessengerRw,
+        filepath: &Path,
+    ) -> (ResourceLoadRef<Self>, JobHandle)
+    where
+        Self: Sized,
+    {
+        let shared_data = shared_data.clone();
+        let path = filepath.to_path_buf();
+        load_async(job_handler, global_messenger, filepath, move || {
+            Self::try_create_from_file(&shared_data, path.as_path()).map_err(|e| e.to_string())
+        })
+    }
+}
+
+impl<T> FileResourceAsync for T where T: FileResource {}
@@ -0,0 +1,80 @@
+use std::fmt;
+
+use crate::PluginVersion;
+
+pub const GET_PLUGIN_ABI_FUNCTION_NAME: &str = "get_plugin_abi";
+pub type PfnGetPluginAbi = ::std::option::Option<unsafe extern "C" fn() -> PluginAbi>;
+
+// To be increased every time Plugin, PluginHolder or PluginManifest change their layout
+pub const PLUGIN_ABI_VERSION: u32 = 3;
+const ABI_STRING_LENGTH: usize = 64;
+
+// Fixed size string to keep PluginAbi returnable through the C abi
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct AbiString([u8; ABI_STRING_LENGTH]);
+
+impl AbiString {
+    pub fn new(string: &str) -> Self {
+        let mut bytes = [0; ABI_STRING_LENGTH];
+        let len = string.len().min(ABI_STRING_LENGTH);
+        bytes[..len].copy_from_slice(&string.as_bytes()[..len]);
+        Self(bytes)
+    }
+    pub fn as_str(&self) -> &str {
+        let len = self
+            .0
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(ABI_STRING_LENGTH);
+        std::str::from_utf8(&self.0[..len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for AbiString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for AbiString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PluginAbi {
+    pub abi_version: u32,
+    pub core_version: PluginVersion,
+    pub rustc_version: AbiString,
+    pub profile: AbiString,
+}
+
+impl PluginAbi {
+    // Being inlined it gives back the values nrg_core had when the caller has been built
+    #[inline(always)]
+    pub fn current() -> Self {
+        Self {
+            abi_version: PLUGIN_ABI_VERSION,
+            core_version: PluginVersion::parse(env!("CARGO_PKG_VERSION")).unwrap_or_default(),
+            rustc_version: AbiString::new(env!("NRG_RUSTC_VERSION")),
+            profile: AbiString::new(env!("NRG_BUILD_PROFILE")),
+        }
+    }
+
+    pub fn is_compatible_with(&self, other: &PluginAbi) -> bool {
+        self == other
+    }
+}
+
+impl fmt::Display for PluginAbi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "abi {} - nrg_core {} - {} {}",
+            self.abi_version, self.core_version, self.rustc_version, self.profile
+        )
+    }
+}
@@ -0,0 +1,73 @@
+use std::{error::Error, fmt, path::PathBuf};
+
+use crate::{PluginAbi, PluginDependency};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginError {
+    NotFound(PathBuf),
+    LoadFailed {
+        path: PathBuf,
+        error: String,
+    },
+    MissingSymbol {
+        path: PathBuf,
+        symbol: String,
+    },
+    AbiMismatch {
+        path: PathBuf,
+        expected: PluginAbi,
+        found: PluginAbi,
+    },
+    AlreadyLoaded(String),
+    UnmetDependencies {
+        name: String,
+        dependencies: Vec<PluginDependency>,
+    },
+}
+
+pub type PluginResult<T> = Result<T, PluginError>;
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::NotFound(path) => {
+                write!(f, "Unable to find plugin {}", path.to_string_lossy())
+            }
+            PluginError::LoadFailed { path, error } => write!(
+                f,
+                "Unable to load plugin {} - {}",
+                path.to_string_lossy(),
+                error
+            ),
+            PluginError::MissingSymbol { path, symbol } => write!(
+                f,
+                "Plugin {} does not export {}",
+                path.to_string_lossy(),
+                symbol
+            ),
+            PluginError::AbiMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Plugin {} has been built with {} while {} is required",
+                path.to_string_lossy(),
+                found,
+                expected
+            ),
+            PluginError::AlreadyLoaded(name) => {
+                write!(f, "A plugin named {} is already loaded", name)
+            }
+            PluginError::UnmetDependencies { name, dependencies } => {
+                write!(f, "Plugin {} is missing dependencies:", name)?;
+                for d in dependencies.iter() {
+                    write!(f, " {} {}", d.name, d.requirement)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for PluginError {}
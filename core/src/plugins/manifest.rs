@@ -0,0 +1,180 @@
+use std::{cmp::Ordering, fmt};
+
+type PfnCreateRequirement = fn(PluginVersion) -> VersionRequirement;
+
+pub const GET_PLUGIN_MANIFEST_FUNCTION_NAME: &str = "get_plugin_manifest";
+// The manifest holds Rust types so it's returned with the Rust abi, checked through PluginAbi
+pub type PfnGetPluginManifest = ::std::option::Option<unsafe fn() -> PluginManifest>;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PluginVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl PluginVersion {
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    // Accepts "1", "1.2" or "1.2.3" where missing numbers are 0
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut numbers = [0u32; 3];
+        let mut count = 0;
+        for (i, s) in version.trim().split('.').enumerate() {
+            if i >= numbers.len() {
+                return None;
+            }
+            numbers[i] = s.parse::<u32>().ok()?;
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+        Some(Self::new(numbers[0], numbers[1], numbers[2]))
+    }
+}
+
+impl PartialOrd for PluginVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PluginVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.major
+            .cmp(&other.major)
+            .then(self.minor.cmp(&other.minor))
+            .then(self.patch.cmp(&other.patch))
+    }
+}
+
+impl fmt::Display for PluginVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VersionRequirement {
+    Any,
+    Exact(PluginVersion),
+    Greater(PluginVersion),
+    GreaterOrEqual(PluginVersion),
+    Less(PluginVersion),
+    LessOrEqual(PluginVersion),
+    // Same as cargo: compatible versions do not change the left-most non-zero number
+    Compatible(PluginVersion),
+}
+
+impl VersionRequirement {
+    // Accepts "*", "=1.0.0", ">1.0", ">=1.0", "<2", "<=1.2.3", "^1.2" or "1.2" (same as "^1.2")
+    pub fn parse(requirement: &str) -> Option<Self> {
+        let requirement = requirement.trim();
+        if requirement == "*" || requirement.is_empty() {
+            return Some(VersionRequirement::Any);
+        }
+        let operators: [(&str, PfnCreateRequirement); 6] = [
+            (">=", VersionRequirement::GreaterOrEqual),
+            ("<=", VersionRequirement::LessOrEqual),
+            (">", VersionRequirement::Greater),
+            ("<", VersionRequirement::Less),
+            ("=", VersionRequirement::Exact),
+            ("^", VersionRequirement::Compatible),
+        ];
+        for (operator, create_fn) in operators.iter() {
+            if let Some(version) = requirement.strip_prefix(operator) {
+                return PluginVersion::parse(version).map(create_fn);
+            }
+        }
+        PluginVersion::parse(requirement).map(VersionRequirement::Compatible)
+    }
+
+    pub fn matches(&self, version: &PluginVersion) -> bool {
+        match self {
+            VersionRequirement::Any => true,
+            VersionRequirement::Exact(v) => version == v,
+            VersionRequirement::Greater(v) => version > v,
+            VersionRequirement::GreaterOrEqual(v) => version >= v,
+            VersionRequirement::Less(v) => version < v,
+            VersionRequirement::LessOrEqual(v) => version <= v,
+            VersionRequirement::Compatible(v) => {
+                if version < v {
+                    false
+                } else if v.major > 0 {
+                    version.major == v.major
+                } else if v.minor > 0 {
+                    version.major == 0 && version.minor == v.minor
+                } else {
+                    version.major == 0 && version.minor == 0 && version.patch == v.patch
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for VersionRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionRequirement::Any => write!(f, "*"),
+            VersionRequirement::Exact(v) => write!(f, "={}", v),
+            VersionRequirement::Greater(v) => write!(f, ">{}", v),
+            VersionRequirement::GreaterOrEqual(v) => write!(f, ">={}", v),
+            VersionRequirement::Less(v) => write!(f, "<{}", v),
+            VersionRequirement::LessOrEqual(v) => write!(f, "<={}", v),
+            VersionRequirement::Compatible(v) => write!(f, "^{}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PluginDependency {
+    pub name: String,
+    pub requirement: VersionRequirement,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: PluginVersion,
+    pub dependencies: Vec<PluginDependency>,
+}
+
+impl PluginManifest {
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: String::from(name),
+            version: PluginVersion::parse(version).unwrap_or_else(|| {
+                eprintln!("Invalid version {} for plugin {}", version, name);
+                PluginVersion::default()
+            }),
+            dependencies: Vec::new(),
+        }
+    }
+
+    pub fn depends_on(&mut self, name: &str, requirement: &str) -> &mut Self {
+        let requirement = VersionRequirement::parse(requirement).unwrap_or_else(|| {
+            eprintln!(
+                "Invalid version requirement {} for dependency {} of plugin {}",
+                requirement, name, self.name
+            );
+            VersionRequirement::Any
+        });
+        self.dependencies.push(PluginDependency {
+            name: String::from(name),
+            requirement,
+        });
+        self
+    }
+
+    pub fn is_dependent_on(&self, name: &str) -> bool {
+        self.dependencies.iter().any(|d| d.name == name)
+    }
+}
@@ -1,5 +1,11 @@
+pub use self::abi::*;
+pub use self::error::*;
+pub use self::manifest::*;
 pub use self::plugin::*;
 pub use self::plugin_manager::*;
 
+pub mod abi;
+pub mod error;
+pub mod manifest;
 pub mod plugin;
 pub mod plugin_manager;
@@ -33,6 +33,14 @@ pub trait Plugin: Any + Send + Sync {
     fn prepare(&mut self, app: &mut App);
     #[no_mangle]
     fn unprepare(&mut self, app: &mut App);
+    // Opt-in: the serialized state is given back to restore_state
+    // of the new instance once it has been prepared after a reload
+    #[no_mangle]
+    fn save_state(&mut self, _app: &mut App) -> Option<String> {
+        None
+    }
+    #[no_mangle]
+    fn restore_state(&mut self, _app: &mut App, _state: &str) {}
     #[no_mangle]
     fn id(&self) -> PluginId {
         PluginId::new()
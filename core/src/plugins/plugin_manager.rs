@@ -1,11 +1,18 @@
-use std::{path::PathBuf, process};
+use std::{
+    collections::HashMap,
+    env::consts::DLL_PREFIX,
+    path::{Path, PathBuf},
+    process,
+};
 
-use nrg_dynamic_library::{library, Library};
+use nrg_dynamic_library::{delete_file, library, Library};
 use nrg_platform::{FileEvent, FileWatcher};
 
 use crate::{
-    App, PfnCreatePlugin, PfnDestroyPlugin, PluginHolder, PluginId, CREATE_PLUGIN_FUNCTION_NAME,
-    DESTROY_PLUGIN_FUNCTION_NAME,
+    App, PfnCreatePlugin, PfnDestroyPlugin, PfnGetPluginAbi, PfnGetPluginManifest, PluginAbi,
+    PluginDependency, PluginError, PluginHolder, PluginId, PluginManifest, PluginResult,
+    CREATE_PLUGIN_FUNCTION_NAME, DESTROY_PLUGIN_FUNCTION_NAME, GET_PLUGIN_ABI_FUNCTION_NAME,
+    GET_PLUGIN_MANIFEST_FUNCTION_NAME,
 };
 
 pub static IN_USE_PREFIX: &str = "in_use";
@@ -15,13 +22,28 @@ pub struct PluginData {
     id: PluginId,
     lib: Box<Library>,
     pub plugin_holder: Option<PluginHolder>,
+    pub manifest: PluginManifest,
+    is_prepared: bool,
     filewatcher: FileWatcher,
     pub original_path: PathBuf,
     in_use_path: PathBuf,
 }
 
+impl PluginData {
+    pub fn id(&self) -> PluginId {
+        self.id
+    }
+    pub fn is_prepared(&self) -> bool {
+        self.is_prepared
+    }
+}
+
+// Plugins are kept in the order they have been prepared in, while the pending ones
+// are waiting for their dependencies to be prepared first
 pub struct PluginManager {
     plugins: Vec<PluginData>,
+    pending_plugins: Vec<PluginData>,
+    saved_states: HashMap<String, String>,
 }
 
 impl Default for PluginManager {
@@ -34,17 +56,96 @@ impl PluginManager {
     pub fn new() -> Self {
         Self {
             plugins: Vec::new(),
+            pending_plugins: Vec::new(),
+            saved_states: HashMap::new(),
         }
     }
 
     pub fn release(&mut self) -> Vec<PluginId> {
         let mut plugins_to_remove: Vec<PluginId> = Vec::new();
-        for plugin in self.plugins.iter() {
+        for plugin in self.pending_plugins.iter() {
+            plugins_to_remove.push(plugin.id);
+        }
+        for plugin in self.plugins.iter().rev() {
             plugins_to_remove.push(plugin.id);
         }
         plugins_to_remove
     }
 
+    pub fn is_plugin_loaded(&self, name: &str) -> bool {
+        self.plugins
+            .iter()
+            .chain(self.pending_plugins.iter())
+            .any(|p| p.manifest.name == name)
+    }
+
+    pub fn get_unmet_dependencies<'a>(
+        &self,
+        manifest: &'a PluginManifest,
+    ) -> Vec<&'a PluginDependency> {
+        manifest
+            .dependencies
+            .iter()
+            .filter(|d| {
+                !self.plugins.iter().any(|p| {
+                    p.manifest.name == d.name && d.requirement.matches(&p.manifest.version)
+                })
+            })
+            .collect()
+    }
+
+    pub fn store_plugin_state(&mut self, name: &str, state: String) {
+        self.saved_states.insert(String::from(name), state);
+    }
+
+    pub fn take_plugin_state(&mut self, name: &str) -> Option<String> {
+        self.saved_states.remove(name)
+    }
+
+    pub fn add_pending_plugin(&mut self, plugin_data: PluginData) {
+        self.pending_plugins.push(plugin_data);
+    }
+
+    pub fn take_next_ready_plugin(&mut self) -> Option<PluginData> {
+        if let Some(index) = self
+            .pending_plugins
+            .iter()
+            .position(|p| self.get_unmet_dependencies(&p.manifest).is_empty())
+        {
+            return Some(self.pending_plugins.remove(index));
+        }
+        None
+    }
+
+    pub fn take_pending_plugins(&mut self) -> Vec<PluginData> {
+        self.pending_plugins.drain(..).collect()
+    }
+
+    // Returns the requested plugins together with all the ones depending on them,
+    // sorted so that every plugin comes before the plugins it depends on
+    pub fn compute_unload_order(&self, plugin_ids: &[PluginId]) -> Vec<PluginId> {
+        let mut names: Vec<&str> = Vec::new();
+        for plugin in self.plugins.iter() {
+            let is_requested = plugin_ids.contains(&plugin.id);
+            let is_dependent = names.iter().any(|n| plugin.manifest.is_dependent_on(n));
+            if is_requested || is_dependent {
+                names.push(plugin.manifest.name.as_str());
+            }
+        }
+        let mut plugins_to_remove: Vec<PluginId> = self
+            .pending_plugins
+            .iter()
+            .filter(|p| plugin_ids.contains(&p.id))
+            .map(|p| p.id)
+            .collect();
+        for plugin in self.plugins.iter().rev() {
+            if names.contains(&plugin.manifest.name.as_str()) {
+                plugins_to_remove.push(plugin.id);
+            }
+        }
+        plugins_to_remove
+    }
+
     pub fn add_plugin(&mut self, plugin_data: PluginData) {
         nrg_profiler::scoped_profile!("plugin_manager::add_plugin");
         self.plugins.push(plugin_data);
@@ -54,6 +155,12 @@ impl PluginManager {
         nrg_profiler::scoped_profile!("plugin_manager::remove_plugin");
         if let Some(index) = self.plugins.iter().position(|el| el.id == *plugin_id) {
             return Some(self.plugins.remove(index));
+        } else if let Some(index) = self
+            .pending_plugins
+            .iter()
+            .position(|el| el.id == *plugin_id)
+        {
+            return Some(self.pending_plugins.remove(index));
         } else {
             eprintln!("Unable to find requested plugin with id {:?}", plugin_id);
         }
@@ -82,24 +189,72 @@ impl PluginManager {
         }
     }
 
-    fn load_plugin(fullpath: PathBuf) -> (library::Library, Option<PluginHolder>) {
+    // Nothing is called in a library exporting plugin symbols before checking its abi
+    fn load_plugin(
+        fullpath: &Path,
+        in_use_fullpath: &Path,
+    ) -> PluginResult<(library::Library, Option<PluginHolder>)> {
         nrg_profiler::scoped_profile!("plugin_manager::load_plugin");
-        let lib = library::Library::new(fullpath);
-        if let Some(create_fn) = lib.get::<PfnCreatePlugin>(CREATE_PLUGIN_FUNCTION_NAME) {
+        let lib =
+            library::Library::load(in_use_fullpath).map_err(|error| PluginError::LoadFailed {
+                path: fullpath.to_path_buf(),
+                error,
+            })?;
+        let create_fn = lib.get::<PfnCreatePlugin>(CREATE_PLUGIN_FUNCTION_NAME);
+        let manifest_fn = lib.get::<PfnGetPluginManifest>(GET_PLUGIN_MANIFEST_FUNCTION_NAME);
+        if create_fn.is_none() && manifest_fn.is_none() {
+            return Ok((lib, None));
+        }
+        let missing_symbol = |symbol: &str| PluginError::MissingSymbol {
+            path: fullpath.to_path_buf(),
+            symbol: String::from(symbol),
+        };
+        let abi_fn = lib
+            .get::<PfnGetPluginAbi>(GET_PLUGIN_ABI_FUNCTION_NAME)
+            .ok_or_else(|| missing_symbol(GET_PLUGIN_ABI_FUNCTION_NAME))?;
+        let expected = PluginAbi::current();
+        let found = unsafe { abi_fn.unwrap()() };
+        if !expected.is_compatible_with(&found) {
+            return Err(PluginError::AbiMismatch {
+                path: fullpath.to_path_buf(),
+                expected,
+                found,
+            });
+        }
+        if let Some(create_fn) = create_fn {
+            if lib
+                .get::<PfnDestroyPlugin>(DESTROY_PLUGIN_FUNCTION_NAME)
+                .is_none()
+            {
+                return Err(missing_symbol(DESTROY_PLUGIN_FUNCTION_NAME));
+            }
             let plugin_holder = unsafe { create_fn.unwrap()() };
-            return (lib, Some(plugin_holder));
+            return Ok((lib, Some(plugin_holder)));
         }
-        (lib, None)
+        Ok((lib, None))
     }
 
-    pub fn create_plugin_data(lib_path: PathBuf, app: &mut App) -> PluginData {
+    // Libraries without a manifest are named after their file and have no dependencies
+    fn load_manifest(lib: &library::Library, fullpath: &Path) -> PluginManifest {
+        if let Some(manifest_fn) =
+            lib.get::<PfnGetPluginManifest>(GET_PLUGIN_MANIFEST_FUNCTION_NAME)
+        {
+            return unsafe { manifest_fn.unwrap()() };
+        }
+        let name = fullpath.file_stem().unwrap().to_str().unwrap();
+        //Same name on every platform, without the lib prefix of .so files
+        let name = name.strip_prefix(DLL_PREFIX).unwrap_or(name);
+        PluginManifest::new(name, "0.0.0")
+    }
+
+    pub fn create_plugin_data(lib_path: PathBuf) -> PluginResult<PluginData> {
+        if lib_path.is_absolute() && !lib_path.is_file() {
+            return Err(PluginError::NotFound(lib_path));
+        }
         let (path, filename) = library::compute_folder_and_filename(lib_path);
         let fullpath = path.join(filename);
-        if !fullpath.exists() && fullpath.is_file() {
-            panic!(
-                "Unable to find requested plugin path {}",
-                fullpath.to_str().unwrap()
-            );
+        if !fullpath.is_file() {
+            return Err(PluginError::NotFound(fullpath));
         }
         let mut in_use_fullpath = PluginManager::compute_dynamic_name(fullpath.clone());
         let res = std::fs::copy(fullpath.clone(), in_use_fullpath.clone());
@@ -113,27 +268,58 @@ impl PluginManager {
             in_use_fullpath = fullpath.clone();
         }
 
-        let (lib, plugin_holder) = PluginManager::load_plugin(in_use_fullpath.clone());
+        let (lib, plugin_holder) =
+            match PluginManager::load_plugin(fullpath.as_path(), in_use_fullpath.as_path()) {
+                Ok(result) => result,
+                Err(error) => {
+                    if in_use_fullpath != fullpath {
+                        delete_file(in_use_fullpath);
+                    }
+                    return Err(error);
+                }
+            };
+        let manifest = PluginManager::load_manifest(&lib, &fullpath);
 
         println!(
             "Loaded plugin {}",
             fullpath.file_stem().unwrap().to_str().unwrap()
         );
 
-        let mut plugin_data = PluginData {
+        Ok(PluginData {
             id: PluginId::new(),
             lib: Box::new(lib),
             plugin_holder,
+            manifest,
+            is_prepared: false,
             filewatcher: FileWatcher::new(fullpath.clone()),
             original_path: fullpath,
             in_use_path: in_use_fullpath,
-        };
+        })
+    }
 
+    pub fn prepare_plugin_data(plugin_data: &mut PluginData, app: &mut App) {
+        nrg_profiler::scoped_profile!("plugin_manager::prepare_plugin_data");
         if let Some(holder) = &mut plugin_data.plugin_holder {
             holder.get_plugin().prepare(app);
         }
+        plugin_data.is_prepared = true;
+    }
+
+    pub fn save_plugin_state(plugin_data: &mut PluginData, app: &mut App) -> Option<String> {
+        nrg_profiler::scoped_profile!("plugin_manager::save_plugin_state");
+        if let Some(holder) = &mut plugin_data.plugin_holder {
+            if plugin_data.is_prepared {
+                return holder.get_plugin().save_state(app);
+            }
+        }
+        None
+    }
 
-        plugin_data
+    pub fn restore_plugin_state(plugin_data: &mut PluginData, state: &str, app: &mut App) {
+        nrg_profiler::scoped_profile!("plugin_manager::restore_plugin_state");
+        if let Some(holder) = &mut plugin_data.plugin_holder {
+            holder.get_plugin().restore_state(app, state);
+        }
     }
 
     pub fn clear_plugin_data(mut plugin_data: PluginData, app: &mut App) {
@@ -143,7 +329,9 @@ impl PluginManager {
         let in_use_path = plugin_data.in_use_path;
         let lib = unsafe { Box::into_raw(plugin_data.lib).as_mut().unwrap() };
         if let Some(mut plugin_holder) = plugin_data.plugin_holder {
-            plugin_holder.get_plugin().unprepare(app);
+            if plugin_data.is_prepared {
+                plugin_holder.get_plugin().unprepare(app);
+            }
             if let Some(destroy_fn) = lib.get::<PfnDestroyPlugin>(DESTROY_PLUGIN_FUNCTION_NAME) {
                 unsafe { destroy_fn.unwrap()(plugin_holder) };
             }
@@ -0,0 +1,258 @@
+use std::{
+    any::TypeId,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use nrg_messenger::{Message, MessengerRw};
+use nrg_platform::{KeyEvent, KeyTextEvent, MouseEvent, WindowEvent};
+use nrg_serialize::{
+    try_deserialize, try_serialize, Deserialize, Serialize, SerializeError, SerializeResult,
+};
+
+use crate::Time;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "nrg_serialize")]
+pub enum RecordedEvent {
+    Key(KeyEvent),
+    KeyText(KeyTextEvent),
+    Mouse(MouseEvent),
+    Window(WindowEvent),
+}
+
+impl RecordedEvent {
+    pub fn from_message(msg: &dyn Message) -> Option<Self> {
+        if msg.type_id() == TypeId::of::<KeyEvent>() {
+            let e = msg.as_any().downcast_ref::<KeyEvent>().unwrap();
+            Some(RecordedEvent::Key(*e))
+        } else if msg.type_id() == TypeId::of::<KeyTextEvent>() {
+            let e = msg.as_any().downcast_ref::<KeyTextEvent>().unwrap();
+            Some(RecordedEvent::KeyText(*e))
+        } else if msg.type_id() == TypeId::of::<MouseEvent>() {
+            let e = msg.as_any().downcast_ref::<MouseEvent>().unwrap();
+            Some(RecordedEvent::Mouse(*e))
+        } else if msg.type_id() == TypeId::of::<WindowEvent>() {
+            let e = msg.as_any().downcast_ref::<WindowEvent>().unwrap();
+            Some(RecordedEvent::Window(e.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_boxed(&self) -> Box<dyn Message> {
+        match self {
+            RecordedEvent::Key(e) => e.as_boxed(),
+            RecordedEvent::KeyText(e) => e.as_boxed(),
+            RecordedEvent::Mouse(e) => e.as_boxed(),
+            RecordedEvent::Window(e) => e.as_boxed(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "nrg_serialize")]
+pub struct RecordedFrame {
+    pub frame_index: u64,
+    pub delta: f32,
+    pub events: Vec<RecordedEvent>,
+}
+
+// Recordings are written one frame per line while recording,
+// so that a crash loses at most the frame that was running
+#[derive(Default, Clone)]
+pub struct MessageRecording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl MessageRecording {
+    pub fn load(filepath: &Path) -> SerializeResult<Self> {
+        let content = fs::read_to_string(filepath)
+            .map_err(|e| SerializeError::from(e).with_path(filepath))?;
+        let lines: Vec<&str> = content.lines().collect();
+        let mut recording = Self::default();
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match try_deserialize::<RecordedFrame>(line) {
+                Ok(frame) => recording.frames.push(frame),
+                Err(e) if i == lines.len() - 1 => {
+                    eprintln!(
+                        "Ignoring truncated last frame of recording {}: {}",
+                        filepath.display(),
+                        e
+                    );
+                }
+                Err(mut e) => {
+                    e.line = i + 1;
+                    return Err(e.with_path(filepath));
+                }
+            }
+        }
+        Ok(recording)
+    }
+}
+
+enum RecorderState {
+    Idle,
+    Recording(BufWriter<File>),
+    Replaying(usize),
+}
+
+pub struct MessageRecorder {
+    state: RecorderState,
+    recording: MessageRecording,
+    recorded_frames_count: u64,
+}
+
+impl Default for MessageRecorder {
+    fn default() -> Self {
+        Self {
+            state: RecorderState::Idle,
+            recording: MessageRecording::default(),
+            recorded_frames_count: 0,
+        }
+    }
+}
+
+impl MessageRecorder {
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        matches!(self.state, RecorderState::Recording(_))
+    }
+    #[inline]
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.state, RecorderState::Replaying(_))
+    }
+
+    pub fn start_recording(&mut self, filepath: PathBuf) -> bool {
+        self.stop_recording();
+        self.recording = MessageRecording::default();
+        self.recorded_frames_count = 0;
+        match File::create(filepath.as_path()) {
+            Ok(file) => {
+                self.state = RecorderState::Recording(BufWriter::new(file));
+                true
+            }
+            Err(e) => {
+                eprintln!("Unable to create recording {}: {}", filepath.display(), e);
+                false
+            }
+        }
+    }
+
+    pub fn stop_recording(&mut self) {
+        if self.is_recording() {
+            self.write_recorded_frames();
+            self.state = RecorderState::Idle;
+        }
+    }
+
+    // Frames are written and flushed as soon as they are complete
+    fn write_recorded_frames(&mut self) {
+        if let RecorderState::Recording(writer) = &mut self.state {
+            for frame in self.recording.frames.drain(..) {
+                match try_serialize(&frame) {
+                    Ok(line) => {
+                        if let Err(e) = writeln!(writer, "{}", line) {
+                            eprintln!("Unable to write recorded frame: {}", e);
+                        }
+                    }
+                    Err(e) => eprintln!("Unable to serialize recorded frame: {}", e),
+                }
+            }
+            if let Err(e) = writer.flush() {
+                eprintln!("Unable to write recording: {}", e);
+            }
+        }
+    }
+
+    pub fn start_replay(&mut self, filepath: PathBuf, global_messenger: &MessengerRw) -> bool {
+        match MessageRecording::load(filepath.as_path()) {
+            Ok(recording) => {
+                self.recording = recording;
+                self.state = RecorderState::Replaying(0);
+                Self::mute_recorded_types(global_messenger, true);
+                true
+            }
+            Err(e) => {
+                eprintln!("Unable to load recording: {}", e);
+                false
+            }
+        }
+    }
+
+    pub fn stop_replay(&mut self, global_messenger: &MessengerRw) {
+        if self.is_replaying() {
+            Self::mute_recorded_types(global_messenger, false);
+            self.state = RecorderState::Idle;
+            self.recording = MessageRecording::default();
+        }
+    }
+
+    fn mute_recorded_types(global_messenger: &MessengerRw, mute: bool) {
+        let mut messenger = global_messenger.write().unwrap();
+        if mute {
+            messenger
+                .mute_type::<KeyEvent>()
+                .mute_type::<KeyTextEvent>()
+                .mute_type::<MouseEvent>()
+                .mute_type::<WindowEvent>();
+        } else {
+            messenger
+                .unmute_type::<KeyEvent>()
+                .unmute_type::<KeyTextEvent>()
+                .unmute_type::<MouseEvent>()
+                .unmute_type::<WindowEvent>();
+        }
+    }
+
+    // Delta to use for the next frame to keep a replay deterministic
+    pub fn get_replay_delta(&self) -> Option<f32> {
+        if let RecorderState::Replaying(index) = self.state {
+            self.recording.frames.get(index).map(|f| f.delta)
+        } else {
+            None
+        }
+    }
+
+    pub fn begin_frame(&mut self, time: &Time) {
+        if self.is_recording() {
+            self.write_recorded_frames();
+            self.recording.frames.push(RecordedFrame {
+                frame_index: self.recorded_frames_count,
+                delta: time.get_unscaled_delta(),
+                events: Vec::new(),
+            });
+            self.recorded_frames_count += 1;
+        }
+    }
+
+    pub fn record(&mut self, msg: &dyn Message) {
+        if !self.is_recording() {
+            return;
+        }
+        if let Some(event) = RecordedEvent::from_message(msg) {
+            if let Some(frame) = self.recording.frames.last_mut() {
+                frame.events.push(event);
+            }
+        }
+    }
+
+    pub fn replay_frame(&mut self, global_messenger: &MessengerRw) {
+        if let RecorderState::Replaying(index) = self.state {
+            if let Some(frame) = self.recording.frames.get(index) {
+                let dispatcher = global_messenger.read().unwrap().get_replay_dispatcher();
+                let dispatcher = dispatcher.write().unwrap();
+                for event in frame.events.iter() {
+                    dispatcher.send(event.as_boxed()).ok();
+                }
+                self.state = RecorderState::Replaying(index + 1);
+            } else {
+                self.stop_replay(global_messenger);
+            }
+        }
+    }
+}
@@ -1,11 +1,160 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    mpsc::Sender,
-    Arc, RwLock,
+use std::{
+    cell::Cell,
+    collections::{HashMap, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, RwLock, Weak,
+    },
+    thread::{self, ThreadId},
+    time::Duration,
 };
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum JobPriority {
+    High = 0,
+    Normal = 1,
+    Background = 2,
+}
+const NUM_JOB_PRIORITIES: usize = 3;
+
+#[derive(Default)]
+struct JobState {
+    is_done: AtomicBool,
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+impl JobState {
+    #[inline]
+    fn is_done(&self) -> bool {
+        self.is_done.load(Ordering::SeqCst)
+    }
+    fn set_done(&self) {
+        let _lock = self.lock.lock().unwrap();
+        self.is_done.store(true, Ordering::SeqCst);
+        self.condvar.notify_all();
+    }
+    // Runs the queued jobs while waiting: the awaited job could be queued behind them
+    // and a worker blocked here could never get to it
+    fn wait(&self, job_queue: &Weak<JobQueue>) {
+        while !self.is_done() {
+            let job = job_queue
+                .upgrade()
+                .and_then(|queue| queue.pop_any().map(|job| (queue, job)));
+            if let Some((queue, job)) = job {
+                queue.execute(job);
+            } else {
+                let lock = self.lock.lock().unwrap();
+                if !self.is_done() {
+                    let _ = self.condvar.wait_timeout(lock, PARK_TIMEOUT).unwrap();
+                }
+            }
+        }
+    }
+}
+
+// Marks the job as done when dropped, even when the job panics
+struct JobDoneGuard(Arc<JobState>);
+
+impl Drop for JobDoneGuard {
+    fn drop(&mut self) {
+        self.0.set_done();
+    }
+}
+
+// Jobs added while running a job belong to the frame of its owner,
+// restored when dropped even when the job panics
+struct JobOwnerGuard(Option<ThreadId>);
+
+impl JobOwnerGuard {
+    fn new(owner: ThreadId) -> Self {
+        Self(JOB_OWNER.with(|o| o.replace(Some(owner))))
+    }
+}
+
+impl Drop for JobOwnerGuard {
+    fn drop(&mut self) {
+        JOB_OWNER.with(|o| o.set(self.0));
+    }
+}
+
+#[derive(Clone)]
+pub struct JobDependency {
+    state: Arc<JobState>,
+    job_queue: Weak<JobQueue>,
+}
+
+impl JobDependency {
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.state.is_done()
+    }
+    pub fn wait(&self) {
+        nrg_profiler::scoped_profile!("job::wait");
+        self.state.wait(&self.job_queue);
+    }
+}
+
+pub struct JobHandle<R = ()> {
+    state: Arc<JobState>,
+    result: Arc<Mutex<Option<R>>>,
+    job_queue: Weak<JobQueue>,
+}
+
+impl<R> Clone for JobHandle<R> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            result: self.result.clone(),
+            job_queue: self.job_queue.clone(),
+        }
+    }
+}
+
+impl<R> JobHandle<R> {
+    fn new(job_queue: &Arc<JobQueue>) -> Self {
+        Self {
+            state: Arc::new(JobState::default()),
+            result: Arc::new(Mutex::new(None)),
+            job_queue: Arc::downgrade(job_queue),
+        }
+    }
+    #[inline]
+    pub fn dependency(&self) -> JobDependency {
+        JobDependency {
+            state: self.state.clone(),
+            job_queue: self.job_queue.clone(),
+        }
+    }
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.state.is_done()
+    }
+    // A job that panicked is done without a result
+    pub fn wait(&self) {
+        nrg_profiler::scoped_profile!("job::wait");
+        self.state.wait(&self.job_queue);
+    }
+    #[inline]
+    pub fn take_result(&self) -> Option<R> {
+        if self.is_done() {
+            self.result.lock().unwrap().take()
+        } else {
+            None
+        }
+    }
+    pub fn wait_result(self) -> Option<R> {
+        self.wait();
+        self.take_result()
+    }
+}
+
 pub struct Job {
     func: Box<dyn FnOnce() + Send + Sync>,
-    wait_count: Arc<AtomicUsize>,
+    wait_count: Option<Arc<AtomicUsize>>,
+    dependencies: Vec<JobDependency>,
+    priority: JobPriority,
     name: String,
 }
 
@@ -13,13 +162,21 @@ unsafe impl Sync for Job {}
 unsafe impl Send for Job {}
 
 impl Job {
-    pub fn new<F>(name: &str, func: F, wait_count: Arc<AtomicUsize>) -> Self
+    pub fn new<F>(
+        name: &str,
+        priority: JobPriority,
+        dependencies: Vec<JobDependency>,
+        func: F,
+        wait_count: Option<Arc<AtomicUsize>>,
+    ) -> Self
     where
         F: FnOnce() + Send + Sync + 'static,
     {
         Self {
             func: Box::new(func),
             wait_count,
+            dependencies,
+            priority,
             name: String::from(name),
         }
     }
@@ -28,20 +185,192 @@ impl Job {
         self.name.as_str()
     }
 
+    pub fn get_priority(&self) -> JobPriority {
+        self.priority
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.dependencies.iter().all(|d| d.is_done())
+    }
+
     pub fn execute(self) {
         nrg_profiler::scoped_profile!(self.name.as_str());
 
-        (self.func)();
+        if panic::catch_unwind(AssertUnwindSafe(self.func)).is_err() {
+            eprintln!("Job {} panicked", self.name);
+        }
 
-        self.wait_count.fetch_sub(1, Ordering::SeqCst);
+        if let Some(wait_count) = &self.wait_count {
+            wait_count.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+const PARK_TIMEOUT: Duration = Duration::from_millis(1);
+
+type WorkerDeque = Mutex<Vec<VecDeque<Job>>>;
+
+thread_local! {
+    //Queue and deque index of the worker running on this thread
+    static WORKER_DEQUE: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+    //Thread whose frame the job running on this thread belongs to
+    static JOB_OWNER: Cell<Option<ThreadId>> = const { Cell::new(None) };
+}
+
+fn current_job_owner() -> ThreadId {
+    JOB_OWNER
+        .with(|o| o.get())
+        .unwrap_or_else(|| thread::current().id())
+}
+
+// Every worker owns a deque per priority: it pops jobs from the front of its own
+// and steals from the back of the others when it runs out of work.
+// Jobs waiting for their dependencies are kept aside until they are ready,
+// so that idle workers don't keep spinning over them
+pub struct JobQueue {
+    deques: RwLock<Vec<WorkerDeque>>,
+    waiting_jobs: Mutex<Vec<Job>>,
+    next_deque: AtomicUsize,
+    num_jobs: AtomicUsize,
+    idle_lock: Mutex<()>,
+    idle_condvar: Condvar,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            deques: RwLock::new(Vec::new()),
+            waiting_jobs: Mutex::new(Vec::new()),
+            next_deque: AtomicUsize::new(0),
+            num_jobs: AtomicUsize::new(0),
+            idle_lock: Mutex::new(()),
+            idle_condvar: Condvar::new(),
+        }
+    }
+}
+
+impl JobQueue {
+    pub fn add_worker(&self) -> usize {
+        let mut deques = self.deques.write().unwrap();
+        let mut queues = Vec::new();
+        for _ in 0..NUM_JOB_PRIORITIES {
+            queues.push(VecDeque::new());
+        }
+        deques.push(Mutex::new(queues));
+        deques.len() - 1
+    }
+
+    // To be called from the worker thread that owns the deque
+    pub fn set_current_worker(&self, worker_index: usize) {
+        let queue = self as *const Self as usize;
+        WORKER_DEQUE.with(|w| w.set(Some((queue, worker_index))));
+    }
+
+    fn current_worker(&self) -> Option<usize> {
+        let queue = self as *const Self as usize;
+        WORKER_DEQUE.with(|w| match w.get() {
+            Some((q, index)) if q == queue => Some(index),
+            _ => None,
+        })
+    }
+
+    pub fn push(&self, job: Job) {
+        if job.is_ready() {
+            self.push_ready(job);
+        } else {
+            self.waiting_jobs.lock().unwrap().push(job);
+            //Its dependencies could have completed meanwhile
+            self.release_ready_jobs();
+        }
+    }
+
+    fn push_ready(&self, job: Job) {
+        if self.deques.read().unwrap().is_empty() {
+            self.add_worker();
+        }
+        {
+            //Workers keep the jobs they spawn, the others are spread among all the workers
+            let deques = self.deques.read().unwrap();
+            let index = match self.current_worker() {
+                Some(index) if index < deques.len() => index,
+                _ => self.next_deque.fetch_add(1, Ordering::SeqCst) % deques.len(),
+            };
+            deques[index].lock().unwrap()[job.get_priority() as usize].push_back(job);
+        }
+        self.num_jobs.fetch_add(1, Ordering::SeqCst);
+        let _lock = self.idle_lock.lock().unwrap();
+        self.idle_condvar.notify_one();
+    }
+
+    fn release_ready_jobs(&self) {
+        let ready_jobs: Vec<Job> = {
+            let mut waiting_jobs = self.waiting_jobs.lock().unwrap();
+            if waiting_jobs.is_empty() {
+                return;
+            }
+            let (ready, waiting): (Vec<Job>, Vec<Job>) =
+                waiting_jobs.drain(..).partition(|j| j.is_ready());
+            *waiting_jobs = waiting;
+            ready
+        };
+        for job in ready_jobs {
+            self.push_ready(job);
+        }
+    }
+
+    // Used by threads waiting for a job, that could not be workers
+    #[inline]
+    pub fn pop_any(&self) -> Option<Job> {
+        self.pop(self.current_worker().unwrap_or_default())
+    }
+
+    pub fn pop(&self, worker_index: usize) -> Option<Job> {
+        let deques = self.deques.read().unwrap();
+        for priority in 0..NUM_JOB_PRIORITIES {
+            if let Some(deque) = deques.get(worker_index) {
+                if let Some(job) = deque.lock().unwrap()[priority].pop_front() {
+                    self.num_jobs.fetch_sub(1, Ordering::SeqCst);
+                    return Some(job);
+                }
+            }
+            for i in 1..deques.len() {
+                let index = (worker_index + i) % deques.len();
+                if let Ok(mut queue) = deques[index].try_lock() {
+                    if let Some(job) = queue[priority].pop_back() {
+                        self.num_jobs.fetch_sub(1, Ordering::SeqCst);
+                        return Some(job);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Jobs waiting for the executed one are made available to the workers
+    pub fn execute(&self, job: Job) {
+        job.execute();
+        self.release_ready_jobs();
+    }
+
+    pub fn park(&self) {
+        let lock = self.idle_lock.lock().unwrap();
+        if self.num_jobs.load(Ordering::SeqCst) == 0 {
+            let _ = self.idle_condvar.wait_timeout(lock, PARK_TIMEOUT).unwrap();
+        }
+    }
+
+    pub fn wake_all(&self) {
+        let _lock = self.idle_lock.lock().unwrap();
+        self.idle_condvar.notify_all();
     }
 }
 
 pub type JobHandlerRw = Arc<RwLock<JobHandler>>;
 
 pub struct JobHandler {
-    sender: Sender<Job>,
-    pending_jobs: Arc<AtomicUsize>,
+    job_queue: Arc<JobQueue>,
+    frame_jobs: HashMap<ThreadId, Vec<JobDependency>>,
+    background_jobs: Arc<AtomicUsize>,
 }
 
 unsafe impl Sync for JobHandler {}
@@ -49,37 +378,88 @@ unsafe impl Send for JobHandler {}
 
 impl JobHandler {
     #[inline]
-    pub fn new(sender: Sender<Job>) -> Arc<RwLock<JobHandler>> {
+    pub fn new(job_queue: Arc<JobQueue>) -> Arc<RwLock<JobHandler>> {
         Arc::new(RwLock::new(JobHandler {
-            sender,
-            pending_jobs: Arc::new(AtomicUsize::new(0)),
+            job_queue,
+            frame_jobs: HashMap::new(),
+            background_jobs: Arc::new(AtomicUsize::new(0)),
         }))
     }
     #[inline]
-    pub fn add_job<F>(&mut self, job_name: &str, func: F)
+    pub fn add_job<F, R>(&mut self, job_name: &str, func: F) -> JobHandle<R>
     where
-        F: FnOnce() + Send + Sync + 'static,
+        F: FnOnce() -> R + Send + Sync + 'static,
+        R: Send + Sync + 'static,
     {
-        self.pending_jobs.fetch_add(1, Ordering::SeqCst);
-
-        let job = Job::new(job_name, func, self.pending_jobs.clone());
-        self.sender.send(job).ok();
+        self.add_job_with_dependencies(job_name, JobPriority::Normal, Vec::new(), func)
     }
 
     #[inline]
-    pub fn has_pending_jobs(&self) -> bool {
-        self.get_pending_jobs_count() > 0
+    pub fn add_job_with_priority<F, R>(
+        &mut self,
+        job_name: &str,
+        priority: JobPriority,
+        func: F,
+    ) -> JobHandle<R>
+    where
+        F: FnOnce() -> R + Send + Sync + 'static,
+        R: Send + Sync + 'static,
+    {
+        self.add_job_with_dependencies(job_name, priority, Vec::new(), func)
     }
 
+    pub fn add_job_with_dependencies<F, R>(
+        &mut self,
+        job_name: &str,
+        priority: JobPriority,
+        dependencies: Vec<JobDependency>,
+        func: F,
+    ) -> JobHandle<R>
+    where
+        F: FnOnce() -> R + Send + Sync + 'static,
+        R: Send + Sync + 'static,
+    {
+        let handle = JobHandle::new(&self.job_queue);
+        let owner = current_job_owner();
+        //Background jobs can span several frames so they are not part of the frame barrier
+        let wait_count = if priority == JobPriority::Background {
+            self.background_jobs.fetch_add(1, Ordering::SeqCst);
+            Some(self.background_jobs.clone())
+        } else {
+            let frame_jobs = self.frame_jobs.entry(owner).or_default();
+            frame_jobs.retain(|j| !j.is_done());
+            frame_jobs.push(handle.dependency());
+            None
+        };
+
+        let state = handle.state.clone();
+        let result = handle.result.clone();
+        let job = Job::new(
+            job_name,
+            priority,
+            dependencies,
+            move || {
+                let _done = JobDoneGuard(state);
+                let _owner = JobOwnerGuard::new(owner);
+                let value = func();
+                *result.lock().unwrap() = Some(value);
+            },
+            wait_count,
+        );
+        self.job_queue.push(job);
+        handle
+    }
+
+    // Jobs of the frame of this thread added since the last call, background ones excluded
     #[inline]
-    pub fn get_pending_jobs_count(&self) -> usize {
-        self.pending_jobs.load(Ordering::SeqCst)
+    pub fn take_frame_jobs(&mut self) -> Vec<JobDependency> {
+        self.frame_jobs
+            .remove(&current_job_owner())
+            .unwrap_or_default()
     }
 
     #[inline]
-    pub fn clear_pending_jobs(&self) {
-        if self.has_pending_jobs() {
-            self.pending_jobs.store(0, Ordering::SeqCst);
-        }
+    pub fn get_background_jobs_count(&self) -> usize {
+        self.background_jobs.load(Ordering::SeqCst)
     }
 }
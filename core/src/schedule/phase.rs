@@ -1,21 +1,37 @@
 use downcast_rs::{impl_downcast, Downcast};
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
 
-use crate::{System, SystemBoxed, SystemId};
+use crate::{
+    FixedStepScope, JobHandlerRw, JobPriority, System, SystemBoxed, SystemId, SystemRw, Time,
+};
 
 pub trait Phase: Downcast + Send + Sync {
     fn get_name(&self) -> &str;
     fn should_run_when_not_focused(&self) -> bool;
     fn init(&mut self);
-    fn run(&mut self, is_focused: bool) -> bool;
+    fn run(&mut self, is_focused: bool, job_handler: &JobHandlerRw, time: &Time) -> bool;
     fn uninit(&mut self);
 }
 impl_downcast!(Phase);
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PhaseMode {
+    PerFrame,
+    // Runs every Time::get_fixed_delta() catching up missed steps up to max_steps per frame:
+    // at least one step is run when the fixed delta elapsed
+    FixedStep { max_steps: u32 },
+}
+
 pub struct PhaseWithSystems {
     name: String,
+    mode: PhaseMode,
+    accumulated_time: f32,
     systems: HashSet<SystemId>,
-    systems_running: Vec<SystemBoxed>,
+    systems_running: Vec<SystemRw>,
+    systems_batches: Vec<Vec<usize>>,
     systems_to_add: Vec<SystemBoxed>,
     systems_to_remove: Vec<SystemId>,
 }
@@ -24,13 +40,31 @@ impl PhaseWithSystems {
     pub fn new(name: &str) -> Self {
         Self {
             name: String::from(name),
+            mode: PhaseMode::PerFrame,
+            accumulated_time: 0.,
             systems: HashSet::new(),
             systems_running: Vec::new(),
+            systems_batches: Vec::new(),
             systems_to_add: Vec::new(),
             systems_to_remove: Vec::new(),
         }
     }
 
+    pub fn set_mode(&mut self, mode: PhaseMode) -> &mut Self {
+        self.mode = match mode {
+            PhaseMode::FixedStep { max_steps } => PhaseMode::FixedStep {
+                max_steps: max_steps.max(1),
+            },
+            mode => mode,
+        };
+        self.accumulated_time = 0.;
+        self
+    }
+
+    pub fn get_mode(&self) -> PhaseMode {
+        self.mode
+    }
+
     pub fn add_system<S: System + 'static>(&mut self, system: S) -> &mut Self {
         if self.systems.contains(&system.id()) {
             eprintln!(
@@ -57,50 +91,126 @@ impl PhaseWithSystems {
 
     fn remove_all_systems(&mut self) -> &mut Self {
         for s in self.systems_running.iter() {
-            if !self.systems_to_remove.contains(&s.id()) {
-                self.systems_to_remove.push(s.id());
+            let id = s.read().unwrap().id();
+            if !self.systems_to_remove.contains(&id) {
+                self.systems_to_remove.push(id);
             }
         }
         self
     }
 
-    fn execute_systems(&mut self, is_focused: bool) -> bool {
+    fn compute_systems_batches(&mut self) -> &mut Self {
+        //Each system goes in the first batch after the last one containing a system it conflicts with
+        //so that conflicting systems keep running in insertion order
+        let accesses: Vec<_> = self
+            .systems_running
+            .iter()
+            .map(|s| s.read().unwrap().access())
+            .collect();
+        self.systems_batches.clear();
+        for (i, access) in accesses.iter().enumerate() {
+            let batch_index = self
+                .systems_batches
+                .iter()
+                .rposition(|batch| batch.iter().any(|&j| access.conflicts_with(&accesses[j])))
+                .map(|b| b + 1)
+                .unwrap_or(0);
+            if batch_index < self.systems_batches.len() {
+                self.systems_batches[batch_index].push(i);
+            } else {
+                self.systems_batches.push(vec![i]);
+            }
+        }
+        self
+    }
+
+    fn execute_system(system: &SystemRw, is_focused: bool, fixed_delta: Option<f32>) -> bool {
+        let mut s = system.write().unwrap();
+        nrg_profiler::scoped_profile!(format!("{}[{:?}]", "phase::execute_system", s.id()).as_str());
+        if is_focused || s.should_run_when_not_focused() {
+            let _fixed_step = fixed_delta.map(FixedStepScope::new);
+            s.run()
+        } else {
+            true
+        }
+    }
+
+    fn execute_systems(
+        &mut self,
+        is_focused: bool,
+        job_handler: &JobHandlerRw,
+        fixed_delta: Option<f32>,
+    ) -> bool {
         nrg_profiler::scoped_profile!("phase::execute_systems");
         let mut can_continue = true;
-        for s in self.systems_running.iter_mut() {
-            nrg_profiler::scoped_profile!(format!(
-                "{}[{:?}]",
-                "phase::execute_system",
-                s.as_mut().id()
-            )
-            .as_str());
-            let ok = if is_focused || s.should_run_when_not_focused() {
-                s.run()
-            } else {
-                true
-            };
-            can_continue &= ok;
+        for batch in self.systems_batches.iter() {
+            //Workers could be stopped when not focused so run everything on this thread
+            if !is_focused || batch.len() == 1 {
+                for &i in batch.iter() {
+                    can_continue &=
+                        Self::execute_system(&self.systems_running[i], is_focused, fixed_delta);
+                }
+                continue;
+            }
+            let handles: Vec<_> = batch
+                .iter()
+                .skip(1)
+                .map(|&i| {
+                    let system = self.systems_running[i].clone();
+                    let job_name = format!(
+                        "{}[{:?}]",
+                        "phase::execute_system",
+                        system.read().unwrap().id()
+                    );
+                    job_handler.write().unwrap().add_job_with_priority(
+                        job_name.as_str(),
+                        JobPriority::High,
+                        move || Self::execute_system(&system, is_focused, fixed_delta),
+                    )
+                })
+                .collect();
+            can_continue &=
+                Self::execute_system(&self.systems_running[batch[0]], is_focused, fixed_delta);
+            for handle in handles {
+                //A system that panicked has no result and stops the phase
+                can_continue &= handle.wait_result().unwrap_or_else(|| {
+                    eprintln!("System panicked while running in phase {}", self.name);
+                    false
+                });
+            }
         }
         can_continue
     }
 
     fn remove_pending_systems_from_execution(&mut self) -> &mut Self {
-        for (i, s) in self.systems_to_remove.iter_mut().enumerate() {
-            let mut system = self.systems_running.remove(i);
-            system.uninit();
-            self.systems.remove(&s);
+        if self.systems_to_remove.is_empty() {
+            return self;
+        }
+        for id in self.systems_to_remove.iter() {
+            if let Some(index) = self
+                .systems_running
+                .iter()
+                .position(|s| s.read().unwrap().id() == *id)
+            {
+                let system = self.systems_running.remove(index);
+                system.write().unwrap().uninit();
+            }
+            self.systems.remove(id);
         }
         self.systems_to_remove.clear();
-        self
+        self.compute_systems_batches()
     }
 
     fn add_pending_systems_into_execution(&mut self) -> &mut Self {
-        for s in self.systems_to_add.iter_mut() {
+        if self.systems_to_add.is_empty() {
+            return self;
+        }
+        for mut s in self.systems_to_add.drain(..) {
             s.init();
             self.systems.insert(s.id());
+            self.systems_running.push(Arc::new(RwLock::new(s)));
         }
-        self.systems_running.append(&mut self.systems_to_add);
-        self
+        self.compute_systems_batches()
     }
 }
 
@@ -110,7 +220,7 @@ impl Phase for PhaseWithSystems {
     }
     fn should_run_when_not_focused(&self) -> bool {
         for s in self.systems_running.iter() {
-            if s.should_run_when_not_focused() {
+            if s.read().unwrap().should_run_when_not_focused() {
                 return true;
             }
         }
@@ -121,10 +231,30 @@ impl Phase for PhaseWithSystems {
         self.add_pending_systems_into_execution();
     }
 
-    fn run(&mut self, is_focused: bool) -> bool {
+    fn run(&mut self, is_focused: bool, job_handler: &JobHandlerRw, time: &Time) -> bool {
         self.remove_pending_systems_from_execution()
-            .add_pending_systems_into_execution()
-            .execute_systems(is_focused)
+            .add_pending_systems_into_execution();
+
+        match self.mode {
+            PhaseMode::PerFrame => self.execute_systems(is_focused, job_handler, None),
+            PhaseMode::FixedStep { max_steps } => {
+                let fixed_delta = time.get_fixed_delta();
+                self.accumulated_time += time.get_delta();
+                let mut can_continue = true;
+                let mut steps = 0;
+                while self.accumulated_time >= fixed_delta && steps < max_steps {
+                    can_continue &=
+                        self.execute_systems(is_focused, job_handler, Some(fixed_delta));
+                    self.accumulated_time -= fixed_delta;
+                    steps += 1;
+                }
+                if steps == max_steps {
+                    //Drop the steps we were not able to catch up
+                    self.accumulated_time %= fixed_delta;
+                }
+                can_continue
+            }
+        }
     }
 
     fn uninit(&mut self) {
@@ -132,6 +262,7 @@ impl Phase for PhaseWithSystems {
             .remove_pending_systems_from_execution();
 
         self.systems_running.clear();
+        self.systems_batches.clear();
     }
 }
 
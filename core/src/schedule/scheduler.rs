@@ -3,7 +3,7 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crate::{JobHandler, Phase, PhaseWithSystems};
+use crate::{JobHandler, Phase, PhaseWithSystems, Time};
 
 pub struct Scheduler {
     is_running: bool,
@@ -31,6 +31,10 @@ impl Scheduler {
         }
     }
 
+    pub fn is_started(&self) -> bool {
+        self.is_started
+    }
+
     pub fn resume(&mut self) {
         self.is_running = true;
     }
@@ -176,7 +180,12 @@ impl Scheduler {
             })
     }
 
-    pub fn run_once(&mut self, is_focused: bool, job_handler: Arc<RwLock<JobHandler>>) -> bool {
+    pub fn run_once(
+        &mut self,
+        is_focused: bool,
+        job_handler: Arc<RwLock<JobHandler>>,
+        time: &Time,
+    ) -> bool {
         if !self.is_started {
             return self.is_running;
         }
@@ -188,15 +197,20 @@ impl Scheduler {
                     nrg_profiler::scoped_profile!(
                         format!("{}[{}]", "scheduler::run_phase", name).as_str()
                     );
-                    let ok = phase.run(is_focused);
+                    let ok = phase.run(is_focused, &job_handler, time);
                     {
                         nrg_profiler::scoped_profile!(format!(
                             "{}[{}]",
                             "scheduler::wait_jobs", name
                         )
                         .as_str());
-                        while job_handler.read().unwrap().has_pending_jobs() {
-                            thread::yield_now();
+                        //Jobs can add other jobs of the same frame while running
+                        loop {
+                            let jobs = job_handler.write().unwrap().take_frame_jobs();
+                            if jobs.is_empty() {
+                                break;
+                            }
+                            jobs.iter().for_each(|j| j.wait());
                         }
                     }
                     ok
@@ -1,4 +1,9 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    any::TypeId,
+    collections::HashSet,
+    sync::{Arc, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct SystemId(pub u64);
@@ -19,12 +24,58 @@ impl SystemId {
     }
 }
 
+// Resource types a System reads and writes while running.
+// An exclusive access conflicts with every other System.
+#[derive(Debug, Default, Clone)]
+pub struct SystemAccess {
+    is_exclusive: bool,
+    reads: HashSet<TypeId>,
+    writes: HashSet<TypeId>,
+}
+
+impl SystemAccess {
+    #[inline]
+    pub fn exclusive() -> Self {
+        Self {
+            is_exclusive: true,
+            ..Default::default()
+        }
+    }
+    #[inline]
+    pub fn read<T: 'static>(&mut self) -> &mut Self {
+        self.reads.insert(TypeId::of::<T>());
+        self
+    }
+    #[inline]
+    pub fn write<T: 'static>(&mut self) -> &mut Self {
+        self.writes.insert(TypeId::of::<T>());
+        self
+    }
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        self.is_exclusive
+    }
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        if self.is_exclusive || other.is_exclusive {
+            return true;
+        }
+        self.writes
+            .iter()
+            .any(|t| other.writes.contains(t) || other.reads.contains(t))
+            || other.writes.iter().any(|t| self.reads.contains(t))
+    }
+}
+
 pub trait System: Send + Sync {
     fn id(&self) -> SystemId;
     fn should_run_when_not_focused(&self) -> bool;
+    fn access(&self) -> SystemAccess {
+        SystemAccess::exclusive()
+    }
     fn init(&mut self);
     fn run(&mut self) -> bool;
     fn uninit(&mut self);
 }
 
 pub type SystemBoxed = Box<dyn System>;
+pub type SystemRw = Arc<RwLock<SystemBoxed>>;
@@ -1,13 +1,17 @@
 use std::{
-    sync::{mpsc::Receiver, Arc, Mutex, RwLock},
+    sync::{Arc, RwLock},
     thread::{self, JoinHandle},
 };
 
-use crate::{Job, JobHandler, Phase, Scheduler};
+use nrg_resources::ResourceRef;
+
+use crate::{JobHandler, JobQueue, Phase, Scheduler, Time};
 
 pub struct Worker {
     scheduler: Arc<RwLock<Scheduler>>,
     thread_handle: Option<JoinHandle<bool>>,
+    job_queue: Option<Arc<JobQueue>>,
+    queue_index: usize,
 }
 
 impl Default for Worker {
@@ -15,6 +19,8 @@ impl Default for Worker {
         Self {
             scheduler: Arc::new(RwLock::new(Scheduler::new())),
             thread_handle: None,
+            job_queue: None,
+            queue_index: 0,
         }
     }
 }
@@ -29,39 +35,42 @@ impl Worker {
     pub fn is_started(&self) -> bool {
         self.thread_handle.is_some()
     }
-    fn get_job(receiver: &Arc<Mutex<Receiver<Job>>>) -> Option<Job> {
-        let recv = receiver.lock().unwrap();
-        if let Ok(job) = recv.try_recv() {
-            drop(recv);
-            return Some(job);
-        }
-        None
-    }
-
     pub fn start(
         &mut self,
         name: &str,
         job_handler: Arc<RwLock<JobHandler>>,
-        receiver: Arc<Mutex<Receiver<Job>>>,
+        job_queue: Arc<JobQueue>,
+        time: ResourceRef<Time>,
     ) {
         if self.thread_handle.is_none() {
+            if self.job_queue.is_none() {
+                self.queue_index = job_queue.add_worker();
+                self.job_queue = Some(job_queue.clone());
+            }
+            let queue_index = self.queue_index;
+            //Resume before spawning or a stop() could happen before the thread starts running
+            self.scheduler.write().unwrap().resume();
             let builder = thread::Builder::new().name(name.into());
             let scheduler = Arc::clone(&self.scheduler);
             let t = builder
                 .spawn(move || {
                     nrg_profiler::register_thread!();
-                    scheduler.write().unwrap().resume();
+                    job_queue.set_current_worker(queue_index);
                     loop {
-                        let can_continue = scheduler
-                            .write()
-                            .unwrap()
-                            .run_once(true, job_handler.clone());
-                        if let Some(job) = Worker::get_job(&receiver) {
-                            job.execute();
+                        let (can_continue, has_phases) = {
+                            let time = time.resource().unwrap().get().clone();
+                            let mut scheduler = scheduler.write().unwrap();
+                            let can_continue = scheduler.run_once(true, job_handler.clone(), &time);
+                            (can_continue, scheduler.is_started())
+                        };
+                        if let Some(job) = job_queue.pop(queue_index) {
+                            job_queue.execute(job);
+                        } else if !has_phases {
+                            job_queue.park();
                         }
                         if !can_continue {
-                            while let Some(job) = Worker::get_job(&receiver) {
-                                job.execute();
+                            while let Some(job) = job_queue.pop(queue_index) {
+                                job_queue.execute(job);
                             }
                             return false;
                         }
@@ -77,6 +86,9 @@ impl Worker {
             let t = self.thread_handle.take().unwrap();
 
             self.scheduler.write().unwrap().cancel();
+            if let Some(job_queue) = &self.job_queue {
+                job_queue.wake_all();
+            }
             t.join().unwrap();
 
             self.thread_handle = None;
@@ -0,0 +1,457 @@
+#![allow(dead_code)]
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use nrg_messenger::Message;
+use nrg_platform::{InputState, Key, KeyEvent};
+use nrg_resources::{
+    DataTypeResource, Deserializable, FileResource, LoadState, ResourceData, ResourceId,
+    ResourceRef, SerializableResource, SharedData, SharedDataRw,
+};
+use nrg_serialize::{generate_uid_from_string, Deserialize, Serialize, Versioned};
+
+use crate::*;
+
+const HEADLESS_PHASE: &str = "HEADLESS_PHASE";
+
+struct TextFile {
+    id: ResourceId,
+    path: PathBuf,
+    content: String,
+}
+
+impl ResourceData for TextFile {
+    fn id(&self) -> ResourceId {
+        self.id
+    }
+}
+
+impl FileResource for TextFile {
+    fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+    fn create_from_file(shared_data: &SharedDataRw, filepath: &Path) -> ResourceRef<Self> {
+        Self::try_create_from_file(shared_data, filepath).unwrap()
+    }
+    fn try_create_from_file(
+        shared_data: &SharedDataRw,
+        filepath: &Path,
+    ) -> std::io::Result<ResourceRef<Self>> {
+        let content = std::fs::read_to_string(filepath)?;
+        Ok(SharedData::add_resource(
+            shared_data,
+            TextFile {
+                id: generate_uid_from_string(filepath.to_str().unwrap()),
+                path: filepath.to_path_buf(),
+                content,
+            },
+        ))
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize", default)]
+struct NoteData {
+    path: PathBuf,
+    text: String,
+}
+
+impl Versioned for NoteData {
+    const VERSION: u32 = 0;
+}
+
+impl Deserializable for NoteData {
+    fn set_path(&mut self, filepath: &Path) {
+        self.path = filepath.to_path_buf();
+    }
+    fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
+
+struct Note {
+    id: ResourceId,
+    path: PathBuf,
+    text: String,
+}
+
+impl ResourceData for Note {
+    fn id(&self) -> ResourceId {
+        self.id
+    }
+}
+
+impl DataTypeResource for Note {
+    type DataType = NoteData;
+    fn create_from_data(shared_data: &SharedDataRw, data: NoteData) -> ResourceRef<Self> {
+        SharedData::add_resource(
+            shared_data,
+            Note {
+                id: generate_uid_from_string(data.path.to_str().unwrap()),
+                path: data.path,
+                text: data.text,
+            },
+        )
+    }
+}
+
+impl SerializableResource for Note {
+    fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
+
+struct CounterSystem {
+    id: SystemId,
+    counter: Arc<AtomicU32>,
+}
+
+impl CounterSystem {
+    fn new(counter: &Arc<AtomicU32>) -> Self {
+        Self {
+            id: SystemId::new(),
+            counter: counter.clone(),
+        }
+    }
+}
+
+impl System for CounterSystem {
+    fn id(&self) -> SystemId {
+        self.id
+    }
+    fn should_run_when_not_focused(&self) -> bool {
+        false
+    }
+    fn init(&mut self) {}
+    fn run(&mut self) -> bool {
+        self.counter.fetch_add(1, Ordering::SeqCst);
+        true
+    }
+    fn uninit(&mut self) {}
+}
+
+struct DeltaSystem {
+    id: SystemId,
+    shared_data: SharedDataRw,
+    deltas: Arc<RwLock<Vec<f32>>>,
+}
+
+impl DeltaSystem {
+    fn new(shared_data: &SharedDataRw, deltas: &Arc<RwLock<Vec<f32>>>) -> Self {
+        Self {
+            id: SystemId::new(),
+            shared_data: shared_data.clone(),
+            deltas: deltas.clone(),
+        }
+    }
+}
+
+impl System for DeltaSystem {
+    fn id(&self) -> SystemId {
+        self.id
+    }
+    fn should_run_when_not_focused(&self) -> bool {
+        false
+    }
+    fn init(&mut self) {}
+    fn run(&mut self) -> bool {
+        let time = Time::get_time(&self.shared_data);
+        let delta = time.resource().unwrap().get().get_delta();
+        self.deltas.write().unwrap().push(delta);
+        true
+    }
+    fn uninit(&mut self) {}
+}
+
+#[test]
+fn test_headless_app() {
+    let mut app = App::new_headless();
+    let counter = Arc::new(AtomicU32::new(0));
+
+    let mut phase = PhaseWithSystems::new(HEADLESS_PHASE);
+    phase.add_system(CounterSystem::new(&counter));
+    app.create_phase(phase);
+
+    assert!(app.run_frames(10));
+    assert_eq!(counter.load(Ordering::SeqCst), 10);
+    assert_eq!(
+        app.get_time().resource().unwrap().get().get_frame_index(),
+        10
+    );
+
+    assert!(app.run_until(|_| counter.load(Ordering::SeqCst) >= 15, 100));
+    assert_eq!(counter.load(Ordering::SeqCst), 15);
+
+    assert!(!app.run_until(|_| false, 5));
+
+    app.destroy_phase(HEADLESS_PHASE);
+}
+
+#[test]
+fn test_headless_fixed_step_phase() {
+    let mut app = App::new_headless();
+    let fixed_delta = app.get_time().resource().unwrap().get().get_fixed_delta();
+    app.set_fixed_frame_delta(Some(fixed_delta * 3.));
+
+    let counter = Arc::new(AtomicU32::new(0));
+    let deltas = Arc::new(RwLock::new(Vec::new()));
+    let mut phase = PhaseWithSystems::new(HEADLESS_PHASE);
+    phase
+        .set_mode(PhaseMode::FixedStep { max_steps: 2 })
+        .add_system(CounterSystem::new(&counter));
+    app.create_phase(phase);
+
+    assert!(app.run_frames(4));
+    assert_eq!(counter.load(Ordering::SeqCst), 8);
+    app.destroy_phase(HEADLESS_PHASE);
+
+    //A cap of zero steps still runs one step per frame
+    let mut phase = PhaseWithSystems::new(HEADLESS_PHASE);
+    phase.set_mode(PhaseMode::FixedStep { max_steps: 0 });
+    assert_eq!(phase.get_mode(), PhaseMode::FixedStep { max_steps: 1 });
+    phase
+        .add_system(CounterSystem::new(&counter))
+        .add_system(DeltaSystem::new(&app.get_shared_data(), &deltas));
+    app.create_phase(phase);
+
+    assert!(app.run_frames(2));
+    assert_eq!(counter.load(Ordering::SeqCst), 10);
+    //Systems see the fixed delta of the step instead of the frame delta
+    assert_eq!(*deltas.read().unwrap(), vec![fixed_delta, fixed_delta]);
+
+    app.destroy_phase(HEADLESS_PHASE);
+}
+
+#[test]
+fn test_plugin_version_requirements() {
+    let version = PluginVersion::parse("0.1.3").unwrap();
+    assert_eq!(version, PluginVersion::new(0, 1, 3));
+    assert!(PluginVersion::parse("1.a").is_none());
+
+    assert!(VersionRequirement::parse("^0.1").unwrap().matches(&version));
+    assert!(VersionRequirement::parse("0.1.2").unwrap().matches(&version));
+    assert!(!VersionRequirement::parse("^0.2").unwrap().matches(&version));
+    assert!(VersionRequirement::parse(">=0.1").unwrap().matches(&version));
+    assert!(!VersionRequirement::parse("<0.1.3").unwrap().matches(&version));
+    assert!(VersionRequirement::parse("*").unwrap().matches(&version));
+
+    let mut manifest = PluginManifest::new("nrg_game", "0.1.0");
+    manifest.depends_on("nrg_window", "^0.1");
+    assert!(manifest.is_dependent_on("nrg_window"));
+    assert!(!manifest.is_dependent_on("nrg_gui"));
+}
+
+#[test]
+fn test_plugin_not_found() {
+    let mut app = App::new_headless();
+
+    let path = std::env::temp_dir().join("nrg_missing_plugin.dll");
+    let result = app.add_plugin(path.clone());
+    assert_eq!(result, Err(PluginError::NotFound(path)));
+
+    let abi = PluginAbi::current();
+    assert!(abi.is_compatible_with(&PluginAbi::current()));
+    let mut other_abi = abi;
+    other_abi.abi_version += 1;
+    assert!(!abi.is_compatible_with(&other_abi));
+    let mut other_abi = abi;
+    other_abi.rustc_version = AbiString::new("rustc 1.0.0");
+    assert!(!abi.is_compatible_with(&other_abi));
+    assert!(abi.rustc_version.as_str().starts_with("rustc"));
+    assert!(abi.to_string().contains(abi.rustc_version.as_str()));
+}
+
+#[test]
+fn test_async_resource_load() {
+    let app = App::new_headless();
+    let shared_data = app.get_shared_data();
+    let job_handler = app.get_job_handler();
+    let global_messenger = app.get_global_messenger();
+
+    let path = std::env::temp_dir().join("nrg_async_load_test.txt");
+    std::fs::write(&path, "content").unwrap();
+    let (load, job) =
+        TextFile::create_from_file_async(&shared_data, &job_handler, &global_messenger, &path);
+    job.wait();
+    assert_eq!(load.state(), LoadState::Loaded);
+    let handle = load.handle().unwrap();
+    assert_eq!(handle.resource().unwrap().get().content, "content");
+    std::fs::remove_file(&path).ok();
+
+    let missing_path = std::env::temp_dir().join("nrg_async_load_missing.txt");
+    let (load, job) = TextFile::create_from_file_async(
+        &shared_data,
+        &job_handler,
+        &global_messenger,
+        &missing_path,
+    );
+    job.wait();
+    assert!(matches!(load.state(), LoadState::Failed(_)));
+    assert!(load.handle().is_none());
+
+    //Files that can't be read fail the load with the error of the resource
+    let invalid_path = std::env::temp_dir().join("nrg_async_load_invalid.txt");
+    std::fs::write(&invalid_path, [0xff, 0xfe, 0xfd]).unwrap();
+    let (load, job) = TextFile::create_from_file_async(
+        &shared_data,
+        &job_handler,
+        &global_messenger,
+        &invalid_path,
+    );
+    job.wait();
+    assert!(matches!(load.state(), LoadState::Failed(e) if e.contains("UTF-8")));
+    std::fs::remove_file(&invalid_path).ok();
+    assert!(SharedData::has_resource::<TextFile>(&shared_data, handle.id()));
+}
+
+#[test]
+fn test_async_resource_load_failure() {
+    let app = App::new_headless();
+    let shared_data = app.get_shared_data();
+    let job_handler = app.get_job_handler();
+    let global_messenger = app.get_global_messenger();
+
+    let path = std::env::temp_dir().join("nrg_async_load_note.json");
+    std::fs::write(&path, r#"{ "text": "note" }"#).unwrap();
+    let (load, job) =
+        Note::create_from_file_async(&shared_data, &job_handler, &global_messenger, &path);
+    job.wait();
+    assert_eq!(load.state(), LoadState::Loaded);
+    let handle = load.handle().unwrap();
+    assert_eq!(handle.resource().unwrap().get().text, "note");
+
+    //A corrupted file fails the load instead of creating a default resource
+    std::fs::write(&path, r#"{ "text": "#).unwrap();
+    let (load, job) =
+        Note::create_from_file_async(&shared_data, &job_handler, &global_messenger, &path);
+    job.wait();
+    assert!(matches!(load.state(), LoadState::Failed(_)));
+    assert!(load.handle().is_none());
+    std::fs::remove_file(&path).ok();
+
+    //Shared data is still usable after the failure
+    assert!(SharedData::has_resource::<Note>(&shared_data, handle.id()));
+}
+
+#[test]
+fn test_job_panic() {
+    let app = App::new_headless();
+    let job_handler = app.get_job_handler();
+
+    let failing = job_handler
+        .write()
+        .unwrap()
+        .add_job("failing_job", || -> u32 { panic!("Job failure") });
+    let dependent = job_handler.write().unwrap().add_job_with_dependencies(
+        "dependent_job",
+        JobPriority::Normal,
+        vec![failing.dependency()],
+        || 42,
+    );
+    failing.wait();
+    assert!(failing.is_done());
+    assert_eq!(failing.take_result(), None);
+    assert_eq!(dependent.wait_result(), Some(42));
+}
+
+#[test]
+fn test_job_dependencies() {
+    let app = App::new_headless();
+    let job_handler = app.get_job_handler();
+
+    let counter = Arc::new(AtomicU32::new(0));
+    let first_counter = counter.clone();
+    let first = job_handler.write().unwrap().add_job("first_job", move || {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        first_counter.fetch_add(1, Ordering::SeqCst)
+    });
+    let second_counter = counter.clone();
+    let second = job_handler.write().unwrap().add_job_with_dependencies(
+        "second_job",
+        JobPriority::High,
+        vec![first.dependency()],
+        move || second_counter.fetch_add(1, Ordering::SeqCst),
+    );
+    assert_eq!(second.wait_result(), Some(1));
+    assert_eq!(first.wait_result(), Some(0));
+}
+
+#[test]
+fn test_job_wait_inside_job() {
+    let app = App::new_headless();
+    let job_handler = app.get_job_handler();
+
+    //More jobs waiting than workers: the waiting ones run the queued jobs meanwhile
+    let outer_jobs: Vec<_> = (0..16)
+        .map(|i| {
+            let inner_job_handler = job_handler.clone();
+            job_handler.write().unwrap().add_job("outer_job", move || {
+                let inner = inner_job_handler
+                    .write()
+                    .unwrap()
+                    .add_job("inner_job", move || i * 2);
+                inner.wait_result().unwrap()
+            })
+        })
+        .collect();
+    //Inner jobs belong to the frame of the thread that added the outer ones
+    loop {
+        let frame_jobs = job_handler.write().unwrap().take_frame_jobs();
+        if frame_jobs.is_empty() {
+            break;
+        }
+        frame_jobs.iter().for_each(|j| j.wait());
+    }
+    assert!(outer_jobs.iter().all(|j| j.is_done()));
+    let results: Vec<u32> = outer_jobs
+        .into_iter()
+        .map(|j| j.wait_result().unwrap())
+        .collect();
+    assert_eq!(results, (0..16).map(|i| i * 2).collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_record_and_replay() {
+    let path = std::env::temp_dir().join("nrg_test_recording.rec");
+    let key = KeyEvent {
+        code: Key::A,
+        state: InputState::JustPressed,
+    };
+
+    let mut app = App::new_headless();
+    app.start_recording(path.clone());
+    assert!(app.run_frames(1));
+    let dispatcher = app.get_global_messenger().read().unwrap().get_dispatcher();
+    dispatcher.write().unwrap().send(key.as_boxed()).ok();
+    assert!(app.run_frames(2));
+    //Completed frames are already on disk while recording
+    assert_eq!(MessageRecording::load(&path).unwrap().frames.len(), 2);
+    app.stop_recording();
+    drop(app);
+
+    let recording = MessageRecording::load(&path).unwrap();
+    assert_eq!(recording.frames.len(), 3);
+    assert_eq!(recording.frames[1].events.len(), 1);
+
+    let mut app = App::new_headless();
+    let key_events = app
+        .get_global_messenger()
+        .write()
+        .unwrap()
+        .subscribe::<KeyEvent>();
+    app.start_replay(path.clone());
+    assert!(app.is_replaying());
+    assert!(app.run_until(|app| !app.is_replaying(), 10));
+    assert_eq!(key_events.read_all(), vec![key]);
+
+    std::fs::write(&path, "{ not a recording\n{}\n").unwrap();
+    app.start_replay(path.clone());
+    assert!(!app.is_replaying());
+    std::fs::remove_file(&path).ok();
+}
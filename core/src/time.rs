@@ -0,0 +1,123 @@
+use std::{cell::Cell, time::Instant};
+
+use nrg_resources::{ResourceData, ResourceId, ResourceRef, SharedData, SharedDataRw};
+use nrg_serialize::generate_uid_from_string;
+
+const TIME_RESOURCE_NAME: &str = "Time";
+const DEFAULT_FIXED_DELTA: f32 = 1. / 60.;
+// Avoid huge steps after a breakpoint or while the window is hidden
+const MAX_FRAME_DELTA: f32 = 0.25;
+
+thread_local! {
+    //Delta of the fixed step whose systems are running on this thread
+    static FIXED_STEP_DELTA: Cell<Option<f32>> = const { Cell::new(None) };
+}
+
+// While alive Time::get_delta() reports the fixed delta on this thread,
+// so that systems of a fixed step phase advance by the same amount every step
+pub(crate) struct FixedStepScope(Option<f32>);
+
+impl FixedStepScope {
+    pub fn new(fixed_delta: f32) -> Self {
+        Self(FIXED_STEP_DELTA.with(|d| d.replace(Some(fixed_delta))))
+    }
+}
+
+impl Drop for FixedStepScope {
+    fn drop(&mut self) {
+        FIXED_STEP_DELTA.with(|d| d.set(self.0));
+    }
+}
+
+#[derive(Clone)]
+pub struct Time {
+    id: ResourceId,
+    last_update: Instant,
+    delta: f32,
+    unscaled_delta: f32,
+    fixed_delta: f32,
+    total: f32,
+    frame_index: u64,
+    time_scale: f32,
+}
+
+impl ResourceData for Time {
+    fn id(&self) -> ResourceId {
+        self.id
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self {
+            id: generate_uid_from_string(TIME_RESOURCE_NAME),
+            last_update: Instant::now(),
+            delta: 0.,
+            unscaled_delta: 0.,
+            fixed_delta: DEFAULT_FIXED_DELTA,
+            total: 0.,
+            frame_index: 0,
+            time_scale: 1.,
+        }
+    }
+}
+
+impl Time {
+    #[inline]
+    pub fn get_time(shared_data: &SharedDataRw) -> ResourceRef<Time> {
+        SharedData::get_resource::<Time>(shared_data, generate_uid_from_string(TIME_RESOURCE_NAME))
+    }
+
+    pub fn update(&mut self) -> &mut Self {
+        let unscaled_delta = Instant::now()
+            .duration_since(self.last_update)
+            .as_secs_f32()
+            .min(MAX_FRAME_DELTA);
+        self.update_with_delta(unscaled_delta)
+    }
+
+    pub fn update_with_delta(&mut self, unscaled_delta: f32) -> &mut Self {
+        self.last_update = Instant::now();
+        self.unscaled_delta = unscaled_delta;
+        self.delta = self.unscaled_delta * self.time_scale;
+        self.total += self.delta;
+        self.frame_index += 1;
+        self
+    }
+
+    // Fixed delta inside the systems of a fixed step phase, scaled frame delta otherwise
+    #[inline]
+    pub fn get_delta(&self) -> f32 {
+        FIXED_STEP_DELTA.with(|d| d.get()).unwrap_or(self.delta)
+    }
+    #[inline]
+    pub fn get_unscaled_delta(&self) -> f32 {
+        self.unscaled_delta
+    }
+    #[inline]
+    pub fn get_fixed_delta(&self) -> f32 {
+        self.fixed_delta
+    }
+    #[inline]
+    pub fn set_fixed_delta(&mut self, fixed_delta: f32) -> &mut Self {
+        self.fixed_delta = fixed_delta.max(f32::EPSILON);
+        self
+    }
+    #[inline]
+    pub fn get_total(&self) -> f32 {
+        self.total
+    }
+    #[inline]
+    pub fn get_frame_index(&self) -> u64 {
+        self.frame_index
+    }
+    #[inline]
+    pub fn get_time_scale(&self) -> f32 {
+        self.time_scale
+    }
+    #[inline]
+    pub fn set_time_scale(&mut self, time_scale: f32) -> &mut Self {
+        self.time_scale = time_scale.max(0.);
+        self
+    }
+}
@@ -33,7 +33,7 @@ fn link_library(name: &str) {
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir)
-        .join("..\\..\\..\\")
+        .join("../../../")
         .canonicalize()
         .unwrap();
     let mut deps_path = Path::new(&out_dir).join("deps");
@@ -50,11 +50,14 @@ fn main() {
     // Deterimine build platform
     let target_os = ::std::env::var("CARGO_CFG_TARGET_OS").unwrap();
     let is_windows_platform = target_os == "windows";
+    let is_linux_platform = target_os == "linux";
 
     if is_windows_platform {
         link_library("user32");
         link_library("kernel32");
         link_library("shcore");
+    } else if is_linux_platform {
+        link_library("dl");
     } else {
         panic!("Platform not yet supported - Check build.rs to setup this platform to build from source");
     }
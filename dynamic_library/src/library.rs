@@ -32,8 +32,23 @@ pub struct Library(platform::Library);
 impl Library {
     #[inline]
     pub fn new<S: AsRef<::std::ffi::OsStr>>(filename: S) -> Library{
-        let _lib = platform::Library::load(filename);
-        Library(_lib)
+        match Library::load(filename) {
+            Ok(lib) => lib,
+            Err(error) => {
+                eprintln!("{}", error);
+                Library(platform::Library::invalid())
+            }
+        }
+    }
+
+    #[inline]
+    pub fn load<S: AsRef<::std::ffi::OsStr>>(filename: S) -> Result<Library, String> {
+        platform::Library::load(filename).map(Library)
+    }
+
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.0.is_valid()
     }
 
     #[inline]
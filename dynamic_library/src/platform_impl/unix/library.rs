@@ -0,0 +1,104 @@
+#![allow(bad_style, dead_code)]
+
+use std::{
+    ffi::*,
+    os::{
+        raw::{c_char, c_int},
+        unix::ffi::*,
+    },
+};
+
+pub const RTLD_LAZY: c_int = 0x1;
+pub const RTLD_NOW: c_int = 0x2;
+pub const RTLD_LOCAL: c_int = 0;
+
+extern "C" {
+    pub fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+    pub fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    pub fn dlclose(handle: *mut c_void) -> c_int;
+    pub fn dlerror() -> *mut c_char;
+}
+
+fn last_error() -> String {
+    let error = unsafe { dlerror() };
+    if error.is_null() {
+        String::from("unknown error")
+    } else {
+        unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+pub struct Library(*mut c_void);
+
+unsafe impl Send for Library {}
+unsafe impl Sync for Library {}
+
+impl Library {
+    #[inline]
+    pub fn load<S: AsRef<OsStr>>(filename: S) -> Result<Library, String> {
+        let c_filename = match CString::new(filename.as_ref().as_bytes()) {
+            Ok(c_filename) => c_filename,
+            Err(_) => {
+                return Err(format!(
+                    "Unable to load library {} with an invalid path",
+                    filename.as_ref().to_string_lossy()
+                ))
+            }
+        };
+        let handle = unsafe { dlopen(c_filename.as_ptr(), RTLD_NOW | RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(format!(
+                "Unable to load library {} and received error {}",
+                filename.as_ref().to_string_lossy(),
+                last_error()
+            ));
+        }
+        Ok(Library(handle))
+    }
+    #[inline]
+    pub fn invalid() -> Library {
+        Library(std::ptr::null_mut())
+    }
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        !self.0.is_null()
+    }
+    #[inline]
+    pub fn get<T>(&self, symbol: &str) -> Option<T> {
+        if !self.is_valid() {
+            return None;
+        }
+        unsafe {
+            let fn_name = CString::new(symbol).ok()?;
+            //Clear any previous error so that only the one of this dlsym is reported
+            dlerror();
+            let ret = dlsym(self.0, fn_name.as_ptr());
+            if ret.is_null() {
+                return None;
+            }
+            Some(::std::mem::transmute_copy(&ret))
+        }
+    }
+
+    #[inline]
+    pub fn close(&mut self) {
+        if self.is_valid() {
+            let res = unsafe { dlclose(self.0) };
+            if res != 0 {
+                eprintln!(
+                    "Unable to close library and received error {}",
+                    last_error()
+                );
+            }
+            self.0 = std::ptr::null_mut();
+        }
+    }
+}
+impl Drop for Library {
+    #[inline]
+    fn drop(&mut self) {
+        self.close();
+    }
+}
@@ -1,5 +1,5 @@
 #![cfg(target_family = "unix")]
 
-pub use super::*;
+pub use library::*;
 
-pub mod unix_handle;
\ No newline at end of file
+pub mod library;
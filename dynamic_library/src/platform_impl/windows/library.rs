@@ -46,24 +46,34 @@ pub struct Library(HMODULE);
 
 impl Library {
     #[inline]
-    pub fn load<S: AsRef<OsStr>>(filename: S) -> Library {
+    pub fn load<S: AsRef<OsStr>>(filename: S) -> Result<Library, String> {
         let wide_filename: Vec<u16> = filename.as_ref().encode_wide().chain(Some(0)).collect();
         let handle = unsafe { LoadLibraryExW(wide_filename.as_ptr(), std::ptr::null_mut(), 0) };
         if handle.is_null() {
             let error = unsafe { GetLastError() };
-            eprintln!(
+            return Err(format!(
                 "Unable to load library {} and received error {}",
-                filename.as_ref().to_str().unwrap(),
+                filename.as_ref().to_string_lossy(),
                 error
-            );
+            ));
         }
-        drop(wide_filename);
-        Library(handle)
+        Ok(Library(handle))
+    }
+    #[inline]
+    pub fn invalid() -> Library {
+        Library(std::ptr::null_mut())
+    }
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        !self.0.is_null()
     }
     #[inline]
     pub fn get<T>(&self, symbol: &str) -> Option<T> {
+        if !self.is_valid() {
+            return None;
+        }
         unsafe {
-            let fn_name = CString::new(symbol).unwrap();
+            let fn_name = CString::new(symbol).ok()?;
             let ret = GetProcAddress(self.0, fn_name.as_ptr());
             if ret.is_null() {
                 return None;
@@ -0,0 +1,16 @@
+use crate::*;
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_load_system_library() {
+    type PfnCos = ::std::option::Option<unsafe extern "C" fn(f64) -> f64>;
+
+    let lib = Library::load("libm.so.6").unwrap();
+    assert!(lib.is_valid());
+    let cos = lib.get::<PfnCos>("cos").unwrap().unwrap();
+    assert_eq!(unsafe { cos(0.) }, 1.);
+    assert!(lib.get::<PfnCos>("missing_symbol").is_none());
+    assert!(lib.get::<PfnCos>("invalid\0symbol").is_none());
+
+    assert!(Library::load("libmissing_library.so").is_err());
+}
@@ -1,6 +1,19 @@
-use std::any::TypeId;
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    path::Path,
+    time::{Duration, Instant},
+};
 
-use nrg_messenger::{Message, MessageBox, MessageChannel, MessengerRw};
+use nrg_messenger::{
+    implement_message, new_message_origin, Message, MessageBox, MessageChannel, MessageOrigin,
+    MessengerRw, SerializableMessage,
+};
+
+use crate::{HistoryData, HistoryEntryData, JournalAction, JournalRecord, SessionJournal};
+
+pub const DEFAULT_HISTORY_SIZE: usize = 256;
+pub const DEFAULT_MERGE_WINDOW: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventsHistoryOperation {
@@ -8,61 +21,274 @@ pub enum EventsHistoryOperation {
     Redo,
     Clear,
 }
+
+// Sent through the global messenger so that transactions stay in order
+// with the undoable events sent between their begin and commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionEvent {
+    Begin(String),
+    Commit,
+    Abort,
+}
+implement_message!(TransactionEvent);
+
+type ToSerializableMessage = fn(&dyn Message) -> Option<Box<dyn SerializableMessage>>;
+
+fn to_serializable_message<T>(msg: &dyn Message) -> Option<Box<dyn SerializableMessage>>
+where
+    T: SerializableMessage + Clone,
+{
+    msg.as_any()
+        .downcast_ref::<T>()
+        .map(|msg| Box::new(msg.clone()) as Box<dyn SerializableMessage>)
+}
+
+// A single undo step: all its events are undone and redone together
+struct HistoryEntry {
+    name: Option<String>,
+    events: Vec<Box<dyn Message>>,
+}
+
+impl HistoryEntry {
+    fn description(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self
+                .events
+                .iter()
+                .map(|e| e.get_type_name())
+                .collect::<Vec<String>>()
+                .join(", "),
+        }
+    }
+
+    fn to_data(
+        &self,
+        persistent_types: &HashMap<TypeId, ToSerializableMessage>,
+    ) -> Option<HistoryEntryData> {
+        let events = self
+            .events
+            .iter()
+            .map(|e| {
+                persistent_types
+                    .get(&e.as_ref().type_id())
+                    .and_then(|f| f(e.as_ref()))
+            })
+            .collect::<Option<Vec<Box<dyn SerializableMessage>>>>()?;
+        Some(HistoryEntryData {
+            name: self.name.clone(),
+            events,
+        })
+    }
+
+    fn from_data(data: HistoryEntryData) -> Self {
+        Self {
+            name: data.name,
+            events: data.events.iter().map(|e| e.as_boxed()).collect(),
+        }
+    }
+}
+
+// Undo and redo pop from the end: only the steps above the last one that can't be saved are kept
+fn history_to_data(
+    entries: &[HistoryEntry],
+    persistent_types: &HashMap<TypeId, ToSerializableMessage>,
+) -> Vec<HistoryEntryData> {
+    let mut data: Vec<HistoryEntryData> = entries
+        .iter()
+        .rev()
+        .map_while(|e| e.to_data(persistent_types))
+        .collect();
+    data.reverse();
+    data
+}
+
+struct Transaction {
+    name: String,
+    events: Vec<Box<dyn Message>>,
+    depth: usize,
+}
+
 pub struct EventsHistory {
-    undoable_events: Vec<Box<dyn Message>>,
-    redoable_events: Vec<Box<dyn Message>>,
+    undoable_events: Vec<HistoryEntry>,
+    redoable_events: Vec<HistoryEntry>,
     operations: Vec<EventsHistoryOperation>,
     registered_event_types: Vec<TypeId>,
     events_dispatcher: Option<MessageBox>,
+    global_messenger: Option<MessengerRw>,
+    origin: MessageOrigin,
     message_channel: MessageChannel,
-    pending_history_event: bool,
+    replay_channel: MessageChannel,
+    transaction: Option<Transaction>,
+    max_steps: usize,
+    merge_window: Duration,
+    last_event_time: Option<Instant>,
+    persistent_types: HashMap<TypeId, ToSerializableMessage>,
+    log: Vec<JournalRecord>,
+    revision: u64,
 }
 
 impl Default for EventsHistory {
     fn default() -> Self {
         Self {
             events_dispatcher: None,
+            global_messenger: None,
+            origin: new_message_origin(),
             message_channel: MessageChannel::default(),
+            replay_channel: MessageChannel::default(),
             undoable_events: Vec::new(),
             redoable_events: Vec::new(),
             operations: Vec::new(),
             registered_event_types: Vec::new(),
-            pending_history_event: false,
+            transaction: None,
+            max_steps: DEFAULT_HISTORY_SIZE,
+            merge_window: DEFAULT_MERGE_WINDOW,
+            last_event_time: None,
+            persistent_types: HashMap::new(),
+            log: Vec::new(),
+            revision: 0,
         }
     }
 }
 
 impl EventsHistory {
+    // Events sent by undo and redo are dispatched with the origin of the history,
+    // so that they are not sent back to it and added again as new steps
+    fn replay<F>(&mut self, events: &[Box<dyn Message>], f: F)
+    where
+        F: Fn(&dyn Message, &MessageBox),
+    {
+        let replay_box = self.replay_channel.get_messagebox();
+        for e in events.iter() {
+            f(e.as_ref(), &replay_box);
+        }
+        let listener = self.replay_channel.get_listener();
+        while let Ok(msg) = listener.read().unwrap().try_recv() {
+            if let Some(global_messenger) = &self.global_messenger {
+                global_messenger.read().unwrap().send_from(self.origin, msg);
+            }
+        }
+    }
+
+    fn add_record(&mut self, action: JournalAction, description: String) {
+        self.log.push(JournalRecord::new(action, description));
+        self.revision += 1;
+    }
+
     fn process_operations(&mut self) {
-        for op in self.operations.iter() {
+        let operations = std::mem::take(&mut self.operations);
+        if !operations.is_empty() {
+            //Edits after an undo or redo never merge with the step before it
+            self.last_event_time = None;
+        }
+        for op in operations.iter() {
             match *op {
                 EventsHistoryOperation::Redo => {
-                    if !self.redoable_events.is_empty() {
-                        let mut last_event = self.redoable_events.pop().unwrap();
-                        if let Some(events_rw) = &mut self.events_dispatcher {
-                            last_event.as_mut().redo(events_rw);
-                        }
-                        self.pending_history_event = true;
-                        self.undoable_events.push(last_event);
+                    if let Some(entry) = self.redoable_events.pop() {
+                        self.replay(&entry.events, |e, events_rw| e.redo(events_rw));
+                        self.add_record(JournalAction::Redo, entry.description());
+                        self.undoable_events.push(entry);
                     }
                 }
                 EventsHistoryOperation::Undo => {
-                    if !self.undoable_events.is_empty() {
-                        let mut last_event = self.undoable_events.pop().unwrap();
-                        if let Some(events_rw) = &mut self.events_dispatcher {
-                            last_event.as_mut().undo(events_rw);
-                        }
-                        self.pending_history_event = true;
-                        self.redoable_events.push(last_event);
+                    if let Some(entry) = self.undoable_events.pop() {
+                        let events: Vec<Box<dyn Message>> =
+                            entry.events.iter().rev().map(|e| e.as_boxed()).collect();
+                        self.replay(&events, |e, events_rw| e.undo(events_rw));
+                        self.add_record(JournalAction::Undo, entry.description());
+                        self.redoable_events.push(entry);
                     }
                 }
                 EventsHistoryOperation::Clear => {
                     self.redoable_events.clear();
                     self.undoable_events.clear();
+                    self.add_record(JournalAction::Clear, String::new());
+                }
+            }
+        }
+    }
+
+    fn process_transaction_event(&mut self, event: &TransactionEvent) {
+        match event {
+            TransactionEvent::Begin(name) => match &mut self.transaction {
+                Some(transaction) => transaction.depth += 1,
+                None => {
+                    self.transaction = Some(Transaction {
+                        name: name.clone(),
+                        events: Vec::new(),
+                        depth: 1,
+                    })
+                }
+            },
+            TransactionEvent::Commit => {
+                if let Some(transaction) = &mut self.transaction {
+                    transaction.depth -= 1;
+                    if transaction.depth == 0 {
+                        let transaction = self.transaction.take().unwrap();
+                        if !transaction.events.is_empty() {
+                            self.add_entry(HistoryEntry {
+                                name: Some(transaction.name),
+                                events: transaction.events,
+                            });
+                        }
+                    }
+                }
+            }
+            TransactionEvent::Abort => {
+                if let Some(transaction) = self.transaction.take() {
+                    let events: Vec<Box<dyn Message>> =
+                        transaction.events.into_iter().rev().collect();
+                    self.replay(&events, |e, events_rw| e.undo(events_rw));
+                    self.add_record(JournalAction::Abort, transaction.name);
+                }
+            }
+        }
+    }
+
+    // Outside of transactions an event is merged with the previous step
+    // only when it comes shortly after it, like the ones sent while dragging
+    fn add_event(&mut self, msg: Box<dyn Message>) {
+        if let Some(transaction) = &mut self.transaction {
+            merge_or_push(&mut transaction.events, msg);
+            return;
+        }
+        let now = Instant::now();
+        let can_merge = self
+            .last_event_time
+            .is_some_and(|t| now.duration_since(t) <= self.merge_window);
+        self.last_event_time = Some(now);
+        if let Some(last) = self.undoable_events.last_mut().filter(|_| can_merge) {
+            if last.name.is_none() && last.events.len() == 1 {
+                if let Some(merged) = last.events[0].merge(msg.as_ref()) {
+                    last.events[0] = merged;
+                    let description = last.description();
+                    self.redoable_events.clear();
+                    //The record of the merged step describes its whole effect
+                    match self.log.last_mut() {
+                        Some(record) if record.action == JournalAction::Edit => {
+                            record.description = description;
+                            self.revision += 1;
+                        }
+                        _ => self.add_record(JournalAction::Edit, description),
+                    }
+                    return;
                 }
             }
         }
-        self.operations.clear();
+        self.add_entry(HistoryEntry {
+            name: None,
+            events: vec![msg],
+        });
+    }
+
+    fn add_entry(&mut self, entry: HistoryEntry) {
+        self.add_record(JournalAction::Edit, entry.description());
+        self.undoable_events.push(entry);
+        self.redoable_events.clear();
+        if self.undoable_events.len() > self.max_steps {
+            let count = self.undoable_events.len() - self.max_steps;
+            self.undoable_events.drain(..count);
+        }
     }
 
     pub fn register_event_as_undoable<T>(&mut self, global_messenger: &MessengerRw) -> &mut Self
@@ -75,59 +301,175 @@ impl EventsHistory {
 
             if self.events_dispatcher.is_none() {
                 self.events_dispatcher = Some(global_messenger.read().unwrap().get_dispatcher());
+                self.global_messenger = Some(global_messenger.clone());
+                global_messenger
+                    .write()
+                    .unwrap()
+                    .register_messagebox::<TransactionEvent>(self.message_channel.get_messagebox());
             }
 
             global_messenger.write().unwrap().register_type::<T>();
             global_messenger
                 .write()
                 .unwrap()
-                .register_messagebox::<T>(self.message_channel.get_messagebox());
+                .register_messagebox_from_origin::<T>(
+                    self.message_channel.get_messagebox(),
+                    self.origin,
+                );
         }
         self
     }
 
+    // Persistent events are saved with the history in the session journal
+    pub fn register_event_as_persistent<T>(&mut self, global_messenger: &MessengerRw) -> &mut Self
+    where
+        T: SerializableMessage + Clone,
+    {
+        self.register_event_as_undoable::<T>(global_messenger);
+        self.persistent_types
+            .insert(TypeId::of::<T>(), to_serializable_message::<T>);
+        self
+    }
+
+    // Oldest undo steps are forgotten when the history grows over the limit
+    pub fn set_max_steps(&mut self, max_steps: usize) -> &mut Self {
+        self.max_steps = max_steps.max(1);
+        if self.undoable_events.len() > self.max_steps {
+            let count = self.undoable_events.len() - self.max_steps;
+            self.undoable_events.drain(..count);
+        }
+        self
+    }
+
+    // Events sent within this time from the previous one are merged in the same undo step
+    pub fn set_merge_window(&mut self, merge_window: Duration) -> &mut Self {
+        self.merge_window = merge_window;
+        self
+    }
+
     pub fn push(&mut self, operation: EventsHistoryOperation) {
         self.operations.push(operation);
     }
 
+    fn send_transaction_event(&self, event: TransactionEvent) {
+        if let Some(events_rw) = &self.events_dispatcher {
+            events_rw.write().unwrap().send(event.as_boxed()).ok();
+        }
+    }
+
+    // Undoable events sent until the transaction is committed become a single undo step.
+    // Transactions can be nested: only the outermost one creates the undo step
+    #[inline]
+    pub fn begin_transaction(&self, name: &str) {
+        self.send_transaction_event(TransactionEvent::Begin(name.to_string()));
+    }
+    #[inline]
+    pub fn commit_transaction(&self) {
+        self.send_transaction_event(TransactionEvent::Commit);
+    }
+    // Undoes the events already sent inside the transaction
+    #[inline]
+    pub fn abort_transaction(&self) {
+        self.send_transaction_event(TransactionEvent::Abort);
+    }
+
+    #[inline]
+    pub fn is_in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
     pub fn update(&mut self) {
-        while let Ok(msg) = self
-            .message_channel
-            .get_listener()
-            .read()
-            .unwrap()
-            .try_recv()
-        {
-            if self.pending_history_event {
-                self.pending_history_event = false;
+        let listener = self.message_channel.get_listener();
+        while let Ok(msg) = listener.read().unwrap().try_recv() {
+            if let Some(event) = msg.as_any().downcast_ref::<TransactionEvent>() {
+                self.process_transaction_event(event);
             } else {
-                self.undoable_events.push(msg);
-                self.redoable_events.clear();
+                self.add_event(msg);
             }
         }
         self.process_operations();
     }
 
+    // Incremented every time the history changes, to know when the journal has to be saved
+    #[inline]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    #[inline]
+    pub fn log(&self) -> &[JournalRecord] {
+        &self.log
+    }
+
+    pub fn data(&self) -> HistoryData {
+        HistoryData {
+            undoable: history_to_data(&self.undoable_events, &self.persistent_types),
+            redoable: history_to_data(&self.redoable_events, &self.persistent_types),
+        }
+    }
+
+    pub fn restore(&mut self, data: HistoryData) -> &mut Self {
+        self.undoable_events = data
+            .undoable
+            .into_iter()
+            .map(HistoryEntry::from_data)
+            .collect();
+        self.redoable_events = data
+            .redoable
+            .into_iter()
+            .map(HistoryEntry::from_data)
+            .collect();
+        self.transaction = None;
+        self.last_event_time = None;
+        self.revision += 1;
+        self
+    }
+
+    pub fn journal(&self, file: &Path) -> SessionJournal {
+        SessionJournal {
+            file: file.to_path_buf(),
+            history: self.data(),
+            log: self.log.clone(),
+        }
+    }
+
+    pub fn restore_journal(&mut self, journal: SessionJournal) -> &mut Self {
+        self.log = journal.log;
+        self.restore(journal.history)
+    }
+
     pub fn get_undoable_events_history_as_string(&self) -> Option<Vec<String>> {
         if self.undoable_events.is_empty() {
             None
         } else {
-            let mut str: Vec<String> = Vec::new();
-            for c in self.undoable_events.iter() {
-                str.push(c.get_type_name());
-            }
-            Some(str)
+            Some(
+                self.undoable_events
+                    .iter()
+                    .map(|e| e.description())
+                    .collect(),
+            )
         }
     }
     pub fn get_redoable_events_history_as_string(&self) -> Option<Vec<String>> {
         if self.redoable_events.is_empty() {
             None
         } else {
-            let mut str: Vec<String> = Vec::new();
-            for c in self.redoable_events.iter() {
-                str.push(c.get_type_name());
-            }
-            Some(str)
+            Some(
+                self.redoable_events
+                    .iter()
+                    .map(|e| e.description())
+                    .collect(),
+            )
+        }
+    }
+}
+
+fn merge_or_push(events: &mut Vec<Box<dyn Message>>, msg: Box<dyn Message>) {
+    if let Some(last) = events.last_mut() {
+        if let Some(merged) = last.merge(msg.as_ref()) {
+            *last = merged;
+            return;
         }
     }
+    events.push(msg);
 }
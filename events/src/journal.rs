@@ -0,0 +1,85 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use nrg_messenger::SerializableMessage;
+use nrg_serialize::{
+    try_deserialize_from_file, try_serialize_to_file, Deserialize, Serialize, SerializeResult,
+};
+
+pub const JOURNAL_EXTENSION: &str = "journal";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "nrg_serialize")]
+pub enum JournalAction {
+    Edit,
+    Undo,
+    Redo,
+    Abort,
+    Clear,
+}
+
+// Audit log entry: what happened to the history and when, in seconds since the unix epoch
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "nrg_serialize")]
+pub struct JournalRecord {
+    pub time: u64,
+    pub action: JournalAction,
+    pub description: String,
+}
+
+impl JournalRecord {
+    pub fn new(action: JournalAction, description: String) -> Self {
+        Self {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            action,
+            description,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
+pub struct HistoryEntryData {
+    pub name: Option<String>,
+    pub events: Vec<Box<dyn SerializableMessage>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "nrg_serialize")]
+pub struct HistoryData {
+    pub undoable: Vec<HistoryEntryData>,
+    pub redoable: Vec<HistoryEntryData>,
+}
+
+// Saved next to the edited file, i.e. "graph.widget.journal", to restore
+// the undo history after a restart and to keep track of all the edits
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "nrg_serialize")]
+pub struct SessionJournal {
+    pub file: PathBuf,
+    pub history: HistoryData,
+    pub log: Vec<JournalRecord>,
+}
+
+impl SessionJournal {
+    pub fn path_for(file: &Path) -> PathBuf {
+        let mut path = file.as_os_str().to_os_string();
+        path.push(".");
+        path.push(JOURNAL_EXTENSION);
+        PathBuf::from(path)
+    }
+
+    #[inline]
+    pub fn save(&self) -> SerializeResult<()> {
+        try_serialize_to_file(self, Self::path_for(self.file.as_path()).as_path())
+    }
+
+    #[inline]
+    pub fn load(file: &Path) -> SerializeResult<Self> {
+        try_deserialize_from_file(Self::path_for(file).as_path())
+    }
+}
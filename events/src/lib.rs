@@ -1,3 +1,8 @@
 pub use history::*;
+pub use journal::*;
 
 pub mod history;
+pub mod journal;
+
+#[cfg(test)]
+mod test;
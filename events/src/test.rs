@@ -0,0 +1,259 @@
+use std::sync::{Arc, RwLock};
+
+use nrg_messenger::{
+    implement_undoable_message, Message, Messenger, MessengerRw, SerializableMessage,
+};
+use nrg_serialize::{typetag, Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "nrg_serialize")]
+enum SliderEvent {
+    Changed(u32, i32, i32), //slider, old value, new value
+}
+implement_undoable_message!(SliderEvent, undo_event, debug_info_event, merge_event);
+#[typetag::serde]
+impl SerializableMessage for SliderEvent {}
+fn undo_event(event: &SliderEvent) -> SliderEvent {
+    let SliderEvent::Changed(id, old, new) = *event;
+    SliderEvent::Changed(id, new, old)
+}
+fn debug_info_event(event: &SliderEvent) -> String {
+    let SliderEvent::Changed(id, old, new) = *event;
+    format!("{}:{}->{}", id, old, new)
+}
+fn merge_event(event: &SliderEvent, next: &SliderEvent) -> Option<SliderEvent> {
+    let (SliderEvent::Changed(id, old, _), SliderEvent::Changed(next_id, _, new)) = (*event, *next);
+    if id == next_id {
+        Some(SliderEvent::Changed(id, old, new))
+    } else {
+        None
+    }
+}
+
+struct Sliders {
+    messenger: MessengerRw,
+    history: EventsHistory,
+    values: [i32; 2],
+}
+
+impl Sliders {
+    fn new() -> Self {
+        let messenger: MessengerRw = Arc::new(RwLock::new(Messenger::default()));
+        let mut history = EventsHistory::default();
+        history.register_event_as_persistent::<SliderEvent>(&messenger);
+        Self {
+            messenger,
+            history,
+            values: [0, 0],
+        }
+    }
+    fn set(&mut self, id: u32, value: i32) {
+        let old = self.values[id as usize];
+        self.values[id as usize] = value;
+        self.send(SliderEvent::Changed(id, old, value).as_boxed());
+    }
+    fn send(&self, msg: Box<dyn Message>) {
+        let dispatcher = self.messenger.read().unwrap().get_dispatcher();
+        dispatcher.write().unwrap().send(msg).ok();
+    }
+    //Dispatches the messages of the frame, then applies the ones coming from the history
+    fn frame(&mut self) {
+        let mut values = self.values;
+        self.messenger.read().unwrap().process_messages(|_| {});
+        self.history.update();
+        self.messenger.read().unwrap().process_messages(|msg| {
+            if let Some(SliderEvent::Changed(id, _, new)) = msg.as_any().downcast_ref() {
+                values[*id as usize] = *new;
+            }
+        });
+        self.values = values;
+    }
+    fn steps(&self) -> usize {
+        self.history
+            .get_undoable_events_history_as_string()
+            .map_or(0, |s| s.len())
+    }
+}
+
+#[test]
+fn test_history_transactions() {
+    let mut sliders = Sliders::new();
+
+    //A drag is merged in a single undo step
+    for v in 1..=100 {
+        sliders.set(0, v);
+    }
+    sliders.frame();
+    sliders.set(1, 5);
+    sliders.frame();
+    assert_eq!(sliders.steps(), 2);
+
+    sliders.history.begin_transaction("Reset");
+    sliders.set(0, 0);
+    sliders.set(1, 0);
+    sliders.history.commit_transaction();
+    sliders.frame();
+    assert_eq!(
+        sliders.history.get_undoable_events_history_as_string(),
+        Some(vec![
+            "SliderEvent - 0:0->100".to_string(),
+            "SliderEvent - 1:0->5".to_string(),
+            "Reset".to_string()
+        ])
+    );
+
+    //Replayed events are not added again to the history
+    sliders.history.push(EventsHistoryOperation::Undo);
+    sliders.frame();
+    sliders.frame();
+    assert_eq!(sliders.values, [100, 5]);
+    assert_eq!(sliders.steps(), 2);
+    sliders.history.push(EventsHistoryOperation::Undo);
+    sliders.history.push(EventsHistoryOperation::Undo);
+    sliders.frame();
+    sliders.frame();
+    assert_eq!(sliders.values, [0, 0]);
+    assert_eq!(sliders.steps(), 0);
+    sliders.history.push(EventsHistoryOperation::Redo);
+    sliders.frame();
+    sliders.frame();
+    assert_eq!(sliders.values, [100, 0]);
+    assert_eq!(sliders.steps(), 1);
+
+    //Aborted transactions restore the previous values
+    sliders.history.begin_transaction("Move");
+    sliders.set(1, 7);
+    sliders.history.abort_transaction();
+    sliders.frame();
+    sliders.frame();
+    assert_eq!(sliders.values, [100, 0]);
+    assert!(!sliders.history.is_in_transaction());
+    assert_eq!(sliders.steps(), 1);
+    assert!(sliders
+        .history
+        .get_redoable_events_history_as_string()
+        .is_some());
+
+    //Oldest steps are forgotten over the limit
+    sliders.history.set_max_steps(3);
+    for v in 0..5 {
+        sliders.set((v % 2) as u32, v);
+    }
+    sliders.frame();
+    assert_eq!(sliders.steps(), 3);
+
+    //Edits equal to a dropped replay are still added to the history
+    sliders
+        .messenger
+        .write()
+        .unwrap()
+        .mute_type::<SliderEvent>();
+    sliders.history.push(EventsHistoryOperation::Undo);
+    sliders.frame();
+    sliders
+        .messenger
+        .write()
+        .unwrap()
+        .unmute_type::<SliderEvent>();
+    sliders.set(0, 2);
+    sliders.frame();
+    assert_eq!(sliders.steps(), 3);
+    assert_eq!(
+        sliders
+            .history
+            .get_undoable_events_history_as_string()
+            .unwrap()
+            .last(),
+        Some(&"SliderEvent - 0:4->2".to_string())
+    );
+}
+
+#[test]
+fn test_history_merge_window() {
+    let mut sliders = Sliders::new();
+    sliders
+        .history
+        .set_merge_window(std::time::Duration::from_millis(50));
+
+    //Edits of the same slider are merged only when they come close to each other
+    sliders.set(0, 1);
+    sliders.frame();
+    sliders.set(0, 2);
+    sliders.frame();
+    assert_eq!(sliders.steps(), 1);
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    sliders.set(0, 3);
+    sliders.frame();
+    assert_eq!(sliders.steps(), 2);
+
+    //A replay that never comes back doesn't hide the following edits
+    sliders
+        .messenger
+        .write()
+        .unwrap()
+        .mute_type::<SliderEvent>();
+    sliders.history.push(EventsHistoryOperation::Undo);
+    sliders.frame();
+    sliders.frame();
+    sliders
+        .messenger
+        .write()
+        .unwrap()
+        .unmute_type::<SliderEvent>();
+    sliders.set(1, 4);
+    sliders.frame();
+    assert_eq!(sliders.steps(), 2);
+    assert!(sliders
+        .history
+        .get_redoable_events_history_as_string()
+        .is_none());
+}
+
+#[test]
+fn test_history_journal() {
+    let file = std::env::temp_dir().join("nrg_test_history_journal.widget");
+    let mut sliders = Sliders::new();
+    sliders.set(0, 10);
+    sliders.frame();
+    sliders.set(1, 20);
+    sliders.frame();
+    sliders.history.push(EventsHistoryOperation::Undo);
+    sliders.frame();
+    sliders.frame();
+    assert_eq!(sliders.values, [10, 0]);
+
+    let revision = sliders.history.revision();
+    assert!(sliders.history.journal(file.as_path()).save().is_ok());
+    assert_eq!(sliders.history.revision(), revision);
+
+    //A new session restores history and log of the edited file
+    let journal = SessionJournal::load(file.as_path()).unwrap();
+    std::fs::remove_file(SessionJournal::path_for(file.as_path())).ok();
+    assert_eq!(journal.file, file);
+    let mut restored = Sliders::new();
+    restored.values = sliders.values;
+    restored.history.restore_journal(journal);
+    assert_eq!(restored.steps(), 1);
+    let actions: Vec<JournalAction> = restored.history.log().iter().map(|r| r.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            JournalAction::Edit,
+            JournalAction::Edit,
+            JournalAction::Undo
+        ]
+    );
+
+    restored.history.push(EventsHistoryOperation::Redo);
+    restored.frame();
+    restored.frame();
+    assert_eq!(restored.values, [10, 20]);
+    restored.history.push(EventsHistoryOperation::Undo);
+    restored.history.push(EventsHistoryOperation::Undo);
+    restored.frame();
+    restored.frame();
+    assert_eq!(restored.values, [0, 0]);
+    assert_eq!(restored.history.log().len(), 6);
+}
@@ -6,15 +6,25 @@ use nrg_serialize::*;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "nrg_serialize")]
 pub struct Config {
+    #[serde(default)]
+    version: u32,
     pub fonts: Vec<PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self { fonts: Vec::new() }
+        Self {
+            version: Self::VERSION,
+            fonts: Vec::new(),
+        }
     }
 }
 
+// Version 1 is the first versioned layout: older data only gets its version
+impl Versioned for Config {
+    const VERSION: u32 = 1;
+}
+
 impl Data for Config {}
 impl ConfigBase for Config {
     fn get_filename(&self) -> &'static str {
@@ -1,16 +1,18 @@
 use super::config::*;
 use nrg_core::*;
-use nrg_resources::ConfigBase;
+use nrg_resources::{read_versioned_file, ConfigBase};
 use nrg_serialize::*;
 
 use super::system::*;
 
 const UPDATE_PHASE: &str = "UPDATE_PHASE";
+const MAX_UPDATE_STEPS: u32 = 4;
 
 #[repr(C)]
 pub struct Game {
     config: Config,
     system_id: SystemId,
+    system_state: MySystemStateRw,
 }
 
 impl Default for Game {
@@ -18,6 +20,7 @@ impl Default for Game {
         Self {
             config: Config::default(),
             system_id: SystemId::default(),
+            system_state: MySystemStateRw::default(),
         }
     }
 }
@@ -27,14 +30,22 @@ unsafe impl Sync for Game {}
 impl Plugin for Game {
     fn prepare<'a>(&mut self, app: &mut App) {
         let path = self.config.get_filepath();
-        deserialize_from_file(&mut self.config, path);
+        match read_versioned_file(path.as_path()) {
+            Ok(config) => self.config = config,
+            Err(e) => eprintln!("Unable to load config {}", e),
+        }
 
         let mut update_phase = PhaseWithSystems::new(UPDATE_PHASE);
-        let system = MySystem::new(&app.get_shared_data(), &self.config);
+        let system = MySystem::new(&app.get_shared_data(), &self.config, &self.system_state);
 
         self.system_id = system.id();
 
-        update_phase.add_system(system);
+        //Game logic advances in fixed steps to behave the same at any frame rate
+        update_phase
+            .set_mode(PhaseMode::FixedStep {
+                max_steps: MAX_UPDATE_STEPS,
+            })
+            .add_system(system);
         app.create_phase_before(update_phase, "RENDERING_UPDATE");
     }
 
@@ -46,4 +57,16 @@ impl Plugin for Game {
         update_phase.remove_system(&self.system_id);
         app.destroy_phase(UPDATE_PHASE);
     }
+
+    fn save_state(&mut self, _app: &mut App) -> Option<String> {
+        Some(serialize(&*self.system_state.read().unwrap()))
+    }
+
+    fn restore_state(&mut self, _app: &mut App, state: &str) {
+        //The state layout could have changed since it has been saved
+        match try_deserialize(state) {
+            Ok(state) => *self.system_state.write().unwrap() = state,
+            Err(e) => eprintln!("Unable to restore game state {}", e),
+        }
+    }
 }
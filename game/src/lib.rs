@@ -6,6 +6,18 @@ mod config;
 mod game;
 mod system;
 
+#[no_mangle]
+pub extern "C" fn get_plugin_abi() -> PluginAbi {
+    PluginAbi::current()
+}
+
+#[no_mangle]
+pub fn get_plugin_manifest() -> PluginManifest {
+    let mut manifest = PluginManifest::new("nrg_game", env!("CARGO_PKG_VERSION"));
+    manifest.depends_on("nrg_window", "^0.1");
+    manifest
+}
+
 #[no_mangle]
 pub extern "C" fn create_plugin() -> PluginHolder {
     let game = game::Game::default();
@@ -1,5 +1,8 @@
 #![allow(dead_code)]
-use std::path::Path;
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+};
 
 use super::config::*;
 
@@ -7,6 +10,16 @@ use nrg_core::*;
 use nrg_graphics::*;
 use nrg_math::{Matrix4, Vector3};
 use nrg_resources::{DataTypeResource, FileResource, SharedDataRw};
+use nrg_serialize::{Deserialize, Serialize};
+
+const ROTATION_SPEED: f32 = 6.; //degrees per second
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize", default)]
+pub struct MySystemState {
+    pub angle: f32,
+}
+pub type MySystemStateRw = Arc<RwLock<MySystemState>>;
 
 pub struct MySystem {
     id: SystemId,
@@ -19,11 +32,11 @@ pub struct MySystem {
     right_texture: TextureRc,
     left_mesh: MeshRc,
     right_mesh: MeshRc,
-    angle: f32,
+    state: MySystemStateRw,
 }
 
 impl MySystem {
-    pub fn new(shared_data: &SharedDataRw, config: &Config) -> Self {
+    pub fn new(shared_data: &SharedDataRw, config: &Config, state: &MySystemStateRw) -> Self {
         let pipeline = PipelineInstance::find_from_name(shared_data, "UI");
 
         let mut mesh = MeshData::default();
@@ -56,7 +69,7 @@ impl MySystem {
             ),
             left_mesh,
             right_mesh,
-            angle: 0.,
+            state: state.clone(),
         }
     }
 }
@@ -68,22 +81,34 @@ impl System for MySystem {
     fn should_run_when_not_focused(&self) -> bool {
         false
     }
+    fn access(&self) -> SystemAccess {
+        let mut access = SystemAccess::default();
+        access
+            .read::<Time>()
+            .write::<MeshInstance>()
+            .write::<MaterialInstance>();
+        access
+    }
     fn init(&mut self) {
         self.left_material
             .resource()
+            .unwrap()
             .get_mut()
             .add_texture(self.left_texture.clone());
         self.left_material
             .resource()
+            .unwrap()
             .get_mut()
             .add_mesh(self.left_mesh.clone());
 
         self.right_material
             .resource()
+            .unwrap()
             .get_mut()
             .add_texture(self.right_texture.clone());
         self.right_material
             .resource()
+            .unwrap()
             .get_mut()
             .add_mesh(self.right_mesh.clone());
     }
@@ -95,20 +120,33 @@ impl System for MySystem {
 
         self.left_mesh
             .resource()
+            .unwrap()
             .get_mut()
             .set_transform(left_matrix);
 
-        self.angle += 0.1;
+        //Fixed delta of the step: the update phase runs in fixed steps
+        let delta = Time::get_time(&self.shared_data)
+            .resource()
+            .unwrap()
+            .get()
+            .get_delta();
+        let angle = {
+            let mut state = self.state.write().unwrap();
+            state.angle += ROTATION_SPEED * delta;
+            state.angle
+        };
         let right_matrix = Matrix4::from_translation(Vector3::new(1000., 800., 0.))
-            * Matrix4::from_angle_z(nrg_math::Rad::from(nrg_math::Deg(self.angle)))
+            * Matrix4::from_angle_z(nrg_math::Rad::from(nrg_math::Deg(angle)))
             * Matrix4::from_nonuniform_scale(400., 600., 1.);
 
         self.right_mesh
             .resource()
+            .unwrap()
             .get_mut()
             .set_transform(right_matrix);
         self.right_material
             .resource()
+            .unwrap()
             .get_mut()
             .set_outline_color([1., 1., 0., 2.].into());
 
@@ -10,6 +10,7 @@ use crate::common::utils::*;
 use nrg_math::matrix4_to_array;
 use nrg_math::Matrix4;
 use nrg_resources::convert_from_local_path;
+use nrg_resources::read_file;
 use nrg_resources::DATA_FOLDER;
 use std::path::PathBuf;
 use std::{cell::RefCell, path::Path, rc::Rc};
@@ -84,13 +85,12 @@ impl Pipeline {
 
     pub fn set_shader(&mut self, shader_type: ShaderType, shader_filepath: &Path) -> &mut Self {
         let path = convert_from_local_path(PathBuf::from(DATA_FOLDER).as_path(), shader_filepath);
-        if path.exists() && path.is_file() {
+        if let Ok(shader_bytes) = read_file(path.as_path()) {
             self.inner
                 .borrow_mut()
                 .remove_shader(&self.device, shader_type);
 
-            let mut shader_file = std::fs::File::open(path).unwrap();
-            let shader_code = read_spirv_from_bytes(&mut shader_file);
+            let shader_code = read_spirv_from_bytes(&mut std::io::Cursor::new(shader_bytes));
 
             self.inner.borrow_mut().create_shader_module(
                 &self.device,
@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use crate::common::utils::*;
 
 use nrg_math::*;
-use nrg_resources::{convert_from_local_path, implement_file_data, DATA_FOLDER};
+use nrg_resources::{convert_from_local_path, Deserializable, DATA_FOLDER};
 use nrg_serialize::*;
 
 #[repr(C)]
@@ -76,6 +76,8 @@ pub struct VertexData {
     pub normal: Vector3,
 }
 
+unsafe impl blob::Blob for VertexData {}
+
 impl Default for VertexData {
     fn default() -> VertexData {
         VertexData {
@@ -112,6 +114,8 @@ impl Default for RenderPassData {
 #[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
 #[serde(crate = "nrg_serialize")]
 pub struct PipelineData {
+    #[serde(default)]
+    version: u32,
     path: PathBuf,
     pub name: String,
     pub fragment_shader: PathBuf,
@@ -126,6 +130,7 @@ unsafe impl Sync for PipelineData {}
 impl Default for PipelineData {
     fn default() -> Self {
         Self {
+            version: Self::VERSION,
             path: PathBuf::new(),
             name: String::from("3D"),
             fragment_shader: PathBuf::new(),
@@ -137,6 +142,11 @@ impl Default for PipelineData {
     }
 }
 
+// Version 1 is the first versioned layout: older data only gets its version
+impl Versioned for PipelineData {
+    const VERSION: u32 = 1;
+}
+
 impl PipelineData {
     pub fn canonicalize_paths(mut self) -> Self {
         let data_path = PathBuf::from(DATA_FOLDER);
@@ -171,19 +181,39 @@ impl PipelineData {
     }
 }
 
-implement_file_data!(
-    struct MaterialData {
-        pipeline_name: String,
-        meshes: Vec<PathBuf>,
-        textures: Vec<PathBuf>,
-        diffuse_color: Vector4,
-        outline_color: Vector4,
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "nrg_serialize")]
+pub struct MaterialData {
+    #[serde(default)]
+    version: u32,
+    path: PathBuf,
+    pub pipeline_name: String,
+    pub meshes: Vec<PathBuf>,
+    pub textures: Vec<PathBuf>,
+    pub diffuse_color: Vector4,
+    pub outline_color: Vector4,
+}
+unsafe impl Send for MaterialData {}
+unsafe impl Sync for MaterialData {}
+impl Versioned for MaterialData {
+    const VERSION: u32 = 1;
+}
+impl Deserializable for MaterialData {
+    #[inline]
+    fn set_path(&mut self, filepath: &Path) {
+        self.path = filepath.to_path_buf();
     }
-);
+    #[inline]
+    fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
 
 impl Default for MaterialData {
     fn default() -> Self {
         Self {
+            version: Self::VERSION,
             path: PathBuf::new(),
             pipeline_name: String::from("3D"),
             meshes: Vec::new(),
@@ -203,13 +233,33 @@ pub struct MeshDataRef {
     pub last_index: u32,
 }
 
-implement_file_data!(
-    struct MeshData {
-        vertices: Vec<VertexData>,
-        indices: Vec<u32>,
-        transform: Matrix4,
+// Vertices and indices are stored as raw blobs when serialized in binary format
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "nrg_serialize")]
+pub struct MeshData {
+    path: PathBuf,
+    #[serde(with = "nrg_serialize::blob")]
+    pub vertices: Vec<VertexData>,
+    #[serde(with = "nrg_serialize::blob")]
+    pub indices: Vec<u32>,
+    pub transform: Matrix4,
+}
+unsafe impl Send for MeshData {}
+unsafe impl Sync for MeshData {}
+impl Versioned for MeshData {
+    const VERSION: u32 = 0;
+}
+impl Deserializable for MeshData {
+    #[inline]
+    fn set_path(&mut self, filepath: &Path) {
+        self.path = filepath.to_path_buf();
     }
-);
+    #[inline]
+    fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
 
 impl Default for MeshData {
     fn default() -> Self {
@@ -1,7 +1,8 @@
 use nrg_math::{get_translation_rotation_scale, Matrix4};
 use nrg_math::{Vector4, Zero};
+use nrg_resources::ResourceData;
 
-use crate::{Mesh, MeshInstance, PipelineId};
+use crate::{Mesh, MeshId, MeshInstance, PipelineId};
 
 use super::data_formats::*;
 use super::device::*;
@@ -9,6 +10,16 @@ use super::render_pass::*;
 use super::shader::*;
 use super::texture::*;
 
+#[derive(Clone)]
+struct PipelineMesh {
+    id: MeshId,
+    vertices: Vec<VertexData>,
+    indices: Vec<u32>,
+    data: InstanceData,
+}
+
+// Mesh instances are kept between frames:
+// only added, changed or removed ones rebuild the instance data
 #[derive(Clone)]
 pub struct Pipeline {
     pub inner: crate::api::backend::pipeline::Pipeline,
@@ -19,6 +30,8 @@ pub struct Pipeline {
     instance_count: usize,
     instance_data: Vec<InstanceData>,
     instance_commands: Vec<InstanceCommand>,
+    meshes: Vec<PipelineMesh>,
+    is_dirty: bool,
 }
 unsafe impl Send for Pipeline {}
 unsafe impl Sync for Pipeline {}
@@ -63,6 +76,8 @@ impl Pipeline {
             instance_count: 0,
             instance_data: Vec::new(),
             instance_commands: Vec::new(),
+            meshes: Vec::new(),
+            is_dirty: false,
         }
     }
     pub fn is_empty(&self) -> bool {
@@ -83,9 +98,37 @@ impl Pipeline {
     }
 
     pub fn prepare(&mut self) -> &mut Self {
+        if !self.is_dirty {
+            return self;
+        }
+        self.is_dirty = false;
         self.vertex_count = 0;
         self.index_count = 0;
         self.instance_count = 0;
+        for mesh in self.meshes.iter() {
+            let mesh_data_ref = self.mesh.bind_at_index(
+                &mesh.vertices,
+                self.vertex_count,
+                &mesh.indices,
+                self.index_count,
+            );
+            self.vertex_count += mesh.vertices.len() as u32;
+            self.index_count += mesh.indices.len() as u32;
+
+            let command = InstanceCommand {
+                mesh_index: self.instance_count,
+                mesh_data_ref,
+            };
+            let data = mesh.data;
+            if self.instance_count >= self.instance_commands.len() {
+                self.instance_commands.push(command);
+                self.instance_data.push(data);
+            } else {
+                self.instance_commands[self.instance_count] = command;
+                self.instance_data[self.instance_count] = data;
+            }
+            self.instance_count += 1;
+        }
         self
     }
 
@@ -136,40 +179,38 @@ impl Pipeline {
         diffuse_layer_index: i32,
         outline_color: Vector4,
     ) -> &mut Self {
-        let mesh_data_ref = self.mesh.bind_at_index(
-            &mesh_instance.get_data().vertices,
-            self.vertex_count,
-            &mesh_instance.get_data().indices,
-            self.index_count,
-        );
-
-        self.vertex_count += mesh_instance.get_data().vertices.len() as u32;
-        self.index_count += mesh_instance.get_data().indices.len() as u32;
-
-        let command = InstanceCommand {
-            mesh_index: self.instance_count,
-            mesh_data_ref,
-        };
         let (position, rotation, scale) = get_translation_rotation_scale(mesh_instance.transform());
-
-        let data = InstanceData {
-            position,
-            rotation,
-            scale,
-            draw_area: mesh_instance.draw_area(),
-            diffuse_color,
-            diffuse_texture_index,
-            diffuse_layer_index,
-            outline_color,
+        let mesh = PipelineMesh {
+            id: mesh_instance.id(),
+            vertices: mesh_instance.get_data().vertices.clone(),
+            indices: mesh_instance.get_data().indices.clone(),
+            data: InstanceData {
+                position,
+                rotation,
+                scale,
+                draw_area: mesh_instance.draw_area(),
+                diffuse_color,
+                diffuse_texture_index,
+                diffuse_layer_index,
+                outline_color,
+            },
         };
-        if self.instance_count >= self.instance_commands.len() {
-            self.instance_commands.push(command);
-            self.instance_data.push(data);
+        if let Some(index) = self.meshes.iter().position(|m| m.id == mesh.id) {
+            self.meshes[index] = mesh;
         } else {
-            self.instance_commands[self.instance_count] = command;
-            self.instance_data[self.instance_count] = data;
+            self.meshes.push(mesh);
         }
-        self.instance_count += 1;
+        self.is_dirty = true;
+        self
+    }
+
+    pub fn retain_mesh_instances<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&MeshId) -> bool,
+    {
+        let count = self.meshes.len();
+        self.meshes.retain(|m| f(&m.id));
+        self.is_dirty |= self.meshes.len() != count;
         self
     }
 }
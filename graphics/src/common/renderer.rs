@@ -2,7 +2,9 @@ use crate::Pipeline;
 use crate::{FontRc, MaterialRc, PipelineRc, RenderPass, RenderPassRc, TextureRc};
 use nrg_math::*;
 use nrg_platform::*;
-use nrg_resources::{convert_from_local_path, FileResource, DATA_FOLDER};
+use nrg_resources::{
+    convert_from_local_path, ChangeTick, ChangeTracker, FileResource, DATA_FOLDER,
+};
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
@@ -29,6 +31,7 @@ pub struct Renderer {
     state: RendererState,
     pipelines: Vec<Pipeline>,
     render_passes: Vec<RenderPass>,
+    change_tracker: ChangeTracker,
 }
 pub type RendererRw = Arc<RwLock<Renderer>>;
 
@@ -49,6 +52,7 @@ impl Renderer {
             scissors: Scissors::default(),
             texture_handler,
             state: RendererState::Init,
+            change_tracker: ChangeTracker::default(),
         }
     }
 
@@ -77,11 +81,12 @@ impl Renderer {
         fonts: &[FontRc],
     ) -> &mut Self {
         nrg_profiler::scoped_profile!("renderer::prepare_frame");
-        self.load_render_passes(render_passes);
-        self.load_pipelines(pipelines);
-        self.load_textures(textures, fonts);
+        //Only resources added or changed since the previous frame need to be uploaded
+        let last_run = self.change_tracker.update();
+        self.load_render_passes(render_passes, last_run);
+        self.load_pipelines(pipelines, last_run);
+        self.load_textures(textures, fonts, last_run);
 
-        self.prepare_pipelines();
         self.prepare_materials(pipelines, materials);
         self
     }
@@ -95,7 +100,9 @@ impl Renderer {
     pub fn get_pipelines(&mut self) -> &mut Vec<Pipeline> {
         &mut self.pipelines
     }
+    // Mesh instances added since prepare_frame are uploaded here
     pub fn end_preparation(&mut self) {
+        self.prepare_pipelines();
         self.state = RendererState::Prepared;
     }
 
@@ -226,20 +233,27 @@ impl Renderer {
         self.pipelines.clear();
         self.render_passes.iter_mut().for_each(|r| r.destroy());
         self.render_passes.clear();
+        //Everything has to be uploaded again
+        self.change_tracker = ChangeTracker::default();
     }
 }
 
 impl Renderer {
-    fn load_render_passes(&mut self, render_passes: &mut [RenderPassRc]) {
+    fn load_render_passes(&mut self, render_passes: &mut [RenderPassRc], last_run: ChangeTick) {
         nrg_profiler::scoped_profile!("renderer::load_render_passes");
         render_passes.iter_mut().for_each(|render_pass_instance| {
+            //render pass could have been released in the meantime or be unchanged
+            let render_pass_resource = match render_pass_instance.resource() {
+                Some(resource) if resource.is_changed_since(last_run) => resource,
+                _ => return,
+            };
             let mut should_create = false;
             if let Some(index) = self
                 .render_passes
                 .iter()
                 .position(|r| r.id() == render_pass_instance.id())
             {
-                if !render_pass_instance.resource().get().is_initialized() {
+                if !render_pass_resource.get().is_initialized() {
                     //render pass needs to be recreated
                     let mut render_pass = self.render_passes.remove(index);
                     render_pass.destroy();
@@ -253,22 +267,27 @@ impl Renderer {
                 self.render_passes.push(RenderPass::create_default(
                     device,
                     render_pass_instance.id(),
-                    render_pass_instance.resource().get().data(),
+                    render_pass_resource.get().data(),
                 ));
-                render_pass_instance.resource().get_mut().init();
+                render_pass_resource.get_mut().init();
             }
         });
     }
-    fn load_pipelines(&mut self, pipelines: &mut [PipelineRc]) {
+    fn load_pipelines(&mut self, pipelines: &mut [PipelineRc], last_run: ChangeTick) {
         nrg_profiler::scoped_profile!("renderer::load_pipelines");
         pipelines.iter_mut().for_each(|pipeline_instance| {
+            //pipeline could have been released in the meantime or be unchanged
+            let pipeline_resource = match pipeline_instance.resource() {
+                Some(resource) if resource.is_changed_since(last_run) => resource,
+                _ => return,
+            };
             let mut create_pipeline = false;
             if let Some(index) = self
                 .pipelines
                 .iter()
                 .position(|p| p.id() == pipeline_instance.id())
             {
-                if !pipeline_instance.resource().get().is_initialized() {
+                if !pipeline_resource.get().is_initialized() {
                     //pipeline needs to be recreated
                     let mut pipeline = self.pipelines.remove(index);
                     pipeline.destroy();
@@ -282,42 +301,69 @@ impl Renderer {
                 self.pipelines.push(Pipeline::create(
                     device,
                     pipeline_instance.id(),
-                    pipeline_instance.resource().get().data(),
+                    pipeline_resource.get().data(),
                     self.render_passes.first().unwrap(),
                 ));
-                pipeline_instance.resource().get_mut().init();
+                pipeline_resource.get_mut().init();
             }
         });
     }
 
-    fn load_textures(&mut self, textures: &mut [TextureRc], fonts: &[FontRc]) {
+    fn load_textures(
+        &mut self,
+        textures: &mut [TextureRc],
+        fonts: &[FontRc],
+        last_run: ChangeTick,
+    ) {
         nrg_profiler::scoped_profile!("renderer::load_textures");
         let texture_handler = &mut self.texture_handler;
         textures.iter_mut().for_each(|texture_instance| {
-            if !texture_instance.resource().get().is_initialized() {
-                if texture_instance.resource().get().texture_index() != INVALID_INDEX {
+            //texture could have been released in the meantime or be unchanged
+            let texture_resource = match texture_instance.resource() {
+                Some(resource) if resource.is_changed_since(last_run) => resource,
+                _ => return,
+            };
+            if !texture_resource.get().is_initialized() {
+                if texture_resource.get().texture_index() != INVALID_INDEX {
                     //texture needs to be recreated
                     texture_handler.remove(texture_instance.id());
                 }
                 let path = convert_from_local_path(
                     PathBuf::from(DATA_FOLDER).as_path(),
-                    texture_instance.resource().get().path(),
+                    texture_resource.get().path(),
                 );
-                let (texture_index, layer_index) = if is_texture(path.as_path()) {
-                    texture_handler.add_from_path(texture_instance.id(), path.as_path())
-                } else if let Some(font) = fonts.iter().find(|f| f.resource().get().path() == path)
+                let texture_data = if is_texture(path.as_path()) {
+                    texture_handler
+                        .add_from_path(texture_instance.id(), path.as_path())
+                        .map_err(|e| e.to_string())
+                } else if let Some(font) = fonts
+                    .iter()
+                    .filter_map(|f| f.resource())
+                    .find(|f| f.get().path() == path)
                 {
-                    texture_handler.add(
+                    Ok(texture_handler.add(
                         texture_instance.id(),
-                        font.resource().get().font().get_texture().clone(),
-                    )
+                        font.get().font().get_texture().clone(),
+                    ))
                 } else {
-                    panic!("Unable to load texture with path {:?}", path.as_path());
+                    Err(String::from("No image or font found"))
                 };
-                texture_instance
-                    .resource()
-                    .get_mut()
-                    .set_texture_data(texture_index, layer_index);
+                match texture_data {
+                    Ok((texture_index, layer_index)) => {
+                        let texture_size = texture_handler
+                            .get_texture(texture_instance.id())
+                            .memory_size();
+                        texture_resource.get_mut().set_texture_data(
+                            texture_index,
+                            layer_index,
+                            texture_size,
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("Unable to load texture {:?}: {}", path, e);
+                        texture_resource.get_mut().set_missing();
+                    }
+                }
             }
         });
     }
@@ -331,17 +377,14 @@ impl Renderer {
 
     fn prepare_materials(&mut self, pipelines: &[PipelineRc], materials: &mut [MaterialRc]) {
         nrg_profiler::scoped_profile!("renderer::prepare_materials");
-        materials.sort_by(|a, b| {
-            let pipeline_a = pipelines
-                .iter()
-                .position(|p| p.id() == a.resource().get().pipeline().id())
-                .unwrap();
-            let pipeline_b = pipelines
-                .iter()
-                .position(|p| p.id() == b.resource().get().pipeline().id())
-                .unwrap();
-            pipeline_a.cmp(&pipeline_b)
-        });
+        //materials released in the meantime are sorted last
+        let pipeline_index = |material: &MaterialRc| {
+            material.resource().and_then(|m| {
+                let pipeline_id = m.get().pipeline().id();
+                pipelines.iter().position(|p| p.id() == pipeline_id)
+            })
+        };
+        materials.sort_by_key(|m| pipeline_index(m).unwrap_or(usize::MAX));
     }
 }
 
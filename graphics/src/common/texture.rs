@@ -1,6 +1,7 @@
-use std::path::Path;
+use std::{io, path::Path};
 
 use image::{DynamicImage, EncodableLayout, Pixel};
+use nrg_resources::read_file;
 use nrg_serialize::{generate_random_uid, Uid};
 
 use crate::{api::backend, Area, AreaAllocator, DEFAULT_AREA_SIZE, INVALID_INDEX};
@@ -57,6 +58,10 @@ impl Texture {
     pub fn get_width(&self) -> u32 {
         self.area.width
     }
+    //Images are always stored as rgba8
+    pub fn memory_size(&self) -> usize {
+        self.area.width as usize * self.area.height as usize * 4
+    }
     pub fn convert_uv(&self, u: f32, v: f32) -> (f32, f32) {
         (
             (self.area.x as f32 + 0.5 + u * self.area.width as f32) / DEFAULT_AREA_SIZE as f32,
@@ -117,9 +122,10 @@ impl TextureHandler {
         (texture_index as _, layer_index as _)
     }
 
-    pub fn add_from_path(&mut self, id: Uid, filepath: &Path) -> (u32, u32) {
-        let image = image::open(filepath).unwrap();
-        self.add(id, image)
+    pub fn add_from_path(&mut self, id: Uid, filepath: &Path) -> io::Result<(u32, u32)> {
+        let image = image::load_from_memory(read_file(filepath)?.as_slice())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(self.add(id, image))
     }
 
     pub fn remove(&mut self, id: Uid) {
@@ -4,12 +4,13 @@ use nrg_math::*;
 use nrg_platform::*;
 use std::{
     collections::HashMap,
+    io,
     num::NonZeroU16,
     path::{Path, PathBuf},
 };
 use ttf_parser::*;
 
-use super::glyph::*;
+use super::{geometry::Line, glyph::*};
 
 const DEFAULT_FONT_COUNT: u8 = 255;
 pub const DEFAULT_FONT_TEXTURE_SIZE: usize = 1024;
@@ -47,7 +48,7 @@ impl Default for Font {
 
 impl Font {
     #[inline]
-    pub fn new(filepath: &Path) -> Self {
+    pub fn new(filepath: &Path) -> io::Result<Self> {
         Font::new_ttf_font(filepath)
     }
 
@@ -126,10 +127,11 @@ impl Font {
 }
 
 impl Font {
-    fn new_ttf_font(filepath: &Path) -> Self {
-        let font_data = ::std::fs::read(filepath).unwrap();
+    fn new_ttf_font(filepath: &Path) -> io::Result<Self> {
+        let font_data = nrg_resources::read_file(filepath)?;
 
-        let face = Face::from_slice(font_data.as_slice(), 0).unwrap();
+        let face = Face::from_slice(font_data.as_slice(), 0)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))?;
         // Collect all the unique codepoint to glyph mappings.
         let mut char_to_glyph = HashMap::new();
         for subtable in face.character_mapping_subtables() {
@@ -157,19 +159,35 @@ impl Font {
         }
 
         let image = Font::create_texture(&mut glyphs, &max_glyph_metrics);
-        Self {
+        Ok(Self {
             filepath: PathBuf::from(filepath),
             metrics: max_glyph_metrics,
             glyphs,
             char_to_glyph,
             image,
-        }
+        })
     }
 
     pub fn get_texture(&self) -> &DynamicImage {
         &self.image
     }
 
+    pub fn memory_size(&self) -> usize {
+        let glyphs_size: usize = self
+            .glyphs
+            .iter()
+            .map(|g| {
+                std::mem::size_of::<Glyph>()
+                    + g.data.capacity() * std::mem::size_of::<f32>()
+                    + g.lines.capacity() * std::mem::size_of::<Line>()
+            })
+            .sum();
+        self.image.as_bytes().len()
+            + glyphs_size
+            + self.char_to_glyph.capacity()
+                * (std::mem::size_of::<u32>() + std::mem::size_of::<NonZeroU16>())
+    }
+
     fn create_texture(glyphs: &mut [Glyph], metrics: &Metrics) -> DynamicImage {
         let size = DEFAULT_FONT_TEXTURE_SIZE;
 
@@ -1,11 +1,14 @@
 use crate::{Font, MaterialInstance, MaterialRc, PipelineInstance, TextureInstance};
 use nrg_math::Vector4;
 use nrg_resources::{
-    convert_from_local_path, FileResource, ResourceData, ResourceId, ResourceRef, SharedData,
-    SharedDataRw, DATA_FOLDER,
+    asset_uuid, convert_from_local_path, FileResource, ResourceData, ResourceId, ResourceRef,
+    SharedData, SharedDataRw, DATA_FOLDER,
+};
+use nrg_serialize::INVALID_UID;
+use std::{
+    io,
+    path::{Path, PathBuf},
 };
-use nrg_serialize::{generate_uid_from_string, INVALID_UID};
-use std::path::{Path, PathBuf};
 
 pub type FontId = ResourceId;
 pub type FontRc = ResourceRef<FontInstance>;
@@ -33,6 +36,9 @@ impl ResourceData for FontInstance {
     fn id(&self) -> ResourceId {
         self.id
     }
+    fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.font.memory_size()
+    }
 }
 
 impl FileResource for FontInstance {
@@ -40,38 +46,53 @@ impl FileResource for FontInstance {
         self.path.as_path()
     }
     fn create_from_file(shared_data: &SharedDataRw, font_path: &Path) -> FontRc {
-        let pipeline = PipelineInstance::find_from_name(shared_data, UI_PIPELINE_NAME);
         let path = convert_from_local_path(PathBuf::from(DATA_FOLDER).as_path(), font_path);
-        if !path.exists() || !path.is_file() {
-            panic!("Invalid font path {}", path.to_str().unwrap());
-        }
         let font_id = FontInstance::find_id(shared_data, path.as_path());
         if font_id != INVALID_UID {
             return SharedData::get_resource::<Self>(shared_data, font_id);
         }
+        let font = match Font::new(path.as_path()) {
+            Ok(font) => font,
+            Err(e) => {
+                eprintln!("Unable to load font {:?}: {}", path, e);
+                Font::default()
+            }
+        };
+        Self::create(shared_data, path, font)
+    }
+    fn try_create_from_file(shared_data: &SharedDataRw, font_path: &Path) -> io::Result<FontRc> {
+        let path = convert_from_local_path(PathBuf::from(DATA_FOLDER).as_path(), font_path);
+        let font_id = FontInstance::find_id(shared_data, path.as_path());
+        if font_id != INVALID_UID {
+            return Ok(SharedData::get_resource::<Self>(shared_data, font_id));
+        }
+        let font = Font::new(path.as_path())?;
+        Ok(Self::create(shared_data, path, font))
+    }
+}
+
+impl FontInstance {
+    fn create(shared_data: &SharedDataRw, path: PathBuf, font: Font) -> FontRc {
+        let pipeline = PipelineInstance::find_from_name(shared_data, UI_PIPELINE_NAME);
         let material = MaterialInstance::create_from_pipeline(shared_data, pipeline);
-        let font = Font::new(path.as_path());
         let texture_id = TextureInstance::find_id(shared_data, path.as_path());
         let texture = if texture_id.is_nil() {
             TextureInstance::create_from_file(shared_data, path.as_path())
         } else {
             SharedData::get_resource::<TextureInstance>(shared_data, texture_id)
         };
-        material.resource().get_mut().add_texture(texture);
+        material.resource().unwrap().get_mut().add_texture(texture);
 
         SharedData::add_resource(
             shared_data,
             FontInstance {
-                id: generate_uid_from_string(path.to_str().unwrap()),
+                id: asset_uuid(path.as_path()),
                 path,
                 material,
                 font,
             },
         )
     }
-}
-
-impl FontInstance {
     pub fn find_id(shared_data: &SharedDataRw, font_path: &Path) -> FontId {
         let path = convert_from_local_path(PathBuf::from(DATA_FOLDER).as_path(), font_path);
         SharedData::match_resource(shared_data, |f: &FontInstance| f.path == path)
@@ -6,10 +6,10 @@ use crate::{
 };
 use nrg_math::{VecBase, Vector4};
 use nrg_resources::{
-    DataTypeResource, Deserializable, FileResource, ResourceData, ResourceId, ResourceRef,
-    SerializableResource, SharedData, SharedDataRw,
+    asset_uuid, DataTypeResource, Deserializable, FileResource, ResourceData, ResourceId,
+    ResourceRef, SerializableResource, SharedData, SharedDataRw,
 };
-use nrg_serialize::{generate_random_uid, generate_uid_from_string, INVALID_UID};
+use nrg_serialize::{generate_random_uid, INVALID_UID};
 
 pub type MaterialId = ResourceId;
 pub type MaterialRc = ResourceRef<MaterialInstance>;
@@ -54,22 +54,26 @@ impl DataTypeResource for MaterialInstance {
     type DataType = MaterialData;
 
     fn create_from_data(shared_data: &SharedDataRw, material_data: Self::DataType) -> MaterialRc {
+        let material_id = asset_uuid(material_data.path());
         let pipeline =
             PipelineInstance::find_from_name(shared_data, material_data.pipeline_name.as_str());
+        SharedData::add_dependency(shared_data, material_id, pipeline.id());
 
         let mut meshes = Vec::new();
         for m in material_data.meshes.iter() {
             let mesh = MeshInstance::create_from_file(&shared_data, m.as_path());
+            SharedData::add_dependency(shared_data, material_id, mesh.id());
             meshes.push(mesh);
         }
         let mut textures = Vec::new();
         for t in material_data.textures.iter() {
             let texture = TextureInstance::create_from_file(&shared_data, t.as_path());
+            SharedData::add_dependency(shared_data, material_id, texture.id());
             textures.push(texture);
         }
 
         let material = Self {
-            id: generate_uid_from_string(material_data.path().to_str().unwrap()),
+            id: material_id,
             path: material_data.path().to_path_buf(),
             pipeline,
             meshes,
@@ -1,10 +1,10 @@
 use std::path::Path;
 
-use crate::{MeshData, Texture};
-use nrg_math::{Matrix4, Vector4};
+use crate::{MeshData, Texture, VertexData};
+use nrg_math::{Matrix4, Vector2, Vector4};
 use nrg_resources::{
-    DataTypeResource, Deserializable, ResourceData, ResourceId, ResourceRef, SerializableResource,
-    SharedData, SharedDataRw,
+    from_file, DataTypeResource, Deserializable, ResourceData, ResourceId, ResourceRef,
+    SerializableResource, SharedData, SharedDataRw,
 };
 use nrg_serialize::{generate_random_uid, INVALID_UID};
 
@@ -19,12 +19,19 @@ pub struct MeshInstance {
     is_visible: bool,
     is_dirty: bool,
     uv_converted: bool,
+    //Texture coordinates before being converted to the area of the texture
+    tex_coords: Vec<Vector2>,
 }
 
 impl ResourceData for MeshInstance {
     fn id(&self) -> ResourceId {
         self.id
     }
+    fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.mesh_data.vertices.capacity() * std::mem::size_of::<VertexData>()
+            + self.mesh_data.indices.capacity() * std::mem::size_of::<u32>()
+    }
 }
 
 impl Default for MeshInstance {
@@ -36,6 +43,7 @@ impl Default for MeshInstance {
             is_visible: true,
             is_dirty: true,
             uv_converted: false,
+            tex_coords: Vec::new(),
         }
     }
 }
@@ -44,6 +52,10 @@ impl SerializableResource for MeshInstance {
     fn path(&self) -> &Path {
         self.mesh_data.path()
     }
+    fn reload_from_file(mesh: &MeshRc, _shared_data: &SharedDataRw, filepath: &Path) {
+        let mesh_data = from_file::<MeshData>(filepath);
+        mesh.resource().unwrap().get_mut().set_mesh_data(mesh_data);
+    }
 }
 
 impl DataTypeResource for MeshInstance {
@@ -80,6 +92,7 @@ impl MeshInstance {
     pub fn set_mesh_data(&mut self, mesh_data: MeshData) {
         self.mesh_data = mesh_data;
         self.uv_converted = false;
+        self.tex_coords.clear();
         self.is_dirty = true;
     }
     pub fn transform(&self) -> &Matrix4 {
@@ -89,9 +102,34 @@ impl MeshInstance {
         self.draw_area
     }
 
+    pub fn is_uv_converted(&self) -> bool {
+        self.uv_converted
+    }
+    // Restores the texture coordinates so that they can be converted for another texture
+    pub fn reset_uv_conversion(&mut self) -> &mut Self {
+        if self.uv_converted {
+            self.uv_converted = false;
+            for (v, tex_coord) in self
+                .mesh_data
+                .vertices
+                .iter_mut()
+                .zip(self.tex_coords.iter())
+            {
+                v.tex_coord = *tex_coord;
+            }
+            self.is_dirty = true;
+        }
+        self
+    }
     pub fn process_uv_for_texture(&mut self, texture: Option<&Texture>) -> &mut Self {
         if !self.uv_converted {
             self.uv_converted = true;
+            self.tex_coords = self
+                .mesh_data
+                .vertices
+                .iter()
+                .map(|v| v.tex_coord)
+                .collect();
             for v in self.mesh_data.vertices.iter_mut() {
                 let tex_coord = &mut v.tex_coord;
                 if let Some(texture) = texture {
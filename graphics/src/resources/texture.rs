@@ -1,10 +1,13 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use nrg_resources::{
-    convert_from_local_path, FileResource, ResourceData, ResourceId, ResourceRef, SharedData,
-    SharedDataRw, DATA_FOLDER,
+    asset_uuid, file_exists, FileResource, ResourceData, ResourceId, ResourceRef, SharedData,
+    SharedDataRw,
 };
-use nrg_serialize::{generate_uid_from_string, INVALID_UID};
+use nrg_serialize::INVALID_UID;
 
 use crate::INVALID_INDEX;
 
@@ -16,6 +19,7 @@ pub struct TextureInstance {
     path: PathBuf,
     texture_index: i32,
     layer_index: i32,
+    texture_size: usize,
     is_initialized: bool,
 }
 
@@ -26,6 +30,7 @@ impl Default for TextureInstance {
             path: PathBuf::new(),
             texture_index: INVALID_INDEX,
             layer_index: INVALID_INDEX,
+            texture_size: 0,
             is_initialized: false,
         }
     }
@@ -35,6 +40,10 @@ impl ResourceData for TextureInstance {
     fn id(&self) -> ResourceId {
         self.id
     }
+    // The image itself is owned by the renderer but is accounted here
+    fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.texture_size
+    }
 }
 
 impl FileResource for TextureInstance {
@@ -42,27 +51,65 @@ impl FileResource for TextureInstance {
         self.path.as_path()
     }
     fn create_from_file(shared_data: &SharedDataRw, filepath: &Path) -> TextureRc {
-        let path = convert_from_local_path(PathBuf::from(DATA_FOLDER).as_path(), filepath);
-        let texture_id =
-            { SharedData::match_resource(shared_data, |t: &TextureInstance| t.path == path) };
-        if texture_id != INVALID_UID {
+        let texture_id = asset_uuid(filepath);
+        if SharedData::has_resource::<Self>(shared_data, texture_id) {
             return SharedData::get_resource::<Self>(shared_data, texture_id);
         }
-        SharedData::add_resource(shared_data, TextureInstance::create(filepath))
+        let texture = SharedData::add_resource(shared_data, TextureInstance::create(filepath));
+        SharedData::track_file(
+            shared_data,
+            texture.id(),
+            filepath,
+            |shared_data, texture_id| {
+                if let Some(texture) = SharedData::try_get_resource::<Self>(shared_data, texture_id)
+                {
+                    texture.resource().unwrap().get_mut().invalidate();
+                }
+            },
+        );
+        texture
+    }
+    // The image is decoded by the renderer, only its file can be checked here
+    fn try_create_from_file(shared_data: &SharedDataRw, filepath: &Path) -> io::Result<TextureRc> {
+        if !file_exists(filepath) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Unable to find texture {}", filepath.to_string_lossy()),
+            ));
+        }
+        Ok(Self::create_from_file(shared_data, filepath))
     }
 }
 
 impl TextureInstance {
     pub fn find_id(shared_data: &SharedDataRw, texture_path: &Path) -> TextureId {
-        let path = convert_from_local_path(PathBuf::from(DATA_FOLDER).as_path(), texture_path);
-        SharedData::match_resource(shared_data, |t: &TextureInstance| t.path == path)
+        let texture_id = asset_uuid(texture_path);
+        if SharedData::has_resource::<Self>(shared_data, texture_id) {
+            texture_id
+        } else {
+            INVALID_UID
+        }
     }
     pub fn path(&self) -> &Path {
         self.path.as_path()
     }
-    pub fn set_texture_data(&mut self, texture_index: u32, layer_index: u32) -> &mut Self {
+    pub fn set_texture_data(
+        &mut self,
+        texture_index: u32,
+        layer_index: u32,
+        texture_size: usize,
+    ) -> &mut Self {
         self.texture_index = texture_index as _;
         self.layer_index = layer_index as _;
+        self.texture_size = texture_size;
+        self.is_initialized = true;
+        self
+    }
+    // A texture that can't be loaded is not loaded again until its file changes
+    pub fn set_missing(&mut self) -> &mut Self {
+        self.texture_index = INVALID_INDEX;
+        self.layer_index = INVALID_INDEX;
+        self.texture_size = 0;
         self.is_initialized = true;
         self
     }
@@ -81,7 +128,7 @@ impl TextureInstance {
     }
     fn create(texture_path: &Path) -> TextureInstance {
         TextureInstance {
-            id: generate_uid_from_string(texture_path.to_str().unwrap()),
+            id: asset_uuid(texture_path),
             path: texture_path.to_path_buf(),
             ..Default::default()
         }
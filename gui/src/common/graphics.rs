@@ -69,6 +69,7 @@ impl WidgetGraphics {
         self.mesh = MeshInstance::create_from_data(&self.shared_data, mesh_data);
         self.material
             .resource()
+            .unwrap()
             .get_mut()
             .add_mesh(self.mesh.clone());
         self.mark_as_dirty();
@@ -94,6 +95,7 @@ impl WidgetGraphics {
         if self.mesh.id() != INVALID_UID {
             self.material
                 .resource()
+                .unwrap()
                 .get_mut()
                 .remove_mesh(self.mesh.id());
         }
@@ -124,7 +126,11 @@ impl WidgetGraphics {
 
     #[inline]
     pub fn set_mesh_data(&mut self, mesh_data: MeshData) -> &mut Self {
-        self.mesh.resource().get_mut().set_mesh_data(mesh_data);
+        self.mesh
+            .resource()
+            .unwrap()
+            .get_mut()
+            .set_mesh_data(mesh_data);
         self.mark_as_dirty();
         self
     }
@@ -226,14 +232,11 @@ impl WidgetGraphics {
             nrg_profiler::scoped_profile!("widget::graphics_update");
             let visible = self.is_visible() && WidgetGraphics::is_valid_drawing_area(drawing_area);
 
-            self.material
-                .resource()
-                .get_mut()
-                .set_outline_color(self.border_color);
-            self.material
-                .resource()
-                .get_mut()
-                .set_diffuse_color(self.color);
+            if let Some(material) = self.material.resource() {
+                let mut material = material.get_mut();
+                material.set_outline_color(self.border_color);
+                material.set_diffuse_color(self.color);
+            }
 
             let transform = Matrix4::from_translation(self.position)
                 * Matrix4::from_angle_z(Rad::from(Deg(self.rotation.x)))
@@ -241,9 +244,12 @@ impl WidgetGraphics {
                 * Matrix4::from_angle_z(Rad::from(Deg(self.rotation.z)))
                 * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z);
 
-            self.mesh.resource().get_mut().set_transform(transform);
-            self.mesh.resource().get_mut().set_draw_area(drawing_area);
-            self.mesh.resource().get_mut().set_visible(visible);
+            if let Some(mesh) = self.mesh.resource() {
+                let mut mesh = mesh.get_mut();
+                mesh.set_transform(transform);
+                mesh.set_draw_area(drawing_area);
+                mesh.set_visible(visible);
+            }
             self.is_dirty = false;
         }
         self
@@ -7,6 +7,17 @@ macro_rules! implement_widget {
             WidgetCreator, WidgetDataGetter, WidgetGraphics, WidgetNode, WidgetState, WidgetStyle,
         };
 
+        // Widgets and graphs saved with an older WidgetState are upgraded while loading
+        impl nrg_serialize::Versioned for $Type {
+            const VERSION: u32 = 0;
+
+            fn migrate_nested(
+                value: &mut nrg_serialize::SerializedValue,
+            ) -> nrg_serialize::SerializeResult<()> {
+                nrg_serialize::migrate_all::<WidgetState>(value, "state")
+            }
+        }
+
         #[typetag::serde]
         impl WidgetDataGetter for $Type {
             #[inline]
@@ -238,10 +249,15 @@ macro_rules! implement_widget_with_data {
                 global_messenger: &nrg_messenger::MessengerRw,
                 filepath: std::path::PathBuf,
             ) -> $Type {
-                let mut w = $Type {
-                    data: WidgetData::new(shared_data.clone(), global_messenger.clone()),
+                let mut w = match nrg_resources::read_versioned_file::<$Type>(filepath.as_path()) {
+                    Ok(w) => w,
+                    Err(e) => {
+                        eprintln!("Unable to load widget {}", e);
+                        $Type {
+                            data: WidgetData::new(shared_data.clone(), global_messenger.clone()),
+                        }
+                    }
                 };
-                nrg_serialize::deserialize_from_file(&mut w, filepath);
                 w.data
                     .load_override(shared_data.clone(), global_messenger.clone());
                 w.init();
@@ -283,11 +299,16 @@ macro_rules! implement_widget_with_custom_members {
                 global_messenger: &nrg_messenger::MessengerRw,
                 filepath: std::path::PathBuf,
             ) -> $Type {
-                let mut w = $Type {
-                    data: WidgetData::new(shared_data.clone(), global_messenger.clone()),
-                    $($field: $value),+
+                let mut w = match nrg_resources::read_versioned_file::<$Type>(filepath.as_path()) {
+                    Ok(w) => w,
+                    Err(e) => {
+                        eprintln!("Unable to load widget {}", e);
+                        $Type {
+                            data: WidgetData::new(shared_data.clone(), global_messenger.clone()),
+                            $($field: $value),+
+                        }
+                    }
                 };
-                nrg_serialize::deserialize_from_file(&mut w, filepath);
                 w.data.load_override(shared_data.clone(), global_messenger.clone());
                 w.init();
                 w
@@ -1,5 +1,5 @@
 use nrg_math::{VecBase, Vector2, Vector4};
-use nrg_serialize::{Deserialize, Serialize};
+use nrg_serialize::{Deserialize, Serialize, Versioned};
 
 use crate::{
     ContainerFillType, HorizontalAlignment, VerticalAlignment, WidgetStyle, COLOR_TRANSPARENT,
@@ -9,6 +9,8 @@ use crate::{
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "nrg_serialize")]
 pub struct WidgetState {
+    #[serde(default)]
+    version: u32,
     pos_in_px: Vector2,
     size_in_px: Vector2,
     is_active: bool,
@@ -39,6 +41,7 @@ impl Default for WidgetState {
     #[inline]
     fn default() -> Self {
         Self {
+            version: Self::VERSION,
             pos_in_px: Vector2::default_zero(),
             size_in_px: DEFAULT_WIDGET_SIZE.into(),
             is_active: true,
@@ -62,6 +65,11 @@ impl Default for WidgetState {
     }
 }
 
+// Version 1 is the first versioned layout: older widgets only get their version
+impl Versioned for WidgetState {
+    const VERSION: u32 = 1;
+}
+
 impl WidgetState {
     #[inline]
     pub fn set_dirty(&mut self, value: bool) {
@@ -3,7 +3,7 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use nrg_core::JobHandlerRw;
+use nrg_core::{JobHandle, JobHandlerRw};
 use nrg_messenger::MessengerRw;
 use nrg_resources::SharedDataRw;
 
@@ -77,7 +77,7 @@ impl Gui {
     }
 
     #[inline]
-    pub fn add_additional_job<F>(name: &str, func: F)
+    pub fn add_additional_job<F>(name: &str, func: F) -> JobHandle
     where
         F: FnOnce() + Send + Sync + 'static,
     {
@@ -87,11 +87,14 @@ impl Gui {
             .job_handler
             .write()
             .unwrap()
-            .add_job(name, func);
+            .add_job(name, func)
     }
 
     #[inline]
-    pub fn update_widgets(job_handler: &JobHandlerRw, first_reduce_draw_area: bool) {
+    pub fn update_widgets(
+        job_handler: &JobHandlerRw,
+        first_reduce_draw_area: bool,
+    ) -> Vec<JobHandle> {
         let mut widget_area = Screen::get_draw_area();
         let mut next_area = widget_area;
         Gui::get()
@@ -101,7 +104,7 @@ impl Gui {
             .get_children()
             .iter()
             .enumerate()
-            .for_each(|(i, w)| {
+            .map(|(i, w)| {
                 let widget = w.clone();
                 if first_reduce_draw_area && i == 0 {
                     let size = widget.read().unwrap().state().get_size();
@@ -113,14 +116,16 @@ impl Gui {
                         - DEFAULT_WIDGET_HEIGHT;
                 }
                 let job_name = String::from(widget.read().unwrap().node().get_name());
-                job_handler
+                let handle = job_handler
                     .write()
                     .unwrap()
                     .add_job(job_name.as_str(), move || {
                         widget.write().unwrap().update(widget_area, widget_area);
                     });
                 widget_area = next_area;
-            });
+                handle
+            })
+            .collect()
     }
 
     #[inline]
@@ -1,9 +1,9 @@
 use std::any::TypeId;
 
 use nrg_math::{Vector2, Vector4};
-use nrg_messenger::{implement_undoable_message, Message};
+use nrg_messenger::{implement_undoable_message, Message, SerializableMessage};
 use nrg_platform::MouseEvent;
-use nrg_serialize::{Deserialize, Serialize, Uid, INVALID_UID};
+use nrg_serialize::{typetag, Deserialize, Serialize, Uid, INVALID_UID};
 
 use crate::{
     implement_widget_with_custom_members, InternalWidget, Panel, Screen, Text, WidgetData,
@@ -15,12 +15,15 @@ pub const DEFAULT_CHECKBOX_SIZE: [f32; 2] = [
     DEFAULT_WIDGET_HEIGHT / 2. * 3.,
 ];
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "nrg_serialize")]
 pub enum CheckboxEvent {
     Checked(Uid),
     Unchecked(Uid),
 }
 implement_undoable_message!(CheckboxEvent, undo_event, debug_info_event);
+#[typetag::serde]
+impl SerializableMessage for CheckboxEvent {}
 fn undo_event(event: &CheckboxEvent) -> CheckboxEvent {
     match event {
         CheckboxEvent::Checked(widget_id) => CheckboxEvent::Unchecked(*widget_id),
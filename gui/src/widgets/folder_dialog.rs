@@ -13,7 +13,11 @@ use nrg_messenger::{implement_message, Message};
 use nrg_resources::DATA_RAW_FOLDER;
 use nrg_serialize::*;
 
-#[derive(Clone)]
+// Argument used to tell a dialog opened in another process who is waiting for its DialogEvent
+pub const DIALOG_REQUESTER_ARG: &str = "--requester";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "nrg_serialize")]
 pub enum DialogEvent {
     Confirmed(Uid, Uid, PathBuf), //my uid, requester uid, text
     Canceled(Uid),
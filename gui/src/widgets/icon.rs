@@ -112,6 +112,7 @@ impl Icon {
             if !self.texture.id().is_nil() {
                 material
                     .resource()
+                    .unwrap()
                     .get_mut()
                     .remove_texture(self.texture.id());
             }
@@ -120,6 +121,7 @@ impl Icon {
                 TextureInstance::create_from_file(self.get_shared_data(), texture_path.as_path());
             material
                 .resource()
+                .unwrap()
                 .get_mut()
                 .add_texture(self.texture.clone());
         }
@@ -2,10 +2,10 @@ use std::any::TypeId;
 
 use nrg_graphics::{FontInstance, FontRc, MaterialRc, MeshData};
 use nrg_math::{Vector2, Vector4};
-use nrg_messenger::{implement_undoable_message, Message};
+use nrg_messenger::{implement_undoable_message, Message, SerializableMessage};
 use nrg_platform::{MouseEvent, MouseState};
 use nrg_resources::{ResourceRef, SharedData};
-use nrg_serialize::{Deserialize, Serialize, Uid};
+use nrg_serialize::{typetag, Deserialize, Serialize, Uid};
 
 use crate::{
     implement_widget_with_custom_members, InternalWidget, Screen, WidgetData,
@@ -15,12 +15,15 @@ use crate::{
 pub const DEFAULT_TEXT_SIZE: [f32; 2] =
     [DEFAULT_WIDGET_WIDTH * 100., DEFAULT_WIDGET_HEIGHT / 4. * 3.];
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "nrg_serialize")]
 pub enum TextEvent {
     AddChar(Uid, i32, char),
     RemoveChar(Uid, i32, char),
 }
 implement_undoable_message!(TextEvent, undo_event, debug_info_event);
+#[typetag::serde]
+impl SerializableMessage for TextEvent {}
 fn undo_event(event: &TextEvent) -> TextEvent {
     match event {
         TextEvent::AddChar(widget_id, character_index, character) => {
@@ -183,7 +186,11 @@ impl Text {
                 mesh_data.add_quad(
                     Vector4::new(pos_x, pos_y, pos_x + char_width, pos_y + char_height),
                     0.,
-                    self.font.resource().get().glyph_texture_coord(*c as _),
+                    self.font
+                        .resource()
+                        .unwrap()
+                        .get()
+                        .glyph_texture_coord(*c as _),
                     Some(mesh_index),
                 );
                 mesh_index += 4;
@@ -200,7 +207,7 @@ impl InternalWidget for Text {
     fn widget_init(&mut self) {
         let font_id = FontInstance::get_default(self.get_shared_data());
         self.font = SharedData::get_resource::<FontInstance>(self.get_shared_data(), font_id);
-        self.material = self.font.resource().get().material();
+        self.material = self.font.resource().unwrap().get().material();
         let material = self.material.clone();
         self.graphics_mut().link_to_material(material);
         if self.is_initialized() {
@@ -0,0 +1,335 @@
+use std::{
+    any::type_name,
+    collections::HashMap,
+    io::{self, ErrorKind, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+use nrg_serialize::{generate_random_uid, try_deserialize, try_serialize, Deserialize, Serialize};
+
+use crate::{new_message_origin, Message, MessageOrigin, MessengerRw};
+
+pub const BRIDGE_ARG: &str = "--bridge";
+pub const BRIDGE_TOKEN_ARG: &str = "--bridge-token";
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+type OutgoingMessages = Box<dyn Fn() -> Vec<BridgeMessage> + Send + Sync>;
+type IncomingMessage = Box<dyn Fn(&str) -> Option<Box<dyn Message>> + Send + Sync>;
+
+// One message per line: the payload is the message serialized on its own
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "nrg_serialize")]
+struct BridgeMessage {
+    type_name: String,
+    data: String,
+}
+
+// First line sent by a client: the host closes the connections without its token
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "nrg_serialize")]
+struct BridgeHello {
+    token: String,
+}
+
+impl BridgeHello {
+    // Compares the whole token to not tell how much of it is right
+    fn is_valid(line: &str, token: &str) -> bool {
+        match try_deserialize::<BridgeHello>(line) {
+            Ok(hello) => {
+                hello.token.len() == token.len()
+                    && hello
+                        .token
+                        .bytes()
+                        .zip(token.bytes())
+                        .fold(0, |diff, (a, b)| diff | (a ^ b))
+                        == 0
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+struct BridgeConnection {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    pending: Vec<u8>,
+    is_verified: bool,
+    connected_at: Instant,
+}
+
+impl BridgeConnection {
+    fn new(stream: TcpStream, is_verified: bool) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            pending: Vec::new(),
+            is_verified,
+            connected_at: Instant::now(),
+        })
+    }
+
+    #[inline]
+    fn write_line(&mut self, line: &str) {
+        self.pending.extend_from_slice(line.as_bytes());
+        self.pending.push(b'\n');
+    }
+
+    // Writes what the socket accepts now, the rest is retried in the next update
+    fn flush(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        while written < self.pending.len() {
+            match self.stream.write(&self.pending[written..]) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => written += n,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        self.pending.drain(..written);
+        Ok(())
+    }
+
+    // Returns the complete lines received so far or an error when the peer disconnected
+    fn read_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut data = [0u8; 4096];
+        loop {
+            match self.stream.read(&mut data) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.buffer.extend_from_slice(&data[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        let mut lines = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            lines.push(String::from_utf8_lossy(&line[..pos]).to_string());
+        }
+        Ok(lines)
+    }
+}
+
+// Forwards the selected message types between the messengers of different processes
+// through localhost sockets. Messages received from other processes are dispatched
+// to the local messenger with the bridge origin and are not sent again to any other process.
+// Any local process can connect to the port: the host forwards messages only to and from
+// clients that sent its random token, passed to them as "--bridge-token <token>"
+pub struct MessengerBridge {
+    origin: MessageOrigin,
+    token: String,
+    messenger: MessengerRw,
+    listener: Option<TcpListener>,
+    connections: Vec<BridgeConnection>,
+    outgoing: Vec<OutgoingMessages>,
+    incoming: HashMap<String, IncomingMessage>,
+}
+
+impl MessengerBridge {
+    fn new(messenger: &MessengerRw, token: &str) -> Self {
+        Self {
+            origin: new_message_origin(),
+            token: token.to_string(),
+            messenger: messenger.clone(),
+            listener: None,
+            connections: Vec::new(),
+            outgoing: Vec::new(),
+            incoming: HashMap::new(),
+        }
+    }
+
+    // Port 0 lets the system choose a free one, see local_port()
+    pub fn host(messenger: &MessengerRw, port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))?;
+        listener.set_nonblocking(true)?;
+        let mut bridge = Self::new(messenger, generate_random_uid().to_string().as_str());
+        bridge.listener = Some(listener);
+        Ok(bridge)
+    }
+
+    pub fn connect(messenger: &MessengerRw, port: u16, token: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))?;
+        let mut connection = BridgeConnection::new(stream, true)?;
+        let hello = BridgeHello {
+            token: token.to_string(),
+        };
+        connection.write_line(
+            try_serialize(&hello)
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?
+                .as_str(),
+        );
+        connection.flush()?;
+        let mut bridge = Self::new(messenger, token);
+        bridge.connections.push(connection);
+        Ok(bridge)
+    }
+
+    // Connects to the port passed to the process as "--bridge <port> --bridge-token <token>", if any
+    pub fn connect_from_args(messenger: &MessengerRw) -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let arg = |name: &str| {
+            args.iter()
+                .position(|a| a == name)
+                .and_then(|i| args.get(i + 1))
+        };
+        let port = arg(BRIDGE_ARG).and_then(|p| p.parse::<u16>().ok())?;
+        let token = match arg(BRIDGE_TOKEN_ARG) {
+            Some(token) => token,
+            None => {
+                eprintln!(
+                    "Unable to connect messenger bridge without {}",
+                    BRIDGE_TOKEN_ARG
+                );
+                return None;
+            }
+        };
+        match Self::connect(messenger, port, token) {
+            Ok(bridge) => Some(bridge),
+            Err(e) => {
+                eprintln!("Unable to connect messenger bridge on port {}: {}", port, e);
+                None
+            }
+        }
+    }
+
+    #[inline]
+    pub fn local_port(&self) -> Option<u16> {
+        self.listener
+            .as_ref()
+            .and_then(|l| l.local_addr().ok())
+            .map(|a| a.port())
+    }
+
+    // Arguments to start a process that connects to this bridge with connect_from_args
+    pub fn connect_args(&self) -> Option<Vec<String>> {
+        self.local_port().map(|port| {
+            vec![
+                BRIDGE_ARG.to_string(),
+                port.to_string(),
+                BRIDGE_TOKEN_ARG.to_string(),
+                self.token.clone(),
+            ]
+        })
+    }
+
+    #[inline]
+    pub fn connections_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    // Messages of this type are sent to the other processes and received from them
+    pub fn forward<T>(&mut self) -> &mut Self
+    where
+        T: Message + Clone + Serialize + for<'de> Deserialize<'de> + 'static,
+    {
+        let subscription = self
+            .messenger
+            .write()
+            .unwrap()
+            .subscribe_from_origin::<T>(self.origin);
+        self.outgoing.push(Box::new(move || {
+            subscription
+                .read_all()
+                .iter()
+                .filter_map(|msg| match try_serialize(msg) {
+                    Ok(data) => Some(BridgeMessage {
+                        type_name: type_name::<T>().to_string(),
+                        data,
+                    }),
+                    Err(e) => {
+                        eprintln!("Unable to forward {}: {}", type_name::<T>(), e);
+                        None
+                    }
+                })
+                .collect()
+        }));
+        self.incoming.insert(
+            type_name::<T>().to_string(),
+            Box::new(|data| match try_deserialize::<T>(data) {
+                Ok(msg) => Some(msg.as_boxed()),
+                Err(e) => {
+                    eprintln!("Unable to receive {}: {}", type_name::<T>(), e);
+                    None
+                }
+            }),
+        );
+        self
+    }
+
+    // To be called every frame: it never blocks waiting for other processes
+    pub fn update(&mut self) -> &mut Self {
+        self.accept_connections().send_messages().receive_messages()
+    }
+
+    fn accept_connections(&mut self) -> &mut Self {
+        if let Some(listener) = &self.listener {
+            while let Ok((stream, _)) = listener.accept() {
+                match BridgeConnection::new(stream, false) {
+                    Ok(connection) => self.connections.push(connection),
+                    Err(e) => eprintln!("Unable to accept messenger bridge connection: {}", e),
+                }
+            }
+        }
+        self
+    }
+
+    fn send_messages(&mut self) -> &mut Self {
+        let mut lines = Vec::new();
+        for outgoing in self.outgoing.iter() {
+            for msg in outgoing() {
+                if let Ok(line) = try_serialize(&msg) {
+                    lines.push(line);
+                }
+            }
+        }
+        self.connections.retain_mut(|c| {
+            if c.is_verified {
+                lines.iter().for_each(|line| c.write_line(line));
+            }
+            c.flush().is_ok()
+        });
+        self
+    }
+
+    fn receive_messages(&mut self) -> &mut Self {
+        let mut lines = Vec::new();
+        let token = self.token.as_str();
+        self.connections.retain_mut(|c| match c.read_lines() {
+            Ok(mut l) => {
+                if !c.is_verified {
+                    if l.is_empty() {
+                        return c.connected_at.elapsed() < HELLO_TIMEOUT;
+                    }
+                    if !BridgeHello::is_valid(l.remove(0).as_str(), token) {
+                        eprintln!("Messenger bridge connection refused: invalid token");
+                        return false;
+                    }
+                    c.is_verified = true;
+                }
+                lines.append(&mut l);
+                true
+            }
+            Err(_) => false,
+        });
+        for line in lines {
+            let msg = match try_deserialize::<BridgeMessage>(line.as_str()) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    eprintln!("Invalid messenger bridge message: {}", e);
+                    continue;
+                }
+            };
+            if let Some(incoming) = self.incoming.get(&msg.type_name) {
+                if let Some(message) = incoming(msg.data.as_str()) {
+                    self.messenger
+                        .read()
+                        .unwrap()
+                        .send_from(self.origin, message);
+                }
+            }
+        }
+        self
+    }
+}
@@ -1,11 +1,12 @@
 use crate::Message;
 use std::sync::{
     mpsc::{channel, Receiver, Sender},
-    Arc, RwLock,
+    Arc, RwLock, Weak,
 };
 
 pub type MessageBox = Arc<RwLock<Sender<Box<dyn Message>>>>;
 pub type Listener = Arc<RwLock<Receiver<Box<dyn Message>>>>;
+pub type WeakMessageBox = Weak<RwLock<Sender<Box<dyn Message>>>>;
 
 pub struct MessageChannel {
     dispatcher: MessageBox,
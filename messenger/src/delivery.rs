@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+use crate::Message;
+
+// Messages with higher priority are dispatched first within the same frame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MessagePriority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+// Every message is queued until the frame dispatch:
+// in order ones are dispatched as they have been sent,
+// batched ones after all the in order messages of the frame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageDelivery {
+    #[default]
+    InOrder,
+    Batched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliverAt {
+    NextFrame,
+    After(Duration),
+}
+
+pub(crate) struct DeferredMessage {
+    pub message: Box<dyn Message>,
+    frame: u64,
+    time: Instant,
+}
+
+impl DeferredMessage {
+    pub fn new(message: Box<dyn Message>, frame: u64, deliver_at: DeliverAt) -> Self {
+        let time = match deliver_at {
+            DeliverAt::NextFrame => Instant::now(),
+            DeliverAt::After(delay) => Instant::now() + delay,
+        };
+        Self {
+            message,
+            frame,
+            time,
+        }
+    }
+
+    // Never delivered in the same frame it has been sent
+    #[inline]
+    pub fn is_ready(&self, frame: u64, now: Instant) -> bool {
+        self.frame < frame && self.time <= now
+    }
+}
@@ -1,9 +1,18 @@
 pub use macros::*;
+pub use bridge::*;
 pub use channel::*;
+pub use delivery::*;
 pub use message::*;
 pub use messenger::*;
+pub use subscription::*;
 
+pub mod bridge;
 pub mod channel;
+pub mod delivery;
 pub mod macros;
 pub mod message;
 pub mod messenger;
+pub mod subscription;
+
+#[cfg(test)]
+mod test;
@@ -12,7 +12,7 @@ macro_rules! implement_message {
             }
             #[inline]
             fn redo(&self, events_rw: &$crate::MessageBox) {
-                let mut events = events_rw.write().unwrap();
+                let events = events_rw.write().unwrap();
                 events.send(self.as_boxed()).ok();
             }
             #[inline]
@@ -29,7 +29,7 @@ macro_rules! implement_message {
 
 #[macro_export]
 macro_rules! implement_undoable_message {
-    ($Type:ident, $func: ident, $debug_func: ident) => {
+    (@impl $Type:ident, $func: ident, $debug_func: ident, {$($merge:tt)*}) => {
         impl $crate::Message for $Type {
             #[inline]
             fn as_any(&self) -> &dyn std::any::Any {
@@ -41,12 +41,12 @@ macro_rules! implement_undoable_message {
             }
             #[inline]
             fn redo(&self, events_rw: &$crate::MessageBox) {
-                let mut events = events_rw.write().unwrap();
+                let events = events_rw.write().unwrap();
                 events.send(self.as_boxed()).ok();
             }
             #[inline]
             fn undo(&self, events_rw: &$crate::MessageBox) {
-                let mut events = events_rw.write().unwrap();
+                let events = events_rw.write().unwrap();
                 let event_to_send = $func(self);
                 events.send(event_to_send.as_boxed()).ok();
             }
@@ -54,6 +54,21 @@ macro_rules! implement_undoable_message {
             fn get_debug_info(&self) -> String {
                 $debug_func(self)
             }
+            $($merge)*
         }
     };
+    ($Type:ident, $func: ident, $debug_func: ident) => {
+        $crate::implement_undoable_message!(@impl $Type, $func, $debug_func, {});
+    };
+    ($Type:ident, $func: ident, $debug_func: ident, $merge_func: ident) => {
+        $crate::implement_undoable_message!(@impl $Type, $func, $debug_func, {
+            #[inline]
+            fn merge(&self, next: &dyn $crate::Message) -> Option<Box<dyn $crate::Message>> {
+                next.as_any()
+                    .downcast_ref::<$Type>()
+                    .and_then(|next| $merge_func(self, next))
+                    .map(|merged| merged.as_boxed())
+            }
+        });
+    };
 }
@@ -1,5 +1,7 @@
 use std::any::{type_name, Any};
 
+use nrg_serialize::typetag;
+
 use crate::MessageBox;
 
 pub trait Message: Send + Sync + Any {
@@ -21,4 +23,16 @@ pub trait Message: Send + Sync + Any {
     }
     fn get_debug_info(&self) -> String;
     fn as_boxed(&self) -> Box<dyn Message>;
+
+    // Consecutive undoable messages that can be combined in a single one, like the steps of a drag,
+    // return the message that has the effect of both
+    #[inline]
+    fn merge(&self, _next: &dyn Message) -> Option<Box<dyn Message>> {
+        None
+    }
 }
+
+// Messages that can be saved, like the undoable ones kept in a session journal.
+// Implementors use #[typetag::serde] like widgets do
+#[typetag::serde(tag = "message")]
+pub trait SerializableMessage: Message {}
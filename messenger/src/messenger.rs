@@ -1,16 +1,43 @@
 use std::{
     any::TypeId,
     collections::HashMap,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Instant,
 };
 
-use crate::{Listener, Message, MessageBox, MessageChannel};
+use crate::{
+    DeferredMessage, DeliverAt, Listener, Message, MessageBox, MessageChannel, MessageDelivery,
+    MessagePriority, Subscription, WeakMessageBox,
+};
 
 pub type MessengerRw = Arc<RwLock<Messenger>>;
+
+// Identifies who sent a message, like a MessengerBridge for the ones of other processes,
+// so that the subscriptions of the same origin don't receive it back
+pub type MessageOrigin = u64;
+
+static NEXT_MESSAGE_ORIGIN: AtomicU64 = AtomicU64::new(1);
+
+#[inline]
+pub fn new_message_origin() -> MessageOrigin {
+    NEXT_MESSAGE_ORIGIN.fetch_add(1, Ordering::SeqCst)
+}
+
 pub struct Messenger {
     message_channel: MessageChannel,
-    messageboxes: HashMap<TypeId, Vec<MessageBox>>,
+    replay_channel: MessageChannel,
+    messageboxes: HashMap<TypeId, Vec<(MessageBox, Option<MessageOrigin>)>>,
+    subscriptions: HashMap<TypeId, Vec<(WeakMessageBox, Option<MessageOrigin>)>>,
     registered_types: Vec<TypeId>,
+    muted_types: Vec<TypeId>,
+    priorities: HashMap<TypeId, MessagePriority>,
+    deliveries: HashMap<TypeId, MessageDelivery>,
+    deferred_messages: Mutex<Vec<DeferredMessage>>,
+    origin_messages: Mutex<Vec<(MessageOrigin, Box<dyn Message>)>>,
+    frame: AtomicU64,
 }
 
 unsafe impl Send for Messenger {}
@@ -21,8 +48,16 @@ impl Default for Messenger {
     fn default() -> Self {
         Self {
             message_channel: MessageChannel::default(),
+            replay_channel: MessageChannel::default(),
             messageboxes: HashMap::new(),
+            subscriptions: HashMap::new(),
             registered_types: Vec::new(),
+            muted_types: Vec::new(),
+            priorities: HashMap::new(),
+            deliveries: HashMap::new(),
+            deferred_messages: Mutex::new(Vec::new()),
+            origin_messages: Mutex::new(Vec::new()),
+            frame: AtomicU64::new(0),
         }
     }
 }
@@ -33,6 +68,78 @@ impl Messenger {
         self.message_channel.get_messagebox()
     }
 
+    // Messages sent here are dispatched even when their type is muted
+    #[inline]
+    pub fn get_replay_dispatcher(&self) -> MessageBox {
+        self.replay_channel.get_messagebox()
+    }
+
+    #[inline]
+    pub fn mute_type<T>(&mut self) -> &mut Self
+    where
+        T: Message + 'static,
+    {
+        let typeid = TypeId::of::<T>();
+        if !self.muted_types.contains(&typeid) {
+            self.muted_types.push(typeid);
+        }
+        self
+    }
+
+    #[inline]
+    pub fn unmute_type<T>(&mut self) -> &mut Self
+    where
+        T: Message + 'static,
+    {
+        let typeid = TypeId::of::<T>();
+        self.muted_types.retain(|t| *t != typeid);
+        self
+    }
+
+    #[inline]
+    pub fn set_priority<T>(&mut self, priority: MessagePriority) -> &mut Self
+    where
+        T: Message + 'static,
+    {
+        self.priorities.insert(TypeId::of::<T>(), priority);
+        self
+    }
+
+    #[inline]
+    pub fn set_delivery<T>(&mut self, delivery: MessageDelivery) -> &mut Self
+    where
+        T: Message + 'static,
+    {
+        self.deliveries.insert(TypeId::of::<T>(), delivery);
+        self
+    }
+
+    #[inline]
+    fn delivery_order(&self, msg: &dyn Message) -> (bool, MessagePriority) {
+        let typeid = msg.type_id();
+        (
+            self.deliveries.get(&typeid) == Some(&MessageDelivery::Batched),
+            self.priorities.get(&typeid).cloned().unwrap_or_default(),
+        )
+    }
+
+    // Queues the message to be dispatched in a following frame
+    #[inline]
+    pub fn send_deferred(&self, msg: Box<dyn Message>, deliver_at: DeliverAt) {
+        let frame = self.frame.load(Ordering::SeqCst);
+        self.deferred_messages
+            .lock()
+            .unwrap()
+            .push(DeferredMessage::new(msg, frame, deliver_at));
+    }
+
+    // Queues the message to be dispatched to all the listeners
+    // except the subscriptions of the same origin
+    #[inline]
+    pub fn send_from(&self, origin: MessageOrigin, msg: Box<dyn Message>) {
+        self.origin_messages.lock().unwrap().push((origin, msg));
+    }
+
     #[inline]
     pub fn register_type<T>(&mut self)
     where
@@ -55,19 +162,39 @@ impl Messenger {
         T: Message + 'static,
     {
         let typeid = TypeId::of::<T>();
-        self.register_messagebox_for_typeid(typeid, messagebox);
+        self.register_messagebox_for_typeid(typeid, messagebox, None);
+        self
+    }
+
+    // The messagebox doesn't receive the messages sent with send_from by the same origin
+    #[inline]
+    pub fn register_messagebox_from_origin<T>(
+        &mut self,
+        messagebox: MessageBox,
+        origin: MessageOrigin,
+    ) -> &mut Self
+    where
+        T: Message + 'static,
+    {
+        let typeid = TypeId::of::<T>();
+        self.register_messagebox_for_typeid(typeid, messagebox, Some(origin));
         self
     }
 
     #[inline]
-    fn register_messagebox_for_typeid(&mut self, typeid: TypeId, messagebox: MessageBox) {
+    fn register_messagebox_for_typeid(
+        &mut self,
+        typeid: TypeId,
+        messagebox: MessageBox,
+        origin: Option<MessageOrigin>,
+    ) {
         self.register_type_with_id(typeid);
         let messageboxes = self.messageboxes.entry(typeid).or_insert_with(Vec::new);
         let index = messageboxes
             .iter()
-            .position(|e| std::ptr::eq(e.as_ref(), messagebox.as_ref()));
+            .position(|(e, _)| std::ptr::eq(e.as_ref(), messagebox.as_ref()));
         if index.is_none() {
-            messageboxes.push(messagebox);
+            messageboxes.push((messagebox, origin));
         }
     }
 
@@ -84,21 +211,114 @@ impl Messenger {
     #[inline]
     fn unregister_messagebox_for_typeid(&mut self, typeid: TypeId, messagebox: MessageBox) {
         let messageboxes = self.messageboxes.entry(typeid).or_insert_with(Vec::new);
-        messageboxes.retain(|e| !std::ptr::eq(e.as_ref(), messagebox.as_ref()));
+        messageboxes.retain(|(e, _)| !std::ptr::eq(e.as_ref(), messagebox.as_ref()));
+    }
+
+    // The messenger keeps only a weak reference to the subscription:
+    // dropping it is enough to stop receiving messages
+    #[inline]
+    pub fn subscribe<T>(&mut self) -> Subscription<T>
+    where
+        T: Message + 'static,
+    {
+        self.subscribe_with_origin(None)
+    }
+
+    // The subscription doesn't receive the messages sent with send_from() by the same origin
+    #[inline]
+    pub fn subscribe_from_origin<T>(&mut self, origin: MessageOrigin) -> Subscription<T>
+    where
+        T: Message + 'static,
+    {
+        self.subscribe_with_origin(Some(origin))
+    }
+
+    fn subscribe_with_origin<T>(&mut self, origin: Option<MessageOrigin>) -> Subscription<T>
+    where
+        T: Message + 'static,
+    {
+        let typeid = TypeId::of::<T>();
+        self.register_type_with_id(typeid);
+        let subscription = Subscription::new();
+        let subscriptions = self.subscriptions.entry(typeid).or_default();
+        subscriptions.retain(|(s, _)| s.strong_count() > 0);
+        subscriptions.push((Arc::downgrade(&subscription.get_messagebox()), origin));
+        subscription
+    }
+
+    #[inline]
+    pub fn subscriptions_count<T>(&self) -> usize
+    where
+        T: Message + 'static,
+    {
+        self.subscriptions.get(&TypeId::of::<T>()).map_or(0, |s| {
+            s.iter().filter(|(s, _)| s.strong_count() > 0).count()
+        })
     }
 
+    // Collects the messages of the frame and dispatches them ordered by delivery and priority:
+    // messages with the same delivery and priority keep the order they have been sent
     pub fn process_messages<F>(&self, mut f: F)
     where
         F: FnMut(&dyn Message),
     {
-        read_messages(self.message_channel.get_listener(), |msg: &dyn Message| {
-            f(msg);
-            if let Some(messageboxes) = self.messageboxes.get(&msg.type_id()) {
-                for messagebox in messageboxes.iter() {
-                    let _ = messagebox.write().unwrap().send(msg.as_boxed());
-                }
+        let frame = self.frame.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut messages = Vec::new();
+        {
+            let now = Instant::now();
+            let mut deferred_messages = self.deferred_messages.lock().unwrap();
+            let (ready, pending): (Vec<_>, Vec<_>) = deferred_messages
+                .drain(..)
+                .partition(|m| m.is_ready(frame, now));
+            *deferred_messages = pending;
+            messages.extend(ready.into_iter().map(|m| (None, m.message)));
+        }
+        let listener = self.message_channel.get_listener();
+        while let Ok(msg) = listener.read().unwrap().try_recv() {
+            messages.push((None, msg));
+        }
+        messages.extend(
+            self.origin_messages
+                .lock()
+                .unwrap()
+                .drain(..)
+                .map(|(origin, msg)| (Some(origin), msg)),
+        );
+        messages.retain(|(_, msg)| !self.muted_types.contains(&msg.as_ref().type_id()));
+        let listener = self.replay_channel.get_listener();
+        while let Ok(msg) = listener.read().unwrap().try_recv() {
+            messages.push((None, msg));
+        }
+
+        messages.sort_by_key(|(_, msg)| self.delivery_order(msg.as_ref()));
+        for (origin, msg) in messages.iter() {
+            self.dispatch_message(msg.as_ref(), *origin, &mut f);
+        }
+    }
+
+    #[inline]
+    fn dispatch_message<F>(&self, msg: &dyn Message, origin: Option<MessageOrigin>, f: &mut F)
+    where
+        F: FnMut(&dyn Message),
+    {
+        f(msg);
+        if let Some(messageboxes) = self.messageboxes.get(&msg.type_id()) {
+            for (messagebox, _) in messageboxes
+                .iter()
+                .filter(|(_, o)| o.is_none() || *o != origin)
+            {
+                let _ = messagebox.write().unwrap().send(msg.as_boxed());
             }
-        });
+        }
+        if let Some(subscriptions) = self.subscriptions.get(&msg.type_id()) {
+            for messagebox in subscriptions
+                .iter()
+                .filter(|(_, o)| o.is_none() || *o != origin)
+                .filter_map(|(s, _)| s.upgrade())
+            {
+                let _ = messagebox.write().unwrap().send(msg.as_boxed());
+            }
+        }
     }
 }
 
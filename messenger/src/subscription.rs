@@ -0,0 +1,65 @@
+use crate::{read_messages, Message, MessageBox, MessageChannel};
+
+pub type MessageFilter<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+// Typed listener created with Messenger::subscribe::<T>():
+// it receives only messages of type T matching all its filters
+// and it is unregistered from the messenger when dropped
+pub struct Subscription<T>
+where
+    T: Message + 'static,
+{
+    message_channel: MessageChannel,
+    filters: Vec<MessageFilter<T>>,
+}
+
+impl<T> Subscription<T>
+where
+    T: Message + 'static,
+{
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            message_channel: MessageChannel::default(),
+            filters: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_messagebox(&self) -> MessageBox {
+        self.message_channel.get_messagebox()
+    }
+
+    #[inline]
+    pub fn filter<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Box::new(f));
+        self
+    }
+
+    // Consumes all the messages received since the last read
+    pub fn read<F>(&self, mut f: F)
+    where
+        F: FnMut(&T),
+    {
+        read_messages(self.message_channel.get_listener(), |msg| {
+            if let Some(msg) = msg.as_any().downcast_ref::<T>() {
+                if self.filters.iter().all(|filter| filter(msg)) {
+                    f(msg);
+                }
+            }
+        });
+    }
+
+    #[inline]
+    pub fn read_all(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut messages = Vec::new();
+        self.read(|msg| messages.push(msg.clone()));
+        messages
+    }
+}
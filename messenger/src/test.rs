@@ -0,0 +1,200 @@
+use std::sync::{Arc, RwLock};
+
+use nrg_serialize::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone)]
+struct KeyTestEvent(u32);
+implement_message!(KeyTestEvent);
+
+#[derive(Clone)]
+struct OtherTestEvent;
+implement_message!(OtherTestEvent);
+
+#[test]
+fn test_subscriptions() {
+    let mut messenger = Messenger::default();
+    let mut even = messenger.subscribe::<KeyTestEvent>();
+    even.filter(|e| e.0 % 2 == 0);
+    let all = messenger.subscribe::<KeyTestEvent>();
+    assert_eq!(messenger.subscriptions_count::<KeyTestEvent>(), 2);
+
+    let dispatcher = messenger.get_dispatcher();
+    for i in 0..5 {
+        dispatcher
+            .write()
+            .unwrap()
+            .send(KeyTestEvent(i).as_boxed())
+            .ok();
+    }
+    dispatcher
+        .write()
+        .unwrap()
+        .send(OtherTestEvent.as_boxed())
+        .ok();
+    let mut processed = 0;
+    messenger.process_messages(|_| processed += 1);
+    assert_eq!(processed, 6);
+
+    let mut received = Vec::new();
+    even.read(|e| received.push(e.0));
+    assert_eq!(received, [0, 2, 4]);
+    received.clear();
+    all.read(|e| received.push(e.0));
+    assert_eq!(received, [0, 1, 2, 3, 4]);
+    received.clear();
+    all.read(|e| received.push(e.0));
+    assert!(received.is_empty());
+
+    //Dropping a subscription unregisters it
+    drop(even);
+    assert_eq!(messenger.subscriptions_count::<KeyTestEvent>(), 1);
+    dispatcher
+        .write()
+        .unwrap()
+        .send(KeyTestEvent(6).as_boxed())
+        .ok();
+    messenger.process_messages(|_| {});
+    all.read(|e| received.push(e.0));
+    assert_eq!(received, [6]);
+    let _other = messenger.subscribe::<OtherTestEvent>();
+    drop(all);
+    let _key = messenger.subscribe::<KeyTestEvent>();
+    assert_eq!(messenger.subscriptions_count::<KeyTestEvent>(), 1);
+}
+
+#[derive(Clone)]
+struct LayoutTestEvent(u32);
+implement_message!(LayoutTestEvent);
+
+#[test]
+fn test_message_delivery() {
+    let mut messenger = Messenger::default();
+    messenger
+        .set_delivery::<LayoutTestEvent>(MessageDelivery::Batched)
+        .set_delivery::<KeyTestEvent>(MessageDelivery::InOrder)
+        .set_priority::<OtherTestEvent>(MessagePriority::High);
+    let dispatcher = messenger.get_dispatcher();
+    let send = |msg: Box<dyn Message>| {
+        dispatcher.write().unwrap().send(msg).ok();
+    };
+
+    let name = |msg: &dyn Message| {
+        if let Some(e) = msg.as_any().downcast_ref::<KeyTestEvent>() {
+            format!("key{}", e.0)
+        } else if let Some(e) = msg.as_any().downcast_ref::<LayoutTestEvent>() {
+            format!("layout{}", e.0)
+        } else {
+            "other".to_string()
+        }
+    };
+    let mut received = Vec::new();
+
+    send(LayoutTestEvent(0).as_boxed());
+    send(KeyTestEvent(0).as_boxed());
+    send(LayoutTestEvent(1).as_boxed());
+    send(KeyTestEvent(1).as_boxed());
+    send(OtherTestEvent.as_boxed());
+    messenger.send_deferred(KeyTestEvent(2).as_boxed(), DeliverAt::NextFrame);
+    messenger.send_deferred(
+        KeyTestEvent(3).as_boxed(),
+        DeliverAt::After(std::time::Duration::from_secs(3600)),
+    );
+    //Deferred messages ready in this frame come before the ones sent during it
+    messenger.process_messages(|msg| received.push(name(msg)));
+    assert_eq!(
+        received,
+        ["other", "key2", "key0", "key1", "layout0", "layout1"]
+    );
+
+    //Messages deferred while processing a frame wait for the next one
+    received.clear();
+    messenger.process_messages(|msg| {
+        messenger.send_deferred(msg.as_boxed(), DeliverAt::NextFrame);
+    });
+    messenger.process_messages(|msg| received.push(name(msg)));
+    assert!(received.is_empty());
+    send(KeyTestEvent(4).as_boxed());
+    messenger.process_messages(|msg| {
+        messenger.send_deferred(msg.as_boxed(), DeliverAt::NextFrame);
+    });
+    messenger.process_messages(|msg| received.push(name(msg)));
+    assert_eq!(received, ["key4"]);
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "nrg_serialize")]
+struct PathTestEvent(String);
+implement_message!(PathTestEvent);
+
+#[test]
+fn test_messenger_bridge() {
+    let host_messenger: MessengerRw = Arc::new(RwLock::new(Messenger::default()));
+    let client_messenger: MessengerRw = Arc::new(RwLock::new(Messenger::default()));
+    let mut host = MessengerBridge::host(&host_messenger, 0).unwrap();
+    host.forward::<PathTestEvent>();
+    let args = host.connect_args().unwrap();
+    assert_eq!(args[0], BRIDGE_ARG);
+    assert_eq!(args[2], BRIDGE_TOKEN_ARG);
+    let port = host.local_port().unwrap();
+    let mut client = MessengerBridge::connect(&client_messenger, port, args[3].as_str()).unwrap();
+    client.forward::<PathTestEvent>();
+    let received = host_messenger.write().unwrap().subscribe::<PathTestEvent>();
+
+    //Connections without the token of the host are closed before forwarding anything
+    let intruder_messenger: MessengerRw = Arc::new(RwLock::new(Messenger::default()));
+    let mut intruder = MessengerBridge::connect(&intruder_messenger, port, "wrong").unwrap();
+    intruder.forward::<PathTestEvent>();
+
+    let send = |messenger: &MessengerRw, msg: Box<dyn Message>| {
+        let dispatcher = messenger.read().unwrap().get_dispatcher();
+        dispatcher.write().unwrap().send(msg).ok();
+    };
+    send(
+        &client_messenger,
+        PathTestEvent("models/box.mesh".into()).as_boxed(),
+    );
+    send(&client_messenger, KeyTestEvent(0).as_boxed());
+    client_messenger.read().unwrap().process_messages(|_| {});
+    client.update();
+    send(
+        &intruder_messenger,
+        PathTestEvent("secret".into()).as_boxed(),
+    );
+    intruder_messenger.read().unwrap().process_messages(|_| {});
+    intruder.update();
+
+    let mut messages = Vec::new();
+    for _ in 0..1000 {
+        host.update();
+        host_messenger.read().unwrap().process_messages(|_| {});
+        messages.append(&mut received.read_all());
+        if !messages.is_empty() && intruder.update().connections_count() == 0 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    assert_eq!(host.connections_count(), 1);
+    assert_eq!(messages, [PathTestEvent("models/box.mesh".into())]);
+
+    //Received messages are not sent back
+    let echo = client_messenger
+        .write()
+        .unwrap()
+        .subscribe::<PathTestEvent>();
+    host.update();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    client.update();
+    client_messenger.read().unwrap().process_messages(|_| {});
+    assert!(echo.read_all().is_empty());
+
+    drop(client);
+    for _ in 0..1000 {
+        if host.update().connections_count() == 0 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    assert_eq!(host.connections_count(), 0);
+}
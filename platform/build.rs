@@ -34,7 +34,7 @@ fn link_library(name: &str) {
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir)
-        .join("..\\..\\..\\")
+        .join("../../../")
         .canonicalize()
         .unwrap();
 
@@ -53,6 +53,9 @@ fn main() {
     let target_os = ::std::env::var("CARGO_CFG_TARGET_OS").unwrap();
     let is_windows_platform = target_os == "windows";
 
+    //Native windows are not implemented yet on Linux so there is nothing to link
+    let is_unix_platform = target_os == "linux" || target_os == "freebsd";
+
     if is_windows_platform {
         link_library("user32");
         link_library("kernel32");
@@ -60,7 +63,7 @@ fn main() {
         link_library("gdi32");
         link_library("dwmapi");
         link_library("uxtheme");
-    } else {
+    } else if !is_unix_platform {
         panic!("Platform not yet supported - Check build.rs to setup this platform to build from source");
     }
 }
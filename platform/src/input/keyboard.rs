@@ -1,13 +1,15 @@
 #![allow(clippy::upper_case_acronyms)]
 
 use nrg_messenger::implement_message;
+use nrg_serialize::{Deserialize, Serialize};
 
 use super::state::*;
 
 // Please refer to
 // https://developer.mozilla.org/en-US/docs/Web/API/KeyboardEvent/key/Key_Values
 
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 #[repr(u32)]
 pub enum Key {
     /// The '1' key over the letters.
@@ -709,7 +711,8 @@ pub enum Key {
     Separator,
 }
 
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 pub struct KeyTextEvent {
     pub char: char,
 }
@@ -723,7 +726,8 @@ impl Default for KeyTextEvent {
     }
 }
 
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 pub struct KeyEvent {
     pub code: Key,
     pub state: InputState,
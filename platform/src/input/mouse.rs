@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 
 use nrg_messenger::implement_message;
+use nrg_serialize::{Deserialize, Serialize};
 
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 pub enum MouseButton {
     None,
     Left,
@@ -10,7 +12,8 @@ pub enum MouseButton {
     Middle,
     Other(u16),
 }
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 pub enum MouseState {
     Invalid,
     Move,
@@ -19,7 +22,8 @@ pub enum MouseState {
     Up,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 pub struct MouseEvent {
     pub x: f64,
     pub y: f64,
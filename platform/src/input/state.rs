@@ -1,4 +1,7 @@
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+use nrg_serialize::{Deserialize, Serialize};
+
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 pub enum InputState {
     Invalid,
     Released,
@@ -30,7 +30,10 @@ pub mod platform_impl {
     #[path = "macos/platform.rs"]
     pub mod platform;
 
-    #[cfg(target_os = "unix")]
+    #[cfg(all(
+        target_family = "unix",
+        not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+    ))]
     #[path = "unix/platform.rs"]
     pub mod platform;
 
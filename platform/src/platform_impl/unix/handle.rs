@@ -0,0 +1,24 @@
+use crate::ctypes::*;
+use crate::handle::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandleImpl {
+    /// An Xlib `Window`, 0 when there is none.
+    pub window: c_ulong,
+    /// A pointer to an Xlib `Display`.
+    pub display: *mut c_void,
+}
+
+impl AsRef<Handle> for HandleImpl {
+    #[inline]
+    fn as_ref(&self) -> &Handle {
+        unsafe { &*(self as *const HandleImpl as *const Handle) }
+    }
+}
+
+impl AsMut<Handle> for HandleImpl {
+    #[inline]
+    fn as_mut(&mut self) -> &mut Handle {
+        unsafe { &mut *(self as *mut HandleImpl as *mut Handle) }
+    }
+}
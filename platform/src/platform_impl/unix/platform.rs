@@ -1,5 +1,5 @@
 #![cfg(target_family = "unix")]
 
-pub use super::*;
-
-pub mod unix_handle;
\ No newline at end of file
+pub mod handle;
+pub mod watcher;
+pub mod window;
@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::watcher::*;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+type WatchSnapshot = HashMap<PathBuf, SystemTime>;
+
+// Watched paths are polled comparing the modification time of their files
+struct FileWatcherServer {
+    rx: Receiver<WatcherRequest>,
+    event_fn: Box<dyn EventFn>,
+    watches: HashMap<PathBuf, WatchSnapshot>,
+}
+
+pub struct FileWatcherImpl {
+    tx: Sender<WatcherRequest>,
+}
+
+impl FileWatcherServer {
+    fn start(event_fn: Box<dyn EventFn>) -> Sender<WatcherRequest> {
+        let (action_tx, action_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut server = FileWatcherServer {
+                rx: action_rx,
+                event_fn,
+                watches: HashMap::new(),
+            };
+            server.run();
+        });
+        action_tx
+    }
+
+    fn run(&mut self) {
+        loop {
+            match self.rx.recv_timeout(POLL_INTERVAL) {
+                Ok(WatcherRequest::Watch(path)) => {
+                    let mut snapshot = WatchSnapshot::new();
+                    take_snapshot(path.as_path(), &mut snapshot);
+                    self.watches.insert(path, snapshot);
+                }
+                Ok(WatcherRequest::Unwatch(path)) => {
+                    self.watches.remove(&path);
+                }
+                Ok(WatcherRequest::Stop) | Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => self.poll(),
+            }
+        }
+    }
+
+    fn poll(&mut self) {
+        for (path, snapshot) in self.watches.iter_mut() {
+            let mut current = WatchSnapshot::new();
+            take_snapshot(path.as_path(), &mut current);
+            for (file, modified) in current.iter() {
+                match snapshot.get(file) {
+                    None => (self.event_fn)(FileEvent::Created(file.clone())),
+                    Some(previous) if previous != modified => {
+                        (self.event_fn)(FileEvent::Modified(file.clone()))
+                    }
+                    _ => {}
+                }
+            }
+            for file in snapshot.keys() {
+                if !current.contains_key(file) {
+                    (self.event_fn)(FileEvent::Deleted(file.clone()));
+                }
+            }
+            *snapshot = current;
+        }
+    }
+}
+
+fn take_snapshot(path: &Path, snapshot: &mut WatchSnapshot) {
+    if path.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                take_snapshot(entry.path().as_path(), snapshot);
+            }
+        }
+    } else if let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) {
+        snapshot.insert(path.to_path_buf(), modified);
+    }
+}
+
+impl FileWatcherImpl {
+    pub fn new<F: EventFn>(event_func: F) -> Result<Self> {
+        Ok(Self {
+            tx: FileWatcherServer::start(Box::new(event_func)),
+        })
+    }
+
+    #[inline]
+    pub fn watch(&mut self, path: &Path) {
+        let pb = self.get_absolute_path(path);
+        let _res = self.tx.send(WatcherRequest::Watch(pb));
+    }
+
+    #[inline]
+    pub fn unwatch(&mut self, path: &Path) {
+        let pb = self.get_absolute_path(path);
+        let _res = self.tx.send(WatcherRequest::Unwatch(pb));
+    }
+
+    #[inline]
+    fn get_absolute_path(&self, path: &Path) -> PathBuf {
+        let pb = if path.is_absolute() {
+            path.to_owned()
+        } else {
+            let p = env::current_dir().unwrap();
+            p.join(path)
+        };
+        if !pb.is_dir() && !pb.is_file() {
+            eprintln!(
+                "Requesting to watch a path that is neither a file nor a directory {}",
+                path.to_str().unwrap()
+            );
+        }
+        pb
+    }
+}
+
+impl Drop for FileWatcherImpl {
+    fn drop(&mut self) {
+        let _ = self.tx.send(WatcherRequest::Stop);
+    }
+}
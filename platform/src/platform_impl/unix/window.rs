@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use super::handle::*;
+use crate::handle::*;
+use crate::window::*;
+use nrg_messenger::MessageBox;
+
+// Native windows are not implemented yet on this platform:
+// apps run without one, as headless apps and tests do
+impl Window {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create_handle(
+        _title: String,
+        _x: u32,
+        _y: u32,
+        _width: &mut u32,
+        _height: &mut u32,
+        _scale_factor: &mut f32,
+        _icon_path: &Path,
+        _events_dispatcher: MessageBox,
+    ) -> Handle {
+        eprintln!("Native windows are not supported yet on this platform");
+        Handle {
+            handle_impl: HandleImpl {
+                window: 0,
+                display: ::std::ptr::null_mut(),
+            },
+        }
+    }
+
+    pub fn change_title(_handle: &Handle, _title: &str) {}
+
+    pub fn change_visibility(_handle: &Handle, _is_visible: bool) {}
+
+    pub fn change_position(_handle: &Handle, _x: u32, _y: u32) {}
+
+    pub fn change_size(_handle: &Handle, _width: u32, _height: u32) {}
+
+    #[inline]
+    pub fn internal_update(_handle: &Handle) -> bool {
+        true
+    }
+}
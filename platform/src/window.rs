@@ -2,10 +2,12 @@ use std::{any::TypeId, path::Path};
 
 use crate::{handle::*, KeyEvent, KeyTextEvent, MouseEvent};
 use nrg_messenger::{implement_message, read_messages, MessageChannel, MessengerRw};
+use nrg_serialize::{Deserialize, Serialize};
 
 pub const DEFAULT_DPI: f32 = 96.0;
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(crate = "nrg_serialize")]
 pub enum WindowEvent {
     Show,
     Hide,
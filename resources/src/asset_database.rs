@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use nrg_serialize::{
+    deserialize_from_bytes, generate_random_uid, generate_uid_from_namespace,
+    generate_uid_from_string, serialize_to_file, Deserialize, Serialize, Uid,
+};
+
+use crate::{vfs, MountSource, DATA_MOUNT};
+
+pub const META_EXTENSION: &str = "meta";
+pub const ASSET_MOUNT: &str = "asset";
+
+// Sidecar file "image.png.meta" that keeps the identity of an asset across renames and moves
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "nrg_serialize")]
+pub struct AssetMeta {
+    pub uuid: Uid,
+}
+
+impl AssetMeta {
+    pub fn meta_path(asset_path: &Path) -> PathBuf {
+        let mut path = asset_path.as_os_str().to_os_string();
+        path.push(".");
+        path.push(META_EXTENSION);
+        PathBuf::from(path)
+    }
+
+    pub fn is_meta_file(path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == META_EXTENSION)
+    }
+
+    pub fn read(asset_path: &Path) -> Option<Self> {
+        let bytes = fs::read(Self::meta_path(asset_path)).ok()?;
+        Self::from_bytes(bytes.as_slice())
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut meta = Self { uuid: Uid::nil() };
+        if deserialize_from_bytes(&mut meta, bytes) && !meta.uuid.is_nil() {
+            Some(meta)
+        } else {
+            None
+        }
+    }
+
+    pub fn write(&self, asset_path: &Path) {
+        serialize_to_file(self, Self::meta_path(asset_path));
+    }
+
+    // The uuid is assigned the first time the asset is seen and then kept in its sidecar
+    pub fn read_or_create(asset_path: &Path) -> Self {
+        Self::read(asset_path).unwrap_or_else(|| {
+            let meta = Self {
+                uuid: generate_random_uid(),
+            };
+            meta.write(asset_path);
+            meta
+        })
+    }
+
+    // Files generated from a source asset get a stable uuid derived from the source one
+    pub fn derive(&self, name: &str) -> Self {
+        Self {
+            uuid: generate_uid_from_namespace(&self.uuid, name),
+        }
+    }
+
+    // Path that references the asset by uuid, i.e. "asset://<uuid>"
+    pub fn asset_path(&self) -> PathBuf {
+        PathBuf::from(format!("{}://{}", ASSET_MOUNT, self.uuid))
+    }
+}
+
+// Maps the uuid of every asset to its path relative to the data folder
+#[derive(Default)]
+pub struct AssetDatabase {
+    paths: HashMap<Uid, PathBuf>,
+    uuids: HashMap<PathBuf, Uid>,
+}
+
+impl AssetDatabase {
+    pub fn register(&mut self, uuid: Uid, path: &Path) -> &mut Self {
+        let path = asset_key(path);
+        if let Some(old_path) = self.paths.insert(uuid, path.clone()) {
+            self.uuids.remove(&old_path);
+        }
+        if let Some(old_uuid) = self.uuids.insert(path.clone(), uuid) {
+            if old_uuid != uuid {
+                self.paths.remove(&old_uuid);
+            }
+        }
+        self
+    }
+
+    pub fn unregister(&mut self, uuid: Uid) -> &mut Self {
+        if let Some(path) = self.paths.remove(&uuid) {
+            self.uuids.remove(&path);
+        }
+        self
+    }
+
+    pub fn get_path(&self, uuid: Uid) -> Option<&Path> {
+        self.paths.get(&uuid).map(|p| p.as_path())
+    }
+
+    pub fn get_uuid(&self, path: &Path) -> Option<Uid> {
+        self.uuids.get(&asset_key(path)).cloned()
+    }
+
+    pub fn count(&self) -> usize {
+        self.paths.len()
+    }
+
+    // Registers every asset that has a sidecar inside the source
+    pub fn scan(&mut self, source: &dyn MountSource) -> &mut Self {
+        for path in source.files() {
+            if !AssetMeta::is_meta_file(path.as_path()) {
+                continue;
+            }
+            if let Some(meta) = source
+                .read(path.as_path())
+                .ok()
+                .and_then(|bytes| AssetMeta::from_bytes(bytes.as_slice()))
+            {
+                self.register(meta.uuid, path.with_extension("").as_path());
+            }
+        }
+        self
+    }
+}
+
+fn asset_key(path: &Path) -> PathBuf {
+    let path: PathBuf = path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect();
+    PathBuf::from(path.to_string_lossy().replace('\\', "/"))
+}
+
+// Writes the sidecar of a file inside the data folder and makes it known to the global database
+pub fn register_asset(asset_path: &Path, meta: &AssetMeta) {
+    if AssetMeta::read(asset_path).as_ref() != Some(meta) {
+        meta.write(asset_path);
+    }
+    let vfs = vfs();
+    let mut vfs = vfs.write().unwrap();
+    if let Some((name, relative_path)) = vfs.to_mount_path(asset_path) {
+        if name == DATA_MOUNT {
+            vfs.assets_mut()
+                .register(meta.uuid, relative_path.as_path());
+        }
+    }
+}
+
+// Resource id of a file: the uuid of the asset when known, else one generated from its path
+// relative to its mount so that the same file gets the same id however its path is written
+pub fn asset_uuid(path: &Path) -> Uid {
+    let vfs = vfs();
+    let vfs = vfs.read().unwrap();
+    vfs.asset_uuid(path).unwrap_or_else(|| {
+        let key = match vfs.to_mount_path(path) {
+            Some((name, relative_path)) => format!(
+                "{}://{}",
+                name,
+                asset_key(relative_path.as_path()).to_string_lossy()
+            ),
+            None => asset_key(path).to_string_lossy().to_string(),
+        };
+        generate_uid_from_string(key.as_str())
+    })
+}
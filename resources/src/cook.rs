@@ -0,0 +1,268 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use nrg_serialize::{try_serialize_to_file, Deserialize, Serialize};
+
+use nrg_serialize::Uid;
+
+use crate::{
+    content_hash, AssetDatabase, AssetMeta, FolderSource, PackWriter, Vfs, ASSET_MOUNT,
+    PACK_VERSION,
+};
+
+pub const COOK_MANIFEST_EXTENSION: &str = "manifest";
+//Scenes are opened by path at runtime so no other file references them
+pub const SCENE_FOLDER: &str = "models";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "nrg_serialize")]
+pub struct CookedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "nrg_serialize")]
+pub struct MissingReference {
+    pub path: PathBuf,
+    pub referenced_by: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(crate = "nrg_serialize")]
+pub struct CookManifest {
+    pub version: String,
+    pub pack_version: u32,
+    pub files: Vec<CookedFile>,
+    pub missing: Vec<MissingReference>,
+}
+
+// Collects every file reachable from the roots following the paths found inside them
+// and writes them in a pack together with its manifest
+pub struct Cooker {
+    data_folder: PathBuf,
+    version: String,
+    roots: BTreeSet<PathBuf>,
+    assets: AssetDatabase,
+}
+
+impl Cooker {
+    pub fn new(data_folder: &Path, version: &str) -> Self {
+        let mut assets = AssetDatabase::default();
+        assets.scan(&FolderSource::new(data_folder));
+        Self {
+            data_folder: data_folder.to_path_buf(),
+            version: version.to_string(),
+            roots: BTreeSet::new(),
+            assets,
+        }
+    }
+
+    // Path relative to the data folder
+    pub fn add_root(&mut self, path: &Path) -> &mut Self {
+        let path = self
+            .to_data_path(path)
+            .unwrap_or_else(|| path.to_path_buf());
+        self.roots.insert(path);
+        self
+    }
+
+    // Every file inside a folder of the data folder is a root
+    pub fn add_folder_roots(&mut self, folder: &Path) -> &mut Self {
+        let mut folders = vec![self.data_folder.join(folder)];
+        while let Some(folder) = folders.pop() {
+            if let Ok(dir) = fs::read_dir(folder) {
+                for entry in dir.flatten() {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        folders.push(path);
+                    } else {
+                        self.add_root(path.as_path());
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    fn to_data_path(&self, path: &Path) -> Option<PathBuf> {
+        let path = match Vfs::split_mount(path) {
+            Some((ASSET_MOUNT, uuid)) => {
+                let uuid = Uid::parse_str(uuid.to_str()?).ok()?;
+                self.assets.get_path(uuid)?.to_path_buf()
+            }
+            Some((_, relative_path)) => relative_path,
+            None => path.to_path_buf(),
+        };
+        let path = if path.is_absolute() {
+            let data_folder = self.data_folder.canonicalize().ok()?;
+            path.canonicalize()
+                .ok()?
+                .strip_prefix(data_folder)
+                .ok()?
+                .to_path_buf()
+        } else {
+            path
+        };
+        if self.data_folder.join(&path).is_file() {
+            Some(PathBuf::from(path.to_string_lossy().replace('\\', "/")))
+        } else {
+            None
+        }
+    }
+
+    // References are relative to the referencing file first, as glTF uris are,
+    // and then relative to the data folder
+    fn resolve_reference(&self, referenced_by: &Path, reference: &Path) -> Option<PathBuf> {
+        if Vfs::split_mount(reference).is_none() && reference.is_relative() {
+            let path = referenced_by
+                .parent()
+                .and_then(|parent| normalize_path(parent.join(reference).as_path()))
+                .and_then(|path| self.to_data_path(path.as_path()));
+            if path.is_some() {
+                return path;
+            }
+        }
+        self.to_data_path(reference)
+    }
+
+    pub fn collect(&self) -> (BTreeSet<PathBuf>, Vec<MissingReference>) {
+        let mut files = BTreeSet::new();
+        let mut missing = Vec::new();
+        let mut to_visit: Vec<PathBuf> = Vec::new();
+        for root in self.roots.iter() {
+            if self.data_folder.join(root).is_file() {
+                to_visit.push(root.clone());
+            } else {
+                missing.push(MissingReference {
+                    path: root.clone(),
+                    referenced_by: PathBuf::new(),
+                });
+            }
+        }
+        while let Some(path) = to_visit.pop() {
+            if !files.insert(path.clone()) {
+                continue;
+            }
+            //Sidecars travel with their assets so that uuids are resolved from the pack too
+            let meta_path = AssetMeta::meta_path(path.as_path());
+            if self.data_folder.join(&meta_path).is_file() {
+                files.insert(meta_path);
+            }
+            //Only text files can reference other files
+            let content = match fs::read_to_string(self.data_folder.join(&path)) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            for reference in find_strings(content.as_str()) {
+                if !is_file_reference(reference.as_str()) {
+                    continue;
+                }
+                if let Some(reference) =
+                    self.resolve_reference(path.as_path(), Path::new(reference.as_str()))
+                {
+                    if !files.contains(&reference) {
+                        to_visit.push(reference);
+                    }
+                } else {
+                    missing.push(MissingReference {
+                        path: PathBuf::from(reference),
+                        referenced_by: path.clone(),
+                    });
+                }
+            }
+        }
+        missing.sort_by(|a, b| (&a.path, &a.referenced_by).cmp(&(&b.path, &b.referenced_by)));
+        missing.dedup();
+        (files, missing)
+    }
+
+    // The manifest is written next to the pack
+    pub fn cook(&self, pack_path: &Path) -> io::Result<CookManifest> {
+        let (files, missing) = self.collect();
+        let mut pack = PackWriter::default();
+        let mut manifest = CookManifest {
+            version: self.version.clone(),
+            pack_version: PACK_VERSION,
+            files: Vec::new(),
+            missing,
+        };
+        for path in files {
+            let data = fs::read(self.data_folder.join(&path))?;
+            manifest.files.push(CookedFile {
+                path: path.clone(),
+                size: data.len() as u64,
+                hash: format!("{:016x}", content_hash(data.as_slice())),
+            });
+            pack.add(path.as_path(), data);
+        }
+        if let Some(parent) = pack_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        pack.write(pack_path)?;
+        try_serialize_to_file(
+            &manifest,
+            pack_path.with_extension(COOK_MANIFEST_EXTENSION).as_path(),
+        )
+        .map_err(io::Error::other)?;
+        Ok(manifest)
+    }
+}
+
+// Removes . and .. from a relative path, None when it goes outside of its root
+fn normalize_path(path: &Path) -> Option<PathBuf> {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(c) => result.push(c),
+            _ => return None,
+        }
+    }
+    Some(result)
+}
+
+fn is_file_reference(string: &str) -> bool {
+    !string.chars().any(char::is_whitespace)
+        && (Vfs::split_mount(Path::new(string)).is_some_and(|(name, _)| name == ASSET_MOUNT)
+            || Path::new(string)
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| {
+                    !e.is_empty() && e.chars().all(|c| c.is_alphanumeric() || c == '_')
+                }))
+}
+
+// Strings of a json file, that is the format of every data file
+fn find_strings(content: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(c) = chars.next() {
+                        string.push(c);
+                    }
+                }
+                _ => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
@@ -1,11 +1,18 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
-use nrg_serialize::{deserialize_from_file, Deserialize};
+use nrg_serialize::{
+    try_deserialize_versioned, Deserialize, SerializeError, SerializeResult, Versioned,
+};
 
-use crate::{ResourceData, ResourceRef, SharedDataRw};
+use crate::{read_file, vfs, ResourceData, ResourceRef, SharedData, SharedDataRw, Vfs};
 
 pub const DATA_RAW_FOLDER: &str = "./data_raw/";
 pub const DATA_FOLDER: &str = "./data/";
+pub const USER_FOLDER: &str = "./user/";
+pub const DATA_PACK_FILE: &str = "./data.pack";
 
 pub trait Data {
     #[inline]
@@ -13,7 +20,7 @@ pub trait Data {
         PathBuf::from(DATA_FOLDER)
     }
 }
-pub trait Deserializable: Default + for<'de> Deserialize<'de> {
+pub trait Deserializable: Default + Versioned + for<'de> Deserialize<'de> {
     fn set_path(&mut self, filepath: &Path);
     fn path(&self) -> &Path;
 }
@@ -33,8 +40,58 @@ pub trait SerializableResource: DataTypeResource {
         Self::DataType: Deserializable,
     {
         let data = from_file::<Self::DataType>(filepath);
-        Self::create_from_data(shared_data, data)
+        create_tracked_resource::<Self>(shared_data, filepath, data)
     }
+    // Unlike create_from_file an unreadable or invalid file is reported instead of loaded as default
+    fn try_create_from_file(
+        shared_data: &SharedDataRw,
+        filepath: &Path,
+    ) -> SerializeResult<ResourceRef<Self>>
+    where
+        Self: Sized,
+        Self::DataType: Deserializable,
+    {
+        let data = try_from_file::<Self::DataType>(filepath)?;
+        Ok(create_tracked_resource::<Self>(shared_data, filepath, data))
+    }
+    // The resource is rebuilt in place so that every handle to it stays valid
+    fn reload_from_file(resource: &ResourceRef<Self>, shared_data: &SharedDataRw, filepath: &Path)
+    where
+        Self: Sized,
+        Self::DataType: Deserializable,
+    {
+        let data = from_file::<Self::DataType>(filepath);
+        let new_resource = Self::create_from_data(shared_data, data);
+        if new_resource.key() != resource.key() {
+            if let (Some(old), Some(new)) = (resource.resource(), new_resource.resource()) {
+                std::mem::swap(&mut *old.get_mut(), &mut *new.get_mut());
+            }
+        }
+    }
+}
+
+fn create_tracked_resource<T>(
+    shared_data: &SharedDataRw,
+    filepath: &Path,
+    data: T::DataType,
+) -> ResourceRef<T>
+where
+    T: SerializableResource,
+    T::DataType: Deserializable,
+{
+    let resource = T::create_from_data(shared_data, data);
+    let path = filepath.to_path_buf();
+    SharedData::track_file(
+        shared_data,
+        resource.id(),
+        filepath,
+        move |shared_data, resource_id| {
+            if let Some(resource) = SharedData::try_get_resource::<T>(shared_data, resource_id) {
+                T::reload_from_file(&resource, shared_data, path.as_path());
+            }
+        },
+    );
+    resource
 }
 
 pub trait FileResource: ResourceData {
@@ -42,6 +99,13 @@ pub trait FileResource: ResourceData {
     fn create_from_file(shared_data: &SharedDataRw, filepath: &Path) -> ResourceRef<Self>
     where
         Self: Sized;
+    // Unlike create_from_file an unreadable file is reported instead of loaded as default
+    fn try_create_from_file(
+        shared_data: &SharedDataRw,
+        filepath: &Path,
+    ) -> io::Result<ResourceRef<Self>>
+    where
+        Self: Sized;
 }
 
 #[macro_export]
@@ -57,6 +121,9 @@ macro_rules! implement_file_data {
         }
         unsafe impl Send for $name {}
         unsafe impl Sync for $name {}
+        impl nrg_serialize::Versioned for $name {
+            const VERSION: u32 = 0;
+        }
         impl $crate::Deserializable for $name {
             #[inline]
             fn set_path(&mut self, filepath: &Path) {
@@ -93,36 +160,41 @@ macro_rules! implement_file_data {
 
 #[inline]
 pub fn convert_from_local_path(parent_folder: &Path, relative_path: &Path) -> PathBuf {
+    if Vfs::split_mount(relative_path).is_some() {
+        return vfs()
+            .read()
+            .unwrap()
+            .real_path(relative_path)
+            .unwrap_or_else(|| relative_path.to_path_buf());
+    }
     let mut pathbuf = parent_folder.to_path_buf();
     let data_folder = pathbuf
         .canonicalize()
-        .unwrap()
-        .to_str()
-        .unwrap()
+        .unwrap_or_else(|_| pathbuf.clone())
+        .to_string_lossy()
         .to_string();
-    let string = relative_path.to_str().unwrap().to_string();
-    if string.contains(parent_folder.to_str().unwrap()) {
-        pathbuf = relative_path.canonicalize().unwrap()
+    let string = relative_path.to_string_lossy().to_string();
+    if string.contains(parent_folder.to_string_lossy().as_ref()) {
+        pathbuf = relative_path
+            .canonicalize()
+            .unwrap_or_else(|_| relative_path.to_path_buf())
     } else if string.contains(data_folder.as_str()) {
         pathbuf = relative_path.to_path_buf()
     } else if let Ok(result_path) = pathbuf.join(relative_path).canonicalize() {
         pathbuf = result_path;
     } else {
-        eprintln!("Unable to join {:?} with {:?}", pathbuf, relative_path);
+        //Files not on disk could still be found inside a mounted pack
+        pathbuf = pathbuf.join(relative_path);
     }
     pathbuf
 }
 
 pub fn convert_in_local_path(original_path: &Path, base_path: &Path) -> PathBuf {
-    let path = original_path.to_str().unwrap().to_string();
-    let path = path.replace(
-        PathBuf::from(base_path)
-            .canonicalize()
-            .unwrap()
-            .to_str()
-            .unwrap(),
-        "",
-    );
+    let path = original_path.to_string_lossy().to_string();
+    let base_path = base_path
+        .canonicalize()
+        .unwrap_or_else(|_| base_path.to_path_buf());
+    let path = path.replace(base_path.to_string_lossy().as_ref(), "");
     let mut path = path.replace("\\", "/");
     if path.starts_with('/') {
         path.remove(0);
@@ -130,13 +202,38 @@ pub fn convert_in_local_path(original_path: &Path, base_path: &Path) -> PathBuf
     PathBuf::from(path)
 }
 
-pub fn from_file<T>(filepath: &Path) -> T
+// Same as nrg_serialize::try_deserialize_versioned_from_file but reading through the vfs
+pub fn read_versioned_file<T>(filepath: &Path) -> SerializeResult<T>
+where
+    T: Versioned + for<'de> Deserialize<'de>,
+{
+    read_file(filepath)
+        .map_err(SerializeError::from)
+        .and_then(|bytes| try_deserialize_versioned(bytes.as_slice()))
+        .map_err(|e| e.with_path(filepath))
+}
+
+pub fn try_from_file<T>(filepath: &Path) -> SerializeResult<T>
 where
     T: Deserializable,
 {
     let path = convert_from_local_path(PathBuf::from(DATA_FOLDER).as_path(), filepath);
-    let mut data = T::default();
-    deserialize_from_file(&mut data, path);
+    let mut data: T = read_versioned_file(path.as_path())?;
     data.set_path(filepath);
-    data
+    Ok(data)
+}
+
+pub fn from_file<T>(filepath: &Path) -> T
+where
+    T: Deserializable,
+{
+    match try_from_file(filepath) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Unable to load file {}", e);
+            let mut data = T::default();
+            data.set_path(filepath);
+            data
+        }
+    }
 }
@@ -2,7 +2,7 @@ use std::{any::Any, marker::PhantomData, sync::Arc};
 
 use nrg_serialize::INVALID_UID;
 
-use crate::{Resource, ResourceCastTo, ResourceData, ResourceId, SharedDataRw, TypedStorage};
+use crate::{Resource, ResourceData, ResourceId, ResourceKey, SharedDataRw};
 
 pub trait Handle: Send + Sync + Any {
     fn as_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
@@ -13,6 +13,7 @@ where
     T: ResourceData + ?Sized,
 {
     id: ResourceId,
+    key: ResourceKey,
     shared_data: SharedDataRw,
     _marker: PhantomData<T>,
 }
@@ -33,6 +34,7 @@ where
     fn default() -> Self {
         Self {
             id: INVALID_UID,
+            key: ResourceKey::default(),
             shared_data: SharedDataRw::default(),
             _marker: PhantomData::default(),
         }
@@ -43,9 +45,10 @@ impl<T> ResourceHandle<T>
 where
     T: ResourceData,
 {
-    pub fn new(id: ResourceId, shared_data: SharedDataRw) -> Self {
+    pub fn new(id: ResourceId, key: ResourceKey, shared_data: SharedDataRw) -> Self {
         Self {
             id,
+            key,
             shared_data,
             _marker: PhantomData::default(),
         }
@@ -55,12 +58,23 @@ where
         self.id
     }
 
-    pub fn resource(&self) -> Resource<T> {
+    pub fn key(&self) -> ResourceKey {
+        self.key
+    }
+
+    // None once the resource has been removed from SharedData
+    pub fn resource(&self) -> Option<Resource<T>> {
+        let shared_data = self.shared_data.read().unwrap();
+        shared_data
+            .try_get_storage::<T>()
+            .and_then(|storage| storage.resource_at(self.key))
+    }
+
+    pub fn is_valid(&self) -> bool {
         let shared_data = self.shared_data.read().unwrap();
         shared_data
-            .get_storage::<T>()
-            .resource(self.id)
-            .of_type::<T>()
+            .try_get_storage::<T>()
+            .is_some_and(|storage| storage.is_valid(self.key))
     }
 }
 
@@ -1,14 +1,33 @@
 #![allow(dead_code)]
 #![warn(clippy::all)]
 
+pub use crate::asset_database::*;
 pub use crate::config::*;
+pub use crate::cook::*;
 pub use crate::data::*;
 pub use crate::handle::*;
+pub use crate::load::*;
+pub use crate::memory::*;
+pub use crate::pack::*;
+pub use crate::query::*;
 pub use crate::resource::*;
 pub use crate::shared_data::*;
+pub use crate::storage::*;
+pub use crate::vfs::*;
 
+pub mod asset_database;
 pub mod config;
+pub mod cook;
 pub mod data;
 pub mod handle;
+pub mod load;
+pub mod memory;
+pub mod pack;
+pub mod query;
 pub mod resource;
 pub mod shared_data;
+pub mod storage;
+pub mod vfs;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,90 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use nrg_messenger::{Message, MessageBox};
+
+use crate::{file_exists, ResourceData, ResourceEvent, ResourceRef};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+// Returned right away by an asynchronous load while the resource is created elsewhere
+pub struct ResourceLoad<T>
+where
+    T: ResourceData,
+{
+    path: PathBuf,
+    state: RwLock<LoadState>,
+    handle: RwLock<Option<ResourceRef<T>>>,
+}
+
+pub type ResourceLoadRef<T> = Arc<ResourceLoad<T>>;
+
+impl<T> ResourceLoad<T>
+where
+    T: ResourceData,
+{
+    pub fn new(filepath: &Path) -> ResourceLoadRef<T> {
+        Arc::new(Self {
+            path: filepath.to_path_buf(),
+            state: RwLock::new(LoadState::Loading),
+            handle: RwLock::new(None),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub fn state(&self) -> LoadState {
+        self.state.read().unwrap().clone()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        *self.state.read().unwrap() == LoadState::Loading
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        *self.state.read().unwrap() == LoadState::Loaded
+    }
+
+    // Available only once the state is Loaded
+    pub fn handle(&self) -> Option<ResourceRef<T>> {
+        self.handle.read().unwrap().clone()
+    }
+
+    // Runs the creation of the resource turning its errors into a Failed state
+    // and notifies a ResourceEvent::Loaded on success
+    pub fn load<F>(&self, create_fn: F, dispatcher: &MessageBox)
+    where
+        F: FnOnce() -> Result<ResourceRef<T>, String>,
+    {
+        if !file_exists(self.path.as_path()) {
+            *self.state.write().unwrap() = LoadState::Failed(format!(
+                "Unable to find file {}",
+                self.path.to_string_lossy()
+            ));
+            return;
+        }
+        match create_fn() {
+            Ok(handle) => {
+                *self.handle.write().unwrap() = Some(handle);
+                *self.state.write().unwrap() = LoadState::Loaded;
+                dispatcher
+                    .write()
+                    .unwrap()
+                    .send(ResourceEvent::Loaded(self.path.clone()).as_boxed())
+                    .ok();
+            }
+            Err(error) => {
+                *self.state.write().unwrap() = LoadState::Failed(error);
+            }
+        }
+    }
+}
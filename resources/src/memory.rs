@@ -0,0 +1,68 @@
+use std::fmt;
+
+use crate::ResourceId;
+
+pub struct ResourceMemoryInfo {
+    pub id: ResourceId,
+    pub size: usize,
+    // Number of handles to the resource outside of SharedData
+    pub references: usize,
+    pub keep_alive: bool,
+    pub last_used: u64,
+}
+
+pub struct StorageMemoryInfo {
+    pub type_name: &'static str,
+    pub memory_budget: Option<usize>,
+    pub resources: Vec<ResourceMemoryInfo>,
+}
+
+impl StorageMemoryInfo {
+    pub fn memory_size(&self) -> usize {
+        self.resources.iter().map(|r| r.size).sum()
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryReport {
+    pub storages: Vec<StorageMemoryInfo>,
+}
+
+impl MemoryReport {
+    pub fn memory_size(&self) -> usize {
+        self.storages.iter().map(|s| s.memory_size()).sum()
+    }
+    pub fn get_storage(&self, type_name: &str) -> Option<&StorageMemoryInfo> {
+        self.storages.iter().find(|s| s.type_name == type_name)
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Loaded resources: {} bytes", self.memory_size())?;
+        for storage in self.storages.iter() {
+            write!(
+                f,
+                "{} - {} resources - {} bytes",
+                storage.type_name,
+                storage.resources.len(),
+                storage.memory_size()
+            )?;
+            if let Some(memory_budget) = storage.memory_budget {
+                write!(f, " / {} bytes", memory_budget)?;
+            }
+            writeln!(f)?;
+            for r in storage.resources.iter() {
+                writeln!(
+                    f,
+                    "    {} - {} bytes - {} references{}",
+                    r.id.to_simple(),
+                    r.size,
+                    r.references,
+                    if r.keep_alive { " - kept alive" } else { "" }
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::MountSource;
+
+const PACK_MAGIC: &[u8; 4] = b"NRGP";
+pub const PACK_VERSION: u32 = 2;
+
+// Pack layout, little endian:
+// magic | version: u32 | entries count: u32 |
+// for every entry: path length: u32 | path | offset: u64 | size: u64 | hash: u64 |
+// blobs of all the entries sorted by path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PackEntry {
+    offset: u64,
+    size: u64,
+    hash: u64,
+}
+
+// FNV-1a: stable across platforms and builds so that packs are reproducible
+pub fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn pack_key(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub struct PackSource {
+    file: Mutex<File>,
+    entries: HashMap<String, PackEntry>,
+}
+
+impl PackSource {
+    pub fn open(filepath: &Path) -> io::Result<Self> {
+        let file = File::open(filepath)?;
+        let mut reader = BufReader::new(file);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != PACK_MAGIC {
+            return Err(invalid_data("Not a valid pack file"));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != PACK_VERSION {
+            return Err(invalid_data("Unsupported pack version"));
+        }
+        let count = read_u32(&mut reader)?;
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            let length = read_u32(&mut reader)? as usize;
+            let mut path = vec![0u8; length];
+            reader.read_exact(&mut path)?;
+            let path = String::from_utf8(path).map_err(|_| invalid_data("Invalid entry path"))?;
+            let offset = read_u64(&mut reader)?;
+            let size = read_u64(&mut reader)?;
+            let hash = read_u64(&mut reader)?;
+            entries.insert(path, PackEntry { offset, size, hash });
+        }
+        Ok(Self {
+            file: Mutex::new(reader.into_inner()),
+            entries,
+        })
+    }
+
+    pub fn hash(&self, path: &Path) -> Option<u64> {
+        self.entries.get(&pack_key(path)).map(|e| e.hash)
+    }
+}
+
+impl MountSource for PackSource {
+    fn exists(&self, path: &Path) -> bool {
+        self.entries.contains_key(&pack_key(path))
+    }
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let entry = self.entries.get(&pack_key(path)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{:?} not found in pack", path),
+            )
+        })?;
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0u8; entry.size as usize];
+        file.read_exact(&mut data)?;
+        if content_hash(data.as_slice()) != entry.hash {
+            return Err(invalid_data("Pack entry is corrupted"));
+        }
+        Ok(data)
+    }
+    fn files(&self) -> Vec<PathBuf> {
+        self.entries.keys().map(PathBuf::from).collect()
+    }
+}
+
+#[derive(Default)]
+pub struct PackWriter {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl PackWriter {
+    pub fn add(&mut self, path: &Path, data: Vec<u8>) -> &mut Self {
+        let key = pack_key(path);
+        self.entries.retain(|(p, _)| *p != key);
+        self.entries.push((key, data));
+        self
+    }
+
+    pub fn write(&self, filepath: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filepath)?);
+        writer.write_all(PACK_MAGIC)?;
+        writer.write_all(&PACK_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+
+        let mut entries: Vec<&(String, Vec<u8>)> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let index_size: usize = entries
+            .iter()
+            .map(|(path, _)| 4 + path.len() + 8 + 8 + 8)
+            .sum();
+        let mut offset = (PACK_MAGIC.len() + 4 + 4 + index_size) as u64;
+        for (path, data) in entries.iter() {
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(data.len() as u64).to_le_bytes())?;
+            writer.write_all(&content_hash(data.as_slice()).to_le_bytes())?;
+            offset += data.len() as u64;
+        }
+        for (_, data) in entries.iter() {
+            writer.write_all(data)?;
+        }
+        writer.flush()
+    }
+}
@@ -0,0 +1,116 @@
+use std::marker::PhantomData;
+
+use crate::{change_tick, ChangeTick, Resource, ResourceData, ResourceRef, SharedDataRw};
+
+pub type QueryFilter<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+// Iterates over the resources of a type matching all the filters without collecting them.
+// SharedData is locked while iterating: callbacks must not add or remove resources
+pub struct Query<T>
+where
+    T: ResourceData,
+{
+    filters: Vec<QueryFilter<T>>,
+    changed_since: Option<ChangeTick>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for Query<T>
+where
+    T: ResourceData,
+{
+    fn default() -> Self {
+        Self {
+            filters: Vec::new(),
+            changed_since: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Query<T>
+where
+    T: ResourceData,
+{
+    pub fn filter<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Box::new(f));
+        self
+    }
+
+    // Only resources added or mutably accessed after the tick
+    pub fn changed_since(&mut self, tick: ChangeTick) -> &mut Self {
+        self.changed_since = Some(tick);
+        self
+    }
+
+    fn matches(&self, resource: &Resource<T>) -> bool {
+        if let Some(tick) = self.changed_since {
+            if !resource.is_changed_since(tick) {
+                return false;
+            }
+        }
+        if self.filters.is_empty() {
+            return true;
+        }
+        let data = resource.get();
+        self.filters.iter().all(|f| f(&data))
+    }
+
+    pub fn for_each<F>(&self, shared_data: &SharedDataRw, mut f: F)
+    where
+        F: FnMut(&ResourceRef<T>, &Resource<T>),
+    {
+        let shared_data = shared_data.read().unwrap();
+        if let Some(storage) = shared_data.try_get_storage::<T>() {
+            for (handle, resource) in storage.iter() {
+                if self.matches(resource) {
+                    f(handle, resource);
+                }
+            }
+        }
+    }
+
+    pub fn count(&self, shared_data: &SharedDataRw) -> usize {
+        let mut count = 0;
+        self.for_each(shared_data, |_, _| count += 1);
+        count
+    }
+
+    pub fn first(&self, shared_data: &SharedDataRw) -> Option<ResourceRef<T>> {
+        let shared_data = shared_data.read().unwrap();
+        shared_data.try_get_storage::<T>().and_then(|storage| {
+            storage
+                .iter()
+                .find(|(_, resource)| self.matches(resource))
+                .map(|(handle, _)| handle.clone())
+        })
+    }
+
+    pub fn handles(&self, shared_data: &SharedDataRw) -> Vec<ResourceRef<T>> {
+        let mut handles = Vec::new();
+        self.for_each(shared_data, |handle, _| handles.push(handle.clone()));
+        handles
+    }
+}
+
+// Remembers when a system last looked at the resources to find the ones changed since then
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ChangeTracker {
+    last_run: ChangeTick,
+}
+
+impl ChangeTracker {
+    #[inline]
+    pub fn last_run(&self) -> ChangeTick {
+        self.last_run
+    }
+
+    // To be called once per run: returns the tick of the previous run
+    #[inline]
+    pub fn update(&mut self) -> ChangeTick {
+        std::mem::replace(&mut self.last_run, change_tick())
+    }
+}
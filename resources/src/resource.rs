@@ -2,22 +2,44 @@ use nrg_messenger::implement_message;
 use nrg_serialize::Uid;
 use std::{
     any::Any,
+    ops::{Deref, DerefMut},
     path::PathBuf,
-    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
 };
 
-use crate::{GenericRef, HandleCastTo, ResourceRef};
-
 #[derive(Clone)]
 pub enum ResourceEvent {
     Reload(PathBuf),
+    Loaded(PathBuf),
+    Changed(ResourceId),
 }
 implement_message!(ResourceEvent);
 
 pub type ResourceId = Uid;
+pub type ChangeTick = u64;
+
+static CHANGE_TICK: AtomicU64 = AtomicU64::new(0);
+
+// Every change gets a tick greater than all the ones returned before
+#[inline]
+pub fn change_tick() -> ChangeTick {
+    CHANGE_TICK.load(Ordering::SeqCst)
+}
+
+#[inline]
+fn next_change_tick() -> ChangeTick {
+    CHANGE_TICK.fetch_add(1, Ordering::SeqCst) + 1
+}
 
 pub trait ResourceData: Send + Sync + 'static {
     fn id(&self) -> ResourceId;
+    // Resources owning heap allocations should add them to be correctly accounted
+    fn memory_size(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
 }
 
 pub trait BaseResource: Send + Sync + Any {
@@ -30,6 +52,7 @@ where
 {
     id: ResourceId,
     data: RwLock<T>,
+    last_changed: AtomicU64,
 }
 
 impl<T> ResourceMutex<T>
@@ -40,6 +63,7 @@ where
         Self {
             id: data.id(),
             data: RwLock::new(data),
+            last_changed: AtomicU64::new(next_change_tick()),
         }
     }
 
@@ -47,8 +71,53 @@ where
         self.data.read().unwrap()
     }
 
-    pub fn get_mut(&self) -> RwLockWriteGuard<'_, T> {
-        self.data.write().unwrap()
+    // Any mutable access counts as a change
+    pub fn get_mut(&self) -> ResourceWriteGuard<'_, T> {
+        ResourceWriteGuard {
+            data: self.data.write().unwrap(),
+            last_changed: &self.last_changed,
+        }
+    }
+
+    pub fn try_get(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.data.try_read().ok()
+    }
+
+    #[inline]
+    pub fn last_changed(&self) -> ChangeTick {
+        self.last_changed.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn is_changed_since(&self, tick: ChangeTick) -> bool {
+        self.last_changed() > tick
+    }
+}
+
+// The change is stamped when the write access ends: a tick taken while the resource
+// is still being written is older than the change and doesn't miss it
+pub struct ResourceWriteGuard<'a, T> {
+    data: RwLockWriteGuard<'a, T>,
+    last_changed: &'a AtomicU64,
+}
+
+impl<'a, T> Deref for ResourceWriteGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<'a, T> DerefMut for ResourceWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+}
+
+impl<'a, T> Drop for ResourceWriteGuard<'a, T> {
+    fn drop(&mut self) {
+        self.last_changed
+            .store(next_change_tick(), Ordering::SeqCst);
     }
 }
 
@@ -83,112 +152,3 @@ impl ResourceCastTo for GenericResource {
         Arc::downcast(unsafe { Arc::from_raw(any) }).unwrap()
     }
 }
-
-pub trait TypedStorage {
-    fn as_any(self: Box<Self>) -> Box<dyn Any>;
-    fn add(&mut self, handle: GenericRef, data: GenericResource);
-    fn resource(&self, resource_id: ResourceId) -> GenericResource;
-    fn get(&self, resource_id: ResourceId) -> GenericRef;
-    fn remove_all(&mut self);
-    fn flush(&mut self);
-    fn remove(&mut self, resource_id: ResourceId);
-    fn has(&self, resource_id: ResourceId) -> bool;
-    fn handles(&self) -> Vec<GenericRef>;
-    fn resources(&self) -> Vec<GenericResource>;
-    fn count(&self) -> usize;
-}
-
-pub struct Storage<T>
-where
-    T: ResourceData,
-{
-    handles: Vec<ResourceRef<T>>,
-    resources: Vec<Resource<T>>,
-}
-
-impl<T> Default for Storage<T>
-where
-    T: ResourceData,
-{
-    fn default() -> Self {
-        Self {
-            handles: Vec::new(),
-            resources: Vec::new(),
-        }
-    }
-}
-
-impl<T> TypedStorage for Storage<T>
-where
-    T: ResourceData + Sized + 'static,
-{
-    fn as_any(self: Box<Self>) -> Box<dyn Any> {
-        self
-    }
-    fn add(&mut self, handle: GenericRef, resource: GenericResource) {
-        self.handles.push(handle.of_type::<T>());
-        self.resources.push(resource.of_type::<T>());
-    }
-    fn resource(&self, resource_id: ResourceId) -> GenericResource
-    where
-        T: ResourceData,
-    {
-        if let Some(resource) = self.resources.iter().find(|r| r.id() == resource_id) {
-            resource.clone()
-        } else {
-            panic!("Resource {} not found", resource_id.to_simple());
-        }
-    }
-    fn get(&self, resource_id: ResourceId) -> GenericRef
-    where
-        T: ResourceData,
-    {
-        if let Some(handle) = self.handles.iter().find(|h| h.id() == resource_id) {
-            handle.clone()
-        } else {
-            panic!("Resource {} not found", resource_id);
-        }
-    }
-
-    fn remove_all(&mut self) {
-        self.resources.clear();
-    }
-
-    fn flush(&mut self) {
-        let mut to_remove = Vec::new();
-        for handle in self.handles.iter_mut() {
-            if Arc::strong_count(&handle) == 1 && Arc::weak_count(&handle) == 0 {
-                to_remove.push(handle.id());
-            }
-        }
-        for id in to_remove {
-            self.remove(id);
-        }
-    }
-    fn remove(&mut self, resource_id: ResourceId) {
-        self.handles.retain(|h| h.id() != resource_id);
-        self.resources.retain(|r| r.id() != resource_id);
-    }
-    fn has(&self, resource_id: ResourceId) -> bool {
-        self.handles.iter().any(|h| h.id() == resource_id)
-    }
-
-    fn handles(&self) -> Vec<GenericRef> {
-        let mut handles = Vec::new();
-        for handle in self.handles.iter() {
-            handles.push(handle.clone() as _);
-        }
-        handles
-    }
-
-    fn resources(&self) -> Vec<GenericResource> {
-        let mut resources = Vec::new();
-        for resource in self.resources.iter() {
-            resources.push(resource.clone() as _);
-        }
-        resources
-    }
-    fn count(&self) -> usize {
-        self.resources.len()
-    }
-}
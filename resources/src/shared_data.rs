@@ -1,18 +1,23 @@
 use std::{
     any::{type_name, TypeId},
     collections::HashMap,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
 };
 
 use nrg_serialize::INVALID_UID;
 
 use crate::{
-    Data, HandleCastTo, ResourceData, ResourceHandle, ResourceId, ResourceMutex, ResourceRef,
-    Storage, TypedStorage,
+    vfs, Data, MemoryReport, ResourceData, ResourceId, ResourceRef, Storage, TypedStorage, Vfs,
+    DATA_FOLDER,
 };
 
+pub type ReloadFn = Arc<dyn Fn(&SharedDataRw, ResourceId) + Send + Sync>;
+
 pub struct SharedData {
     storage: HashMap<TypeId, Box<dyn TypedStorage>>,
+    dependents: HashMap<ResourceId, Vec<ResourceId>>,
+    files: HashMap<PathBuf, Vec<(ResourceId, ReloadFn)>>,
 }
 unsafe impl Send for SharedData {}
 unsafe impl Sync for SharedData {}
@@ -24,6 +29,8 @@ impl Default for SharedData {
     fn default() -> Self {
         Self {
             storage: HashMap::new(),
+            dependents: HashMap::new(),
+            files: HashMap::new(),
         }
     }
 }
@@ -38,13 +45,22 @@ impl SharedData {
             .insert(TypeId::of::<T>(), Box::new(Storage::<T>::default()));
     }
     #[inline]
-    pub fn get_storage<T>(&self) -> &Storage<T>
+    pub fn try_get_storage<T>(&self) -> Option<&Storage<T>>
     where
         T: ResourceData,
     {
-        if let Some(rs) = self.storage.get(&TypeId::of::<T>()) {
+        self.storage.get(&TypeId::of::<T>()).map(|rs| {
             let storage = rs.as_ref() as *const dyn TypedStorage as *const Storage<T>;
             unsafe { &*storage }
+        })
+    }
+    #[inline]
+    pub fn get_storage<T>(&self) -> &Storage<T>
+    where
+        T: ResourceData,
+    {
+        if let Some(storage) = self.try_get_storage::<T>() {
+            storage
         } else {
             panic!("Type {} has not been registered", type_name::<T>());
         }
@@ -64,31 +80,39 @@ impl SharedData {
     }
     #[inline]
     pub fn add_resource<T: ResourceData>(shared_data: &SharedDataRw, data: T) -> ResourceRef<T> {
-        let handle = Arc::new(ResourceHandle::new(data.id(), shared_data.clone()));
+        let shared_data_rw = shared_data.clone();
         let mut shared_data = shared_data.write().unwrap();
         shared_data
             .get_storage_mut::<T>()
-            .add(handle.clone(), Arc::new(ResourceMutex::new(data)));
-        handle
+            .add(data, &shared_data_rw)
     }
     #[inline]
-    pub fn get_resource<T: ResourceData>(
+    pub fn try_get_resource<T: ResourceData>(
         shared_data: &SharedDataRw,
         resource_id: ResourceId,
-    ) -> ResourceRef<T> {
+    ) -> Option<ResourceRef<T>> {
         let shared_data = shared_data.read().unwrap();
         shared_data
-            .get_storage::<T>()
-            .get(resource_id)
-            .of_type::<T>()
+            .try_get_storage::<T>()
+            .and_then(|storage| storage.try_get(resource_id))
+    }
+    #[inline]
+    pub fn get_resource<T: ResourceData>(
+        shared_data: &SharedDataRw,
+        resource_id: ResourceId,
+    ) -> ResourceRef<T> {
+        if let Some(handle) = Self::try_get_resource::<T>(shared_data, resource_id) {
+            handle
+        } else {
+            panic!("Resource {} not found", resource_id);
+        }
     }
     #[inline]
     pub fn get_resources_of_type<T: ResourceData>(
         shared_data: &SharedDataRw,
     ) -> Vec<ResourceRef<T>> {
         let shared_data = shared_data.read().unwrap();
-        let handles = shared_data.get_storage::<T>().handles();
-        handles.into_iter().map(|h| h.of_type::<T>()).collect()
+        shared_data.get_storage::<T>().typed_handles()
     }
     #[inline]
     fn clear(&mut self) {
@@ -96,12 +120,199 @@ impl SharedData {
             rs.remove_all();
         }
         self.storage.clear();
+        self.dependents.clear();
+        self.files.clear();
     }
     #[inline]
     pub fn flush_resources(&mut self) {
         for (_, rs) in self.storage.iter_mut() {
             rs.flush();
         }
+        let storage = &self.storage;
+        let contains = |id: &ResourceId| storage.values().any(|rs| rs.has(*id));
+        self.dependents.retain(|id, dependents| {
+            dependents.retain(|d| contains(d));
+            contains(id) && !dependents.is_empty()
+        });
+        self.files.retain(|_, resources| {
+            resources.retain(|(id, _)| contains(id));
+            !resources.is_empty()
+        });
+    }
+    // Removes the resource even if still referenced: every handle to it becomes invalid
+    #[inline]
+    pub fn unload_resource<T: ResourceData>(shared_data: &SharedDataRw, resource_id: ResourceId) {
+        let mut shared_data = shared_data.write().unwrap();
+        if let Some(rs) = shared_data.storage.get_mut(&TypeId::of::<T>()) {
+            rs.remove(resource_id);
+        }
+    }
+    #[inline]
+    pub fn keep_alive<T: ResourceData>(
+        shared_data: &SharedDataRw,
+        resource_id: ResourceId,
+        keep_alive: bool,
+    ) {
+        let mut shared_data = shared_data.write().unwrap();
+        shared_data
+            .get_storage_mut::<T>()
+            .set_keep_alive(resource_id, keep_alive);
+    }
+    #[inline]
+    pub fn is_kept_alive<T: ResourceData>(
+        shared_data: &SharedDataRw,
+        resource_id: ResourceId,
+    ) -> bool {
+        let shared_data = shared_data.read().unwrap();
+        shared_data
+            .try_get_storage::<T>()
+            .is_some_and(|storage| storage.is_kept_alive(resource_id))
+    }
+    // Unused resources of this type are kept loaded until the budget in bytes is exceeded,
+    // with no budget they are never unloaded by a flush
+    // and with a budget of 0 they are unloaded as soon as unused
+    #[inline]
+    pub fn set_memory_budget<T: ResourceData>(
+        shared_data: &SharedDataRw,
+        memory_budget: Option<usize>,
+    ) {
+        let mut shared_data = shared_data.write().unwrap();
+        shared_data
+            .get_storage_mut::<T>()
+            .set_memory_budget(memory_budget);
+    }
+    #[inline]
+    pub fn get_memory_size<T: ResourceData>(shared_data: &SharedDataRw) -> usize {
+        let shared_data = shared_data.read().unwrap();
+        shared_data
+            .storage
+            .get(&TypeId::of::<T>())
+            .map_or(0, |rs| rs.memory_size())
+    }
+    #[inline]
+    pub fn memory_report(shared_data: &SharedDataRw) -> MemoryReport {
+        let shared_data = shared_data.read().unwrap();
+        let mut storages: Vec<_> = shared_data
+            .storage
+            .values()
+            .map(|rs| rs.memory_info())
+            .collect();
+        storages.sort_by_key(|s| s.type_name);
+        MemoryReport { storages }
+    }
+    // The dependent resource will be notified when the dependency is reloaded
+    #[inline]
+    pub fn add_dependency(
+        shared_data: &SharedDataRw,
+        resource_id: ResourceId,
+        dependency_id: ResourceId,
+    ) {
+        if resource_id.is_nil() || dependency_id.is_nil() || resource_id == dependency_id {
+            return;
+        }
+        let mut shared_data = shared_data.write().unwrap();
+        let dependents = shared_data.dependents.entry(dependency_id).or_default();
+        if !dependents.contains(&resource_id) {
+            dependents.push(resource_id);
+        }
+    }
+    // Dependents at any depth, each one after all the dependents it depends on
+    #[inline]
+    pub fn get_dependents(shared_data: &SharedDataRw, resource_id: ResourceId) -> Vec<ResourceId> {
+        let shared_data = shared_data.read().unwrap();
+        let mut found: Vec<ResourceId> = Vec::new();
+        let mut to_visit = vec![resource_id];
+        while let Some(id) = to_visit.pop() {
+            if let Some(dependents) = shared_data.dependents.get(&id) {
+                for d in dependents.iter() {
+                    if *d != resource_id && !found.contains(d) {
+                        found.push(*d);
+                        to_visit.push(*d);
+                    }
+                }
+            }
+        }
+        let mut missing_dependencies: HashMap<ResourceId, usize> = HashMap::new();
+        for id in found.iter().chain(std::iter::once(&resource_id)) {
+            if let Some(dependents) = shared_data.dependents.get(id) {
+                for d in dependents.iter().filter(|d| found.contains(d)) {
+                    *missing_dependencies.entry(*d).or_default() += 1;
+                }
+            }
+        }
+        let mut result: Vec<ResourceId> = Vec::new();
+        let mut ready = vec![resource_id];
+        while let Some(id) = ready.pop() {
+            if let Some(dependents) = shared_data.dependents.get(&id) {
+                for d in dependents.iter().filter(|d| found.contains(d)) {
+                    let missing = missing_dependencies.get_mut(d).unwrap();
+                    *missing -= 1;
+                    if *missing == 0 {
+                        result.push(*d);
+                        ready.push(*d);
+                    }
+                }
+            }
+        }
+        //Dependents in a cycle can't be sorted
+        found.retain(|d| !result.contains(d));
+        result.append(&mut found);
+        result
+    }
+    // reload_fn is called to rebuild the resource every time its file is reloaded
+    #[inline]
+    pub fn track_file<F>(
+        shared_data: &SharedDataRw,
+        resource_id: ResourceId,
+        filepath: &Path,
+        reload_fn: F,
+    ) where
+        F: Fn(&SharedDataRw, ResourceId) + Send + Sync + 'static,
+    {
+        let mut shared_data = shared_data.write().unwrap();
+        let resources = shared_data.files.entry(file_key(filepath)).or_default();
+        resources.retain(|(id, _)| *id != resource_id);
+        resources.push((resource_id, Arc::new(reload_fn)));
+    }
+    #[inline]
+    fn get_reload_fn(shared_data: &SharedDataRw, resource_id: ResourceId) -> Option<ReloadFn> {
+        let shared_data = shared_data.read().unwrap();
+        shared_data
+            .files
+            .values()
+            .flat_map(|resources| resources.iter())
+            .find(|(id, _)| *id == resource_id)
+            .map(|(_, reload_fn)| reload_fn.clone())
+    }
+    // Returns the reloaded resources followed by all the resources depending on them.
+    // Dependents created from a file are rebuilt too, after their own dependencies
+    #[inline]
+    pub fn reload_file(shared_data: &SharedDataRw, filepath: &Path) -> Vec<ResourceId> {
+        let resources = shared_data
+            .read()
+            .unwrap()
+            .files
+            .get(&file_key(filepath))
+            .cloned()
+            .unwrap_or_default();
+        let mut result: Vec<ResourceId> = Vec::new();
+        for (id, reload_fn) in resources.iter() {
+            reload_fn(shared_data, *id);
+            if !result.contains(id) {
+                result.push(*id);
+            }
+        }
+        for (id, _) in resources.iter() {
+            for d in Self::get_dependents(shared_data, *id) {
+                if !result.contains(&d) {
+                    if let Some(reload_fn) = Self::get_reload_fn(shared_data, d) {
+                        reload_fn(shared_data, d);
+                    }
+                    result.push(d);
+                }
+            }
+        }
+        result
     }
     #[inline]
     pub fn has_resource<T: 'static>(shared_data: &SharedDataRw, resource_id: ResourceId) -> bool {
@@ -123,11 +334,9 @@ impl SharedData {
         F: Fn(&T) -> bool,
     {
         let shared_data = shared_data.read().unwrap();
-        if let Some(rs) = shared_data.storage.get(&TypeId::of::<T>()) {
-            let handles = rs.handles();
-            for h in handles.iter() {
-                let handle = h.clone().of_type::<T>();
-                if f(&handle.resource().as_ref().get()) {
+        if let Some(storage) = shared_data.try_get_storage::<T>() {
+            for (handle, resource) in storage.iter() {
+                if f(&resource.get()) {
                     return handle.id();
                 }
             }
@@ -150,3 +359,19 @@ impl Drop for SharedData {
 }
 
 pub type SharedDataRw = Arc<RwLock<SharedData>>;
+
+fn file_key(filepath: &Path) -> PathBuf {
+    //Files referenced through a mount or by uuid are reloaded when their file on disk changes
+    let filepath = if Vfs::split_mount(filepath).is_some() {
+        match vfs().read().unwrap().real_path(filepath) {
+            Some(path) => path,
+            None => return filepath.to_path_buf(),
+        }
+    } else {
+        filepath.to_path_buf()
+    };
+    filepath
+        .canonicalize()
+        .or_else(|_| PathBuf::from(DATA_FOLDER).join(&filepath).canonicalize())
+        .unwrap_or(filepath)
+}
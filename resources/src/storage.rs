@@ -0,0 +1,340 @@
+use std::{
+    any::{type_name, Any},
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    GenericRef, GenericResource, Resource, ResourceData, ResourceHandle, ResourceId,
+    ResourceMemoryInfo, ResourceMutex, ResourceRef, SharedDataRw, StorageMemoryInfo,
+};
+
+// Generational index of a resource inside its Storage:
+// a slot is reused after a removal only with a different generation
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ResourceKey {
+    index: u32,
+    generation: u32,
+}
+
+pub trait TypedStorage {
+    fn as_any(self: Box<Self>) -> Box<dyn Any>;
+    fn resource(&self, resource_id: ResourceId) -> Option<GenericResource>;
+    fn get(&self, resource_id: ResourceId) -> Option<GenericRef>;
+    fn remove_all(&mut self);
+    fn flush(&mut self);
+    fn remove(&mut self, resource_id: ResourceId);
+    fn has(&self, resource_id: ResourceId) -> bool;
+    fn handles(&self) -> Vec<GenericRef>;
+    fn resources(&self) -> Vec<GenericResource>;
+    fn count(&self) -> usize;
+    fn memory_size(&self) -> usize;
+    fn memory_info(&self) -> StorageMemoryInfo;
+}
+
+struct Slot<T>
+where
+    T: ResourceData,
+{
+    generation: u32,
+    entry: Option<(ResourceRef<T>, Resource<T>)>,
+    keep_alive: bool,
+    last_used: AtomicU64,
+    size: AtomicUsize,
+}
+
+impl<T> Slot<T>
+where
+    T: ResourceData,
+{
+    // A resource locked by someone else keeps the last size computed
+    fn update_size(&self) -> usize {
+        if let Some((_, resource)) = &self.entry {
+            if let Some(data) = resource.try_get() {
+                self.size.store(data.memory_size(), Ordering::Relaxed);
+            }
+        }
+        self.size.load(Ordering::Relaxed)
+    }
+    fn is_unused(&self) -> bool {
+        match &self.entry {
+            Some((handle, _)) => {
+                !self.keep_alive && Arc::strong_count(handle) == 1 && Arc::weak_count(handle) == 0
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct Storage<T>
+where
+    T: ResourceData,
+{
+    slots: Vec<Slot<T>>,
+    free_slots: Vec<u32>,
+    // Resources added more than once with the same id are found with the first one
+    indices: HashMap<ResourceId, Vec<ResourceKey>>,
+    count: usize,
+    frame: u64,
+    // With no budget unused resources are kept until explicitly removed,
+    // otherwise the least recently used are removed only when the budget is exceeded
+    memory_budget: Option<usize>,
+}
+
+impl<T> Default for Storage<T>
+where
+    T: ResourceData,
+{
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            indices: HashMap::new(),
+            count: 0,
+            frame: 0,
+            memory_budget: None,
+        }
+    }
+}
+
+impl<T> Storage<T>
+where
+    T: ResourceData,
+{
+    pub fn add(&mut self, data: T, shared_data: &SharedDataRw) -> ResourceRef<T> {
+        let index = if let Some(index) = self.free_slots.pop() {
+            index
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                entry: None,
+                keep_alive: false,
+                last_used: AtomicU64::new(0),
+                size: AtomicUsize::new(0),
+            });
+            (self.slots.len() - 1) as u32
+        };
+        let slot = &mut self.slots[index as usize];
+        //Generation 0 is never used so that a default key is never valid
+        slot.generation += 1;
+        let key = ResourceKey {
+            index,
+            generation: slot.generation,
+        };
+        let id = data.id();
+        let handle = Arc::new(ResourceHandle::new(id, key, shared_data.clone()));
+        slot.keep_alive = false;
+        slot.last_used.store(self.frame, Ordering::Relaxed);
+        slot.size.store(data.memory_size(), Ordering::Relaxed);
+        slot.entry = Some((handle.clone(), Arc::new(ResourceMutex::new(data))));
+        self.indices.entry(id).or_default().push(key);
+        self.count += 1;
+        handle
+    }
+
+    fn slot(&self, key: ResourceKey) -> Option<&Slot<T>> {
+        match self.slots.get(key.index as usize) {
+            Some(slot) if slot.generation == key.generation => Some(slot),
+            _ => None,
+        }
+    }
+
+    fn entry(&self, key: ResourceKey) -> Option<&(ResourceRef<T>, Resource<T>)> {
+        self.slot(key).and_then(|slot| slot.entry.as_ref())
+    }
+
+    fn first_key(&self, resource_id: ResourceId) -> Option<ResourceKey> {
+        self.indices
+            .get(&resource_id)
+            .and_then(|keys| keys.first())
+            .copied()
+    }
+
+    fn remove_key(&mut self, key: ResourceKey) {
+        if let Some(slot) = self.slots.get_mut(key.index as usize) {
+            if slot.generation == key.generation {
+                if let Some((handle, _)) = slot.entry.take() {
+                    if let Some(keys) = self.indices.get_mut(&handle.id()) {
+                        keys.retain(|k| *k != key);
+                        if keys.is_empty() {
+                            self.indices.remove(&handle.id());
+                        }
+                    }
+                    self.free_slots.push(key.index);
+                    self.count -= 1;
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_valid(&self, key: ResourceKey) -> bool {
+        self.entry(key).is_some()
+    }
+
+    #[inline]
+    pub fn resource_at(&self, key: ResourceKey) -> Option<Resource<T>> {
+        self.slot(key).and_then(|slot| {
+            slot.last_used.store(self.frame, Ordering::Relaxed);
+            slot.entry.as_ref().map(|(_, resource)| resource.clone())
+        })
+    }
+
+    #[inline]
+    pub fn try_get(&self, resource_id: ResourceId) -> Option<ResourceRef<T>> {
+        self.first_key(resource_id)
+            .and_then(|key| self.entry(key))
+            .map(|(handle, _)| handle.clone())
+    }
+
+    #[inline]
+    pub fn try_resource(&self, resource_id: ResourceId) -> Option<Resource<T>> {
+        self.first_key(resource_id)
+            .and_then(|key| self.resource_at(key))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ResourceRef<T>, &Resource<T>)> {
+        self.slots.iter().filter_map(|slot| {
+            slot.entry
+                .as_ref()
+                .map(|(handle, resource)| (handle, resource))
+        })
+    }
+
+    pub fn typed_handles(&self) -> Vec<ResourceRef<T>> {
+        self.iter().map(|(handle, _)| handle.clone()).collect()
+    }
+
+    // A resource kept alive is never removed by a flush even if no one is using it
+    pub fn set_keep_alive(&mut self, resource_id: ResourceId, keep_alive: bool) {
+        if let Some(keys) = self.indices.get(&resource_id) {
+            for key in keys.iter() {
+                self.slots[key.index as usize].keep_alive = keep_alive;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_kept_alive(&self, resource_id: ResourceId) -> bool {
+        self.first_key(resource_id)
+            .and_then(|key| self.slot(key))
+            .is_some_and(|slot| slot.keep_alive)
+    }
+
+    #[inline]
+    pub fn set_memory_budget(&mut self, memory_budget: Option<usize>) {
+        self.memory_budget = memory_budget;
+    }
+
+    #[inline]
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+}
+
+impl<T> TypedStorage for Storage<T>
+where
+    T: ResourceData + Sized + 'static,
+{
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn resource(&self, resource_id: ResourceId) -> Option<GenericResource> {
+        self.try_resource(resource_id)
+            .map(|resource| resource as GenericResource)
+    }
+    fn get(&self, resource_id: ResourceId) -> Option<GenericRef> {
+        self.try_get(resource_id).map(|handle| handle as GenericRef)
+    }
+
+    fn remove_all(&mut self) {
+        self.slots.clear();
+        self.free_slots.clear();
+        self.indices.clear();
+        self.count = 0;
+    }
+
+    fn flush(&mut self) {
+        self.frame += 1;
+        let memory_budget = match self.memory_budget {
+            Some(memory_budget) => memory_budget,
+            None => return,
+        };
+        let mut unused = Vec::new();
+        for (index, slot) in self.slots.iter().enumerate() {
+            if slot.is_unused() {
+                unused.push((
+                    ResourceKey {
+                        index: index as u32,
+                        generation: slot.generation,
+                    },
+                    slot.last_used.load(Ordering::Relaxed),
+                ));
+            }
+        }
+        let mut memory_size = self.memory_size();
+        unused.sort_by_key(|(_, last_used)| *last_used);
+        for (key, _) in unused {
+            if memory_size <= memory_budget {
+                break;
+            }
+            memory_size -= self.slots[key.index as usize].size.load(Ordering::Relaxed);
+            self.remove_key(key);
+        }
+    }
+    fn remove(&mut self, resource_id: ResourceId) {
+        if let Some(keys) = self.indices.get(&resource_id).cloned() {
+            for key in keys {
+                self.remove_key(key);
+            }
+        }
+    }
+    fn has(&self, resource_id: ResourceId) -> bool {
+        self.indices.contains_key(&resource_id)
+    }
+
+    fn handles(&self) -> Vec<GenericRef> {
+        self.iter()
+            .map(|(handle, _)| handle.clone() as GenericRef)
+            .collect()
+    }
+
+    fn resources(&self) -> Vec<GenericResource> {
+        self.iter()
+            .map(|(_, resource)| resource.clone() as GenericResource)
+            .collect()
+    }
+    fn count(&self) -> usize {
+        self.count
+    }
+    fn memory_size(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.entry.is_some())
+            .map(|slot| slot.update_size())
+            .sum()
+    }
+    fn memory_info(&self) -> StorageMemoryInfo {
+        let resources = self
+            .slots
+            .iter()
+            .filter_map(|slot| {
+                slot.entry.as_ref().map(|(handle, _)| ResourceMemoryInfo {
+                    id: handle.id(),
+                    size: slot.update_size(),
+                    references: Arc::strong_count(handle) - 1,
+                    keep_alive: slot.keep_alive,
+                    last_used: slot.last_used.load(Ordering::Relaxed),
+                })
+            })
+            .collect();
+        StorageMemoryInfo {
+            type_name: type_name::<T>(),
+            memory_budget: self.memory_budget,
+            resources,
+        }
+    }
+}
@@ -0,0 +1,581 @@
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use nrg_serialize::{generate_random_uid, Deserialize, Versioned};
+
+use crate::*;
+
+struct TestData {
+    id: ResourceId,
+    value: u32,
+}
+
+impl TestData {
+    fn new(value: u32) -> Self {
+        Self {
+            id: generate_random_uid(),
+            value,
+        }
+    }
+}
+
+impl ResourceData for TestData {
+    fn id(&self) -> ResourceId {
+        self.id
+    }
+}
+
+#[test]
+fn test_storage_lookup() {
+    let shared_data = SharedDataRw::default();
+    let first = SharedData::add_resource(&shared_data, TestData::new(1));
+    let second = SharedData::add_resource(&shared_data, TestData::new(2));
+
+    assert_eq!(
+        SharedData::get_num_resources_of_type::<TestData>(&shared_data),
+        2
+    );
+    assert!(SharedData::has_resource::<TestData>(
+        &shared_data,
+        second.id()
+    ));
+    assert_eq!(first.resource().unwrap().get().value, 1);
+    let handle = SharedData::get_resource::<TestData>(&shared_data, second.id());
+    assert_eq!(handle.resource().unwrap().get().value, 2);
+    assert!(
+        SharedData::try_get_resource::<TestData>(&shared_data, generate_random_uid()).is_none()
+    );
+}
+
+#[test]
+fn test_storage_generations() {
+    let shared_data = SharedDataRw::default();
+    SharedData::set_memory_budget::<TestData>(&shared_data, Some(0));
+    let first = SharedData::add_resource(&shared_data, TestData::new(1));
+    let first_id = first.id();
+    let first_key = first.key();
+    drop(first);
+
+    shared_data.write().unwrap().flush_resources();
+    assert!(!SharedData::has_resource::<TestData>(
+        &shared_data,
+        first_id
+    ));
+
+    //The slot is reused with a new generation so old keys are not valid anymore
+    let second = SharedData::add_resource(&shared_data, TestData::new(2));
+    assert_ne!(second.key(), first_key);
+    let stale = ResourceHandle::<TestData>::new(first_id, first_key, shared_data.clone());
+    assert!(!stale.is_valid());
+    assert!(stale.resource().is_none());
+    assert!(second.is_valid());
+    assert_eq!(second.resource().unwrap().get().value, 2);
+}
+
+#[test]
+fn test_dependencies_reload() {
+    let shared_data = SharedDataRw::default();
+    SharedData::set_memory_budget::<TestData>(&shared_data, Some(0));
+    let texture = SharedData::add_resource(&shared_data, TestData::new(1));
+    let material = SharedData::add_resource(&shared_data, TestData::new(2));
+    let object = SharedData::add_resource(&shared_data, TestData::new(3));
+    SharedData::add_dependency(&shared_data, material.id(), texture.id());
+    SharedData::add_dependency(&shared_data, object.id(), material.id());
+
+    assert_eq!(
+        SharedData::get_dependents(&shared_data, texture.id()),
+        vec![material.id(), object.id()]
+    );
+    assert!(SharedData::get_dependents(&shared_data, object.id()).is_empty());
+
+    let path = std::env::temp_dir().join("nrg_dependencies_reload_test.txt");
+    std::fs::write(&path, "10").unwrap();
+    SharedData::track_file(
+        &shared_data,
+        texture.id(),
+        &path,
+        |shared_data, texture_id| {
+            let texture = SharedData::get_resource::<TestData>(shared_data, texture_id);
+            texture.resource().unwrap().get_mut().value += 10;
+        },
+    );
+    assert_eq!(
+        SharedData::reload_file(&shared_data, &path),
+        vec![texture.id(), material.id(), object.id()]
+    );
+    assert_eq!(texture.resource().unwrap().get().value, 11);
+    std::fs::remove_file(&path).ok();
+
+    //Removed resources are not notified anymore
+    let object_id = object.id();
+    drop(object);
+    shared_data.write().unwrap().flush_resources();
+    assert_eq!(
+        SharedData::get_dependents(&shared_data, texture.id()),
+        vec![material.id()]
+    );
+    assert!(!SharedData::get_dependents(&shared_data, material.id()).contains(&object_id));
+}
+
+#[test]
+fn test_dependents_rebuild() {
+    let shared_data = SharedDataRw::default();
+    let texture = SharedData::add_resource(&shared_data, TestData::new(1));
+    let material = SharedData::add_resource(&shared_data, TestData::new(0));
+    let object = SharedData::add_resource(&shared_data, TestData::new(0));
+    SharedData::add_dependency(&shared_data, object.id(), texture.id());
+    SharedData::add_dependency(&shared_data, object.id(), material.id());
+    SharedData::add_dependency(&shared_data, material.id(), texture.id());
+
+    //Dependents come after every dependency they have
+    assert_eq!(
+        SharedData::get_dependents(&shared_data, texture.id()),
+        vec![material.id(), object.id()]
+    );
+
+    let texture_path = std::env::temp_dir().join("nrg_dependents_rebuild_texture.txt");
+    let material_path = std::env::temp_dir().join("nrg_dependents_rebuild_material.txt");
+    let object_path = std::env::temp_dir().join("nrg_dependents_rebuild_object.txt");
+    let texture_id = texture.id();
+    let material_id = material.id();
+    SharedData::track_file(
+        &shared_data,
+        texture_id,
+        &texture_path,
+        |shared_data, id| {
+            let texture = SharedData::get_resource::<TestData>(shared_data, id);
+            texture.resource().unwrap().get_mut().value += 1;
+        },
+    );
+    SharedData::track_file(
+        &shared_data,
+        material_id,
+        &material_path,
+        move |shared_data, id| {
+            let texture = SharedData::get_resource::<TestData>(shared_data, texture_id);
+            let value = texture.resource().unwrap().get().value * 10;
+            let material = SharedData::get_resource::<TestData>(shared_data, id);
+            material.resource().unwrap().get_mut().value = value;
+        },
+    );
+    SharedData::track_file(
+        &shared_data,
+        object.id(),
+        &object_path,
+        move |shared_data, id| {
+            let material = SharedData::get_resource::<TestData>(shared_data, material_id);
+            let value = material.resource().unwrap().get().value + 1;
+            let object = SharedData::get_resource::<TestData>(shared_data, id);
+            object.resource().unwrap().get_mut().value = value;
+        },
+    );
+
+    //Editing the material rebuilds the object using it
+    assert_eq!(
+        SharedData::reload_file(&shared_data, &material_path),
+        vec![material.id(), object.id()]
+    );
+    assert_eq!(material.resource().unwrap().get().value, 10);
+    assert_eq!(object.resource().unwrap().get().value, 11);
+
+    //The material is rebuilt before the object even if both use the texture
+    assert_eq!(
+        SharedData::reload_file(&shared_data, &texture_path),
+        vec![texture.id(), material.id(), object.id()]
+    );
+    assert_eq!(material.resource().unwrap().get().value, 20);
+    assert_eq!(object.resource().unwrap().get().value, 21);
+}
+
+#[test]
+fn test_memory_budget() {
+    let shared_data = SharedDataRw::default();
+    let size = std::mem::size_of::<TestData>();
+    let pinned = SharedData::add_resource(&shared_data, TestData::new(1)).id();
+    SharedData::keep_alive::<TestData>(&shared_data, pinned, true);
+    let unused = SharedData::add_resource(&shared_data, TestData::new(2)).id();
+    let used = SharedData::add_resource(&shared_data, TestData::new(3));
+
+    //Without a budget unused resources are kept
+    shared_data.write().unwrap().flush_resources();
+    assert!(SharedData::has_resource::<TestData>(&shared_data, unused));
+
+    //With a budget of 0 every unused resource is removed
+    SharedData::set_memory_budget::<TestData>(&shared_data, Some(0));
+    shared_data.write().unwrap().flush_resources();
+    assert!(SharedData::has_resource::<TestData>(&shared_data, pinned));
+    assert!(SharedData::is_kept_alive::<TestData>(&shared_data, pinned));
+    assert!(!SharedData::has_resource::<TestData>(&shared_data, unused));
+
+    SharedData::set_memory_budget::<TestData>(&shared_data, Some(size * 3));
+    let oldest = SharedData::add_resource(&shared_data, TestData::new(4)).id();
+    shared_data.write().unwrap().flush_resources();
+    assert!(SharedData::has_resource::<TestData>(&shared_data, oldest));
+
+    //The least recently used resources are removed until the budget is respected
+    let newest = SharedData::add_resource(&shared_data, TestData::new(5)).id();
+    assert_eq!(
+        SharedData::get_memory_size::<TestData>(&shared_data),
+        size * 4
+    );
+    shared_data.write().unwrap().flush_resources();
+    assert!(!SharedData::has_resource::<TestData>(&shared_data, oldest));
+    assert!(SharedData::has_resource::<TestData>(&shared_data, newest));
+
+    let report = SharedData::memory_report(&shared_data);
+    let storage = report
+        .get_storage(std::any::type_name::<TestData>())
+        .unwrap();
+    assert_eq!(storage.memory_size(), size * 3);
+    let info = storage
+        .resources
+        .iter()
+        .find(|r| r.id == used.id())
+        .unwrap();
+    assert_eq!(info.references, 1);
+    assert!(storage
+        .resources
+        .iter()
+        .any(|r| r.id == pinned && r.keep_alive));
+
+    SharedData::unload_resource::<TestData>(&shared_data, used.id());
+    assert!(!used.is_valid());
+}
+
+#[test]
+fn test_vfs_mounts() {
+    let folder = std::env::temp_dir().join("nrg_vfs_test");
+    let base = folder.join("base");
+    let overlay = folder.join("overlay");
+    std::fs::create_dir_all(base.join("textures")).unwrap();
+    std::fs::create_dir_all(&overlay).unwrap();
+    std::fs::write(base.join("textures/a.txt"), "base").unwrap();
+    std::fs::write(base.join("b.txt"), "base").unwrap();
+    std::fs::write(overlay.join("b.txt"), "overlay").unwrap();
+
+    let pack_path = folder.join("test.pack");
+    let mut pack = PackWriter::default();
+    pack.add(Path::new("textures/a.txt"), b"pack".to_vec())
+        .add(Path::new("c.txt"), b"pack".to_vec());
+    pack.write(&pack_path).unwrap();
+
+    let mut vfs = Vfs::default();
+    vfs.mount("test", FolderSource::new(&base), 0)
+        .mount("test", FolderSource::new(&overlay), 1)
+        .mount("test", PackSource::open(&pack_path).unwrap(), -1);
+
+    //Higher priority mounts override the lower ones
+    let read = |path: &str| vfs.read_to_string(Path::new(path)).unwrap();
+    assert_eq!(read("test://textures/a.txt"), "base");
+    assert_eq!(read("test://b.txt"), "overlay");
+    assert_eq!(read("test://c.txt"), "pack");
+    assert_eq!(read(base.join("b.txt").to_str().unwrap()), "overlay");
+    //Folders mounted through a different path are matched once canonicalized
+    let mut indirect = Vfs::default();
+    indirect.mount("indirect", FolderSource::new(&base.join("textures/..")), 0);
+    assert_eq!(
+        indirect.to_mount_path(&base.join("b.txt")),
+        Some(("indirect".to_string(), PathBuf::from("b.txt")))
+    );
+    assert!(!vfs.exists(Path::new("test://d.txt")));
+    assert!(vfs.read(Path::new("other://b.txt")).is_err());
+    assert_eq!(
+        vfs.real_path(Path::new("test://b.txt")),
+        Some(overlay.join("b.txt"))
+    );
+
+    vfs.unmount("test");
+    assert!(!vfs.is_mounted("test"));
+    assert!(!vfs.exists(Path::new("test://c.txt")));
+    std::fs::remove_dir_all(&folder).ok();
+}
+
+#[test]
+fn test_asset_uuid_from_path() {
+    //Files without a sidecar get the same id however their path is written
+    let uuid = asset_uuid(Path::new("data://textures/image.png"));
+    assert_eq!(asset_uuid(Path::new("./data/textures/image.png")), uuid);
+    assert_eq!(asset_uuid(Path::new("data/textures/image.png")), uuid);
+    assert_ne!(asset_uuid(Path::new("data://textures/other.png")), uuid);
+
+    let folder = std::env::temp_dir().join("nrg_asset_uuid_test");
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("image.png"), [0u8]).unwrap();
+    vfs()
+        .write()
+        .unwrap()
+        .mount("uuid", FolderSource::new(&folder), 0);
+    let uuid = asset_uuid(Path::new("uuid://image.png"));
+    assert_eq!(asset_uuid(&folder.join("image.png")), uuid);
+    assert_eq!(
+        asset_uuid(&folder.join("image.png").canonicalize().unwrap()),
+        uuid
+    );
+    vfs().write().unwrap().unmount("uuid");
+    std::fs::remove_dir_all(&folder).ok();
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "nrg_serialize")]
+struct PackedConfig {
+    name: String,
+}
+
+impl Versioned for PackedConfig {
+    const VERSION: u32 = 0;
+}
+
+#[test]
+fn test_read_versioned_file() {
+    let folder = std::env::temp_dir().join("nrg_read_versioned_test");
+    std::fs::create_dir_all(&folder).unwrap();
+    let pack_path = folder.join("config.pack");
+    let mut pack = PackWriter::default();
+    pack.add(Path::new("a.cfg"), br#"{ "name": "packed" }"#.to_vec())
+        .add(Path::new("b.cfg"), br#"{ "name": "#.to_vec());
+    pack.write(&pack_path).unwrap();
+
+    //Files only available inside a mounted pack are found through the vfs
+    vfs()
+        .write()
+        .unwrap()
+        .mount("versioned", PackSource::open(&pack_path).unwrap(), 0);
+    let config: PackedConfig = read_versioned_file(Path::new("versioned://a.cfg")).unwrap();
+    assert_eq!(config.name, "packed");
+    let error = read_versioned_file::<PackedConfig>(Path::new("versioned://b.cfg"))
+        .err()
+        .unwrap();
+    assert_eq!(error.path, Some(PathBuf::from("versioned://b.cfg")));
+    assert!(read_versioned_file::<PackedConfig>(Path::new("versioned://c.cfg")).is_err());
+
+    vfs().write().unwrap().unmount("versioned");
+    std::fs::remove_dir_all(&folder).ok();
+}
+
+#[test]
+fn test_cook_pack() {
+    let folder = std::env::temp_dir().join("nrg_cook_test");
+    let data = folder.join("data");
+    std::fs::create_dir_all(data.join("config")).unwrap();
+    std::fs::create_dir_all(data.join("models")).unwrap();
+    std::fs::create_dir_all(data.join("textures")).unwrap();
+    std::fs::write(
+        data.join("config/game.cfg"),
+        r#"{ "title": "Game 1.0", "scene": "models/box.object_data" }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        data.join("models/box.object_data"),
+        r#"{ "material": "models/box.material_data", "texture": "textures/box.png" }"#,
+    )
+    .unwrap();
+    std::fs::write(data.join("textures/box.png"), [0u8, 1, 2, 3]).unwrap();
+    std::fs::write(data.join("textures/unused.png"), [4u8]).unwrap();
+
+    let mut cooker = Cooker::new(&data, "1.0.0");
+    cooker.add_folder_roots(Path::new(CONFIG_FOLDER));
+    let pack_path = folder.join("game.pack");
+    let manifest = cooker.cook(&pack_path).unwrap();
+
+    //Only reachable files are cooked and the missing ones are reported
+    let files: Vec<PathBuf> = manifest.files.iter().map(|f| f.path.clone()).collect();
+    assert_eq!(
+        files,
+        vec![
+            PathBuf::from("config/game.cfg"),
+            PathBuf::from("models/box.object_data"),
+            PathBuf::from("textures/box.png"),
+        ]
+    );
+    assert_eq!(manifest.missing.len(), 1);
+    assert_eq!(
+        manifest.missing[0].path,
+        PathBuf::from("models/box.material_data")
+    );
+    assert_eq!(manifest.pack_version, PACK_VERSION);
+    assert!(pack_path.with_extension(COOK_MANIFEST_EXTENSION).is_file());
+
+    let pack = PackSource::open(&pack_path).unwrap();
+    let texture = Path::new("textures/box.png");
+    assert_eq!(pack.read(texture).unwrap(), vec![0u8, 1, 2, 3]);
+    assert_eq!(pack.hash(texture), Some(content_hash(&[0u8, 1, 2, 3])));
+
+    //Cooking the same data gives the same pack
+    let first = std::fs::read(&pack_path).unwrap();
+    cooker.cook(&pack_path).unwrap();
+    assert_eq!(std::fs::read(&pack_path).unwrap(), first);
+
+    //A manifest that can't be written is reported as an error
+    let blocked_path = folder.join("blocked.pack");
+    std::fs::create_dir_all(blocked_path.with_extension(COOK_MANIFEST_EXTENSION)).unwrap();
+    assert!(cooker.cook(&blocked_path).is_err());
+    std::fs::remove_dir_all(&folder).ok();
+}
+
+#[test]
+fn test_cook_relative_references() {
+    let folder = std::env::temp_dir().join("nrg_cook_relative_test");
+    let data = folder.join("data");
+    std::fs::create_dir_all(data.join("models/duck")).unwrap();
+    std::fs::create_dir_all(data.join("models/shared")).unwrap();
+    std::fs::create_dir_all(data.join("textures")).unwrap();
+    std::fs::write(
+        data.join("models/duck/duck.gltf"),
+        r#"{ "images": [{ "uri": "duck.png" }, { "uri": "../shared/grass.png" }],
+            "texture": "textures/box.png", "outside": "../../../secret.png" }"#,
+    )
+    .unwrap();
+    std::fs::write(data.join("models/duck/duck.png"), [0u8]).unwrap();
+    std::fs::write(data.join("models/shared/grass.png"), [1u8]).unwrap();
+    std::fs::write(data.join("textures/box.png"), [2u8]).unwrap();
+    std::fs::write(data.join("duck.png"), [3u8]).unwrap();
+
+    let mut cooker = Cooker::new(&data, "1.0.0");
+    cooker.add_folder_roots(Path::new(SCENE_FOLDER));
+    let (files, missing) = cooker.collect();
+
+    //References relative to the scene are found before the ones relative to the data folder
+    assert_eq!(
+        files.into_iter().collect::<Vec<_>>(),
+        vec![
+            PathBuf::from("models/duck/duck.gltf"),
+            PathBuf::from("models/duck/duck.png"),
+            PathBuf::from("models/shared/grass.png"),
+            PathBuf::from("textures/box.png"),
+        ]
+    );
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].path, PathBuf::from("../../../secret.png"));
+    std::fs::remove_dir_all(&folder).ok();
+}
+
+#[test]
+fn test_asset_database() {
+    let folder = std::env::temp_dir().join("nrg_asset_database_test");
+    let data = folder.join("data");
+    std::fs::remove_dir_all(&folder).ok();
+    std::fs::create_dir_all(data.join("textures")).unwrap();
+    std::fs::create_dir_all(data.join("models")).unwrap();
+    let texture_path = data.join("textures/box.png");
+    std::fs::write(&texture_path, [0u8, 1, 2, 3]).unwrap();
+
+    //The uuid is created once and then read back from the sidecar
+    let meta = AssetMeta::read_or_create(&texture_path);
+    assert_eq!(AssetMeta::read_or_create(&texture_path), meta);
+    assert!(data.join("textures/box.png.meta").is_file());
+    assert_eq!(meta.derive("mesh"), meta.derive("mesh"));
+    assert_ne!(meta.derive("mesh").uuid, meta.uuid);
+
+    let object_path = data.join("models/box.object_data");
+    let object_content = format!(r#"{{ "texture": "{}" }}"#, meta.asset_path().display());
+    std::fs::write(&object_path, object_content.as_str()).unwrap();
+    let object_meta = AssetMeta::read_or_create(&object_path);
+
+    let mut vfs = Vfs::default();
+    vfs.mount(DATA_MOUNT, FolderSource::new(&data), 0);
+    assert_eq!(vfs.assets().count(), 2);
+    assert_eq!(
+        vfs.asset_uuid(Path::new("data://textures/box.png")),
+        Some(meta.uuid)
+    );
+    assert_eq!(
+        vfs.read(meta.asset_path().as_path()).unwrap(),
+        vec![0u8, 1, 2, 3]
+    );
+    let unknown = AssetMeta {
+        uuid: generate_random_uid(),
+    };
+    assert!(!vfs.exists(unknown.asset_path().as_path()));
+
+    //Moving the asset together with its sidecar keeps its identity
+    std::fs::create_dir_all(data.join("images")).unwrap();
+    std::fs::rename(&texture_path, data.join("images/crate.png")).unwrap();
+    std::fs::rename(
+        AssetMeta::meta_path(&texture_path),
+        AssetMeta::meta_path(&data.join("images/crate.png")),
+    )
+    .unwrap();
+    let mut vfs = Vfs::default();
+    vfs.mount(DATA_MOUNT, FolderSource::new(&data), 0);
+    assert_eq!(
+        vfs.asset_uuid(Path::new("data://images/crate.png")),
+        Some(meta.uuid)
+    );
+    assert_eq!(vfs.asset_uuid(Path::new("data://textures/box.png")), None);
+    assert_eq!(
+        vfs.read(meta.asset_path().as_path()).unwrap(),
+        vec![0u8, 1, 2, 3]
+    );
+
+    //References by uuid are followed by the cooker and sidecars are packed with their assets
+    let mut cooker = Cooker::new(&data, "1.0.0");
+    cooker.add_root(Path::new("models/box.object_data"));
+    let (files, missing) = cooker.collect();
+    assert!(missing.is_empty());
+    assert_eq!(
+        files.into_iter().collect::<Vec<_>>(),
+        vec![
+            PathBuf::from("images/crate.png"),
+            PathBuf::from("images/crate.png.meta"),
+            PathBuf::from("models/box.object_data"),
+            PathBuf::from("models/box.object_data.meta"),
+        ]
+    );
+    assert_ne!(object_meta.uuid, meta.uuid);
+    std::fs::remove_dir_all(&folder).ok();
+}
+
+#[test]
+fn test_query_changes() {
+    let shared_data = SharedDataRw::default();
+    let resources: Vec<_> = (0..10)
+        .map(|i| SharedData::add_resource(&shared_data, TestData::new(i)))
+        .collect();
+
+    let mut query = Query::<TestData>::default();
+    query.filter(|d| d.value % 2 == 0);
+    assert_eq!(query.count(&shared_data), 5);
+    query.filter(|d| d.value > 4);
+    let mut values = Vec::new();
+    query.for_each(&shared_data, |_, r| values.push(r.get().value));
+    assert_eq!(values, vec![6, 8]);
+    assert_eq!(
+        query.first(&shared_data).map(|h| h.id()),
+        Some(resources[6].id())
+    );
+
+    //At the first run every resource is new
+    let mut tracker = ChangeTracker::default();
+    let mut changed = Query::<TestData>::default();
+    changed.changed_since(tracker.update());
+    assert_eq!(changed.count(&shared_data), 10);
+
+    let mut changed = Query::<TestData>::default();
+    changed.changed_since(tracker.update());
+    assert_eq!(changed.count(&shared_data), 0);
+
+    resources[3].resource().unwrap().get_mut().value = 30;
+    let _ = resources[5].resource().unwrap().get().value;
+    let added = SharedData::add_resource(&shared_data, TestData::new(11));
+    let mut changed = Query::<TestData>::default();
+    changed.changed_since(tracker.update());
+    let ids: Vec<_> = changed
+        .handles(&shared_data)
+        .iter()
+        .map(|h| h.id())
+        .collect();
+    assert_eq!(ids, vec![resources[3].id(), added.id()]);
+    assert!(!resources[5]
+        .resource()
+        .unwrap()
+        .is_changed_since(tracker.last_run()));
+
+    //A change still being written when the tracker runs is found at the next run
+    let resource = resources[7].resource().unwrap();
+    let mut data = resource.get_mut();
+    tracker.update();
+    data.value = 70;
+    drop(data);
+    assert!(resource.is_changed_since(tracker.update()));
+}
@@ -0,0 +1,276 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use nrg_serialize::Uid;
+
+use crate::{
+    AssetDatabase, PackSource, ASSET_MOUNT, DATA_FOLDER, DATA_PACK_FILE, DATA_RAW_FOLDER,
+    USER_FOLDER,
+};
+
+pub const DATA_MOUNT: &str = "data";
+pub const RAW_MOUNT: &str = "raw";
+pub const USER_MOUNT: &str = "user";
+const MOUNT_SEPARATOR: &str = "://";
+
+pub trait MountSource: Send + Sync {
+    fn exists(&self, path: &Path) -> bool;
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    // Relative paths of all the files of the source
+    fn files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+    // Only sources backed by a folder on disk have one
+    fn folder(&self) -> Option<&Path> {
+        None
+    }
+}
+
+pub struct FolderSource {
+    folder: PathBuf,
+}
+
+impl FolderSource {
+    pub fn new(folder: &Path) -> Self {
+        Self {
+            folder: folder.to_path_buf(),
+        }
+    }
+}
+
+impl MountSource for FolderSource {
+    fn exists(&self, path: &Path) -> bool {
+        self.folder.join(path).is_file()
+    }
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(self.folder.join(path))
+    }
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut folders = vec![self.folder.clone()];
+        while let Some(folder) = folders.pop() {
+            if let Ok(dir) = fs::read_dir(folder) {
+                for entry in dir.flatten() {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        folders.push(path);
+                    } else if let Ok(relative_path) = path.strip_prefix(&self.folder) {
+                        files.push(relative_path.to_path_buf());
+                    }
+                }
+            }
+        }
+        files
+    }
+    fn folder(&self) -> Option<&Path> {
+        Some(self.folder.as_path())
+    }
+}
+
+struct Mount {
+    name: String,
+    priority: i32,
+    source: Box<dyn MountSource>,
+    // Computed once when mounted, a folder created later is matched only by its own path
+    canonical_folder: Option<PathBuf>,
+}
+
+// Paths are in the form "data://textures/image.png":
+// when more sources are mounted with the same name the one with higher priority wins.
+// Paths without a mount name are searched inside the mounted folders or else on disk.
+// Assets of the data mounts can also be referenced by uuid as "asset://<uuid>"
+#[derive(Default)]
+pub struct Vfs {
+    mounts: Vec<Mount>,
+    assets: AssetDatabase,
+}
+
+pub type VfsRw = Arc<RwLock<Vfs>>;
+
+impl Vfs {
+    pub fn mount<S>(&mut self, name: &str, source: S, priority: i32) -> &mut Self
+    where
+        S: MountSource + 'static,
+    {
+        if name == DATA_MOUNT {
+            self.assets.scan(&source);
+        }
+        let index = self
+            .mounts
+            .iter()
+            .position(|m| m.priority < priority)
+            .unwrap_or(self.mounts.len());
+        let canonical_folder = source.folder().and_then(|f| f.canonicalize().ok());
+        self.mounts.insert(
+            index,
+            Mount {
+                name: name.to_string(),
+                priority,
+                source: Box::new(source),
+                canonical_folder,
+            },
+        );
+        self
+    }
+
+    pub fn unmount(&mut self, name: &str) -> &mut Self {
+        self.mounts.retain(|m| m.name != name);
+        self
+    }
+
+    pub fn is_mounted(&self, name: &str) -> bool {
+        self.mounts.iter().any(|m| m.name == name)
+    }
+
+    pub fn split_mount(path: &Path) -> Option<(&str, PathBuf)> {
+        let string = path.to_str()?;
+        let index = string.find(MOUNT_SEPARATOR)?;
+        Some((
+            &string[..index],
+            PathBuf::from(&string[index + MOUNT_SEPARATOR.len()..]),
+        ))
+    }
+
+    pub fn assets(&self) -> &AssetDatabase {
+        &self.assets
+    }
+
+    pub fn assets_mut(&mut self) -> &mut AssetDatabase {
+        &mut self.assets
+    }
+
+    // Mount name and path relative to it
+    pub fn to_mount_path(&self, path: &Path) -> Option<(String, PathBuf)> {
+        if let Some((name, relative_path)) = Self::split_mount(path) {
+            if name == ASSET_MOUNT {
+                let uuid = Uid::parse_str(relative_path.to_str()?).ok()?;
+                let path = self.assets.get_path(uuid)?;
+                return Some((DATA_MOUNT.to_string(), path.to_path_buf()));
+            }
+            return Some((name.to_string(), relative_path));
+        }
+        let canonical_path = path.canonicalize().ok();
+        for m in self.mounts.iter() {
+            if let Some(folder) = m.source.folder() {
+                if let Some(relative_path) = strip_folder(path, folder) {
+                    return Some((m.name.clone(), relative_path));
+                }
+                if let (Some(path), Some(folder)) = (&canonical_path, &m.canonical_folder) {
+                    if let Ok(relative_path) = path.strip_prefix(folder) {
+                        return Some((m.name.clone(), relative_path.to_path_buf()));
+                    }
+                }
+            }
+        }
+        if path.is_relative() && !path.is_file() {
+            return Some((DATA_MOUNT.to_string(), path.to_path_buf()));
+        }
+        None
+    }
+
+    fn find(&self, path: &Path) -> Option<(&Mount, PathBuf)> {
+        let (name, relative_path) = self.to_mount_path(path)?;
+        self.mounts
+            .iter()
+            .find(|m| m.name == name && m.source.exists(relative_path.as_path()))
+            .map(|m| (m, relative_path))
+    }
+
+    pub fn exists(&self, path: &Path) -> bool {
+        self.find(path).is_some() || path.is_file()
+    }
+
+    pub fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        if let Some((m, relative_path)) = self.find(path) {
+            return m.source.read(relative_path.as_path());
+        }
+        if Self::split_mount(path).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{:?} not found in any mount", path),
+            ));
+        }
+        fs::read(path)
+    }
+
+    pub fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let bytes = self.read(path)?;
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn asset_uuid(&self, path: &Path) -> Option<Uid> {
+        match self.to_mount_path(path)? {
+            (name, relative_path) if name == DATA_MOUNT => self.assets.get_uuid(&relative_path),
+            _ => None,
+        }
+    }
+
+    // Path on disk of the file, if any, even if the file has not been created yet
+    pub fn real_path(&self, path: &Path) -> Option<PathBuf> {
+        if let Some((m, relative_path)) = self.find(path) {
+            return m.source.folder().map(|f| f.join(relative_path));
+        }
+        match self.to_mount_path(path) {
+            Some((name, relative_path)) => self
+                .mounts
+                .iter()
+                .filter(|m| m.name == name)
+                .find_map(|m| m.source.folder())
+                .map(|f| f.join(relative_path)),
+            None => Some(path.to_path_buf()),
+        }
+    }
+}
+
+fn normalize(path: &Path) -> Vec<Component<'_>> {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+fn strip_folder(path: &Path, folder: &Path) -> Option<PathBuf> {
+    let path = normalize(path);
+    let folder = normalize(folder);
+    if path.len() > folder.len() && path.starts_with(folder.as_slice()) {
+        Some(path[folder.len()..].iter().collect())
+    } else {
+        None
+    }
+}
+
+static VFS: OnceLock<VfsRw> = OnceLock::new();
+
+// Global file system used to load every resource,
+// by default it mounts the data, raw and user folders and the data pack if present
+pub fn vfs() -> VfsRw {
+    VFS.get_or_init(|| {
+        let mut vfs = Vfs::default();
+        vfs.mount(DATA_MOUNT, FolderSource::new(Path::new(DATA_FOLDER)), 0)
+            .mount(RAW_MOUNT, FolderSource::new(Path::new(DATA_RAW_FOLDER)), 0)
+            .mount(USER_MOUNT, FolderSource::new(Path::new(USER_FOLDER)), 0);
+        //Loose files override the packed ones
+        if let Ok(pack) = PackSource::open(Path::new(DATA_PACK_FILE)) {
+            vfs.mount(DATA_MOUNT, pack, -1);
+        }
+        Arc::new(RwLock::new(vfs))
+    })
+    .clone()
+}
+
+#[inline]
+pub fn file_exists(path: &Path) -> bool {
+    vfs().read().unwrap().exists(path)
+}
+
+#[inline]
+pub fn read_file(path: &Path) -> io::Result<Vec<u8>> {
+    vfs().read().unwrap().read(path)
+}
+
+#[inline]
+pub fn read_file_to_string(path: &Path) -> io::Result<String> {
+    vfs().read().unwrap().read_to_string(path)
+}
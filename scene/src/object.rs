@@ -7,10 +7,10 @@ use std::{
 use nrg_graphics::MaterialInstance;
 use nrg_math::Matrix4;
 use nrg_resources::{
-    DataTypeResource, Deserializable, GenericRef, HandleCastTo, ResourceData, ResourceId,
-    ResourceRef, SerializableResource, SharedData, SharedDataRw,
+    asset_uuid, DataTypeResource, Deserializable, GenericRef, HandleCastTo, ResourceData,
+    ResourceId, ResourceRef, SerializableResource, SharedData, SharedDataRw,
 };
-use nrg_serialize::{generate_uid_from_string, INVALID_UID};
+use nrg_serialize::INVALID_UID;
 
 use crate::{ObjectData, Transform};
 
@@ -54,37 +54,43 @@ impl DataTypeResource for Object {
         let object = SharedData::add_resource(
             shared_data,
             Object {
-                id: generate_uid_from_string(object_data.path().to_str().unwrap()),
+                id: asset_uuid(object_data.path()),
                 filepath: object_data.path().to_path_buf(),
                 ..Default::default()
             },
         );
         let transform = object
             .resource()
+            .unwrap()
             .get_mut()
             .add_default_component::<Transform>(shared_data);
         transform
             .resource()
+            .unwrap()
             .get_mut()
             .set_matrix(object_data.transform);
 
         if !object_data.material.clone().into_os_string().is_empty() {
-            let material_id =
-                MaterialInstance::find_id_from_path(shared_data, object_data.material.as_path());
-            let material = if material_id.is_nil() {
-                MaterialInstance::create_from_file(shared_data, object_data.material.as_path())
-            } else {
+            //The same material could be referenced by path or by uuid
+            let material_id = asset_uuid(object_data.material.as_path());
+            let material = if SharedData::has_resource::<MaterialInstance>(shared_data, material_id)
+            {
                 SharedData::get_resource(shared_data, material_id)
+            } else {
+                MaterialInstance::create_from_file(shared_data, object_data.material.as_path())
             };
+            SharedData::add_dependency(shared_data, object.id(), material.id());
             object
                 .resource()
+                .unwrap()
                 .get_mut()
                 .add_component::<MaterialInstance>(material);
         }
 
         for child in object_data.children.iter() {
             let child = Object::create_from_file(shared_data, child.as_path());
-            object.resource().get_mut().add_child(child);
+            SharedData::add_dependency(shared_data, object.id(), child.id());
+            object.resource().unwrap().get_mut().add_child(child);
         }
 
         object
@@ -138,22 +144,26 @@ impl Object {
     }
 
     pub fn update_from_parent(&mut self, shared_data: &SharedDataRw, parent_transform: Matrix4) {
-        if let Some(transform) = self.get_component::<Transform>() {
-            let object_matrix = transform.resource().get().matrix();
-            let object_matrix = parent_transform * object_matrix;
-            transform.resource().get_mut().set_matrix(object_matrix);
-
-            if let Some(material) = self.get_component::<MaterialInstance>() {
-                for mesh in material.resource().get().meshes() {
-                    let matrix = object_matrix * *mesh.resource().get().transform();
-                    mesh.resource().get_mut().set_transform(matrix);
+        //components or children could have been released in the meantime
+        if let Some(transform) = self.get_component::<Transform>().and_then(|t| t.resource()) {
+            let object_matrix = parent_transform * transform.get().matrix();
+            transform.get_mut().set_matrix(object_matrix);
+
+            if let Some(material) = self
+                .get_component::<MaterialInstance>()
+                .and_then(|m| m.resource())
+            {
+                for mesh in material.get().meshes() {
+                    if let Some(mesh) = mesh.resource() {
+                        let matrix = object_matrix * *mesh.get().transform();
+                        mesh.get_mut().set_transform(matrix);
+                    }
                 }
             }
 
             let children = self.children();
-            for child in children {
+            for child in children.iter().filter_map(|c| c.resource()) {
                 child
-                    .resource()
                     .get_mut()
                     .update_from_parent(shared_data, object_matrix);
             }
@@ -1,8 +1,8 @@
 use std::path::{Path, PathBuf};
 
 use nrg_math::{MatBase, Matrix4};
-use nrg_resources::{ResourceData, ResourceId, ResourceRef, SharedDataRw};
-use nrg_serialize::{generate_random_uid, generate_uid_from_string};
+use nrg_resources::{asset_uuid, ResourceData, ResourceId, ResourceRef, SharedDataRw};
+use nrg_serialize::generate_random_uid;
 
 use crate::ObjectRc;
 
@@ -33,7 +33,7 @@ impl ResourceData for Scene {
 
 impl Scene {
     pub fn set_filepath(&mut self, path: &Path) {
-        self.id = generate_uid_from_string(path.to_str().unwrap());
+        self.id = asset_uuid(path);
         self.filepath = path.to_path_buf();
     }
 
@@ -49,6 +49,7 @@ impl Scene {
         for object in self.objects.iter() {
             object
                 .resource()
+                .unwrap()
                 .get_mut()
                 .update_from_parent(shared_data, Matrix4::default_identity());
         }
@@ -0,0 +1,85 @@
+use std::{fmt, mem::size_of};
+
+use serde::{
+    de::{Error, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+const WORD_SIZE: usize = size_of::<u32>();
+
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` and made only of 32 bits fields, without any padding
+pub unsafe trait Blob: Copy + 'static {}
+
+unsafe impl Blob for u32 {}
+unsafe impl Blob for i32 {}
+unsafe impl Blob for f32 {}
+
+// To be used as #[serde(with = "nrg_serialize::blob")] on a Vec of Blob:
+// binary formats store it as a single little endian blob of bytes,
+// human readable ones keep the usual representation
+pub fn serialize<T, S>(data: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Blob + Serialize,
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        return data.serialize(serializer);
+    }
+    let words = unsafe {
+        std::slice::from_raw_parts(
+            data.as_ptr() as *const u32,
+            std::mem::size_of_val(data) / WORD_SIZE,
+        )
+    };
+    let mut bytes = Vec::with_capacity(words.len() * WORD_SIZE);
+    for w in words {
+        bytes.extend_from_slice(&w.to_le_bytes());
+    }
+    serializer.serialize_bytes(bytes.as_slice())
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: Blob + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        return Vec::<T>::deserialize(deserializer);
+    }
+    let bytes = deserializer.deserialize_byte_buf(BytesVisitor)?;
+    if bytes.len() % size_of::<T>() != 0 {
+        return Err(D::Error::custom(
+            "blob size is not a multiple of its element",
+        ));
+    }
+    let count = bytes.len() / size_of::<T>();
+    let mut data: Vec<T> = Vec::with_capacity(count);
+    unsafe {
+        let words = data.as_mut_ptr() as *mut u32;
+        for (i, w) in bytes.chunks_exact(WORD_SIZE).enumerate() {
+            words
+                .add(i)
+                .write(u32::from_le_bytes([w[0], w[1], w[2], w[3]]));
+        }
+        data.set_len(count);
+    }
+    Ok(data)
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a blob of bytes")
+    }
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+    fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+}
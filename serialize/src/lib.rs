@@ -7,6 +7,12 @@ pub use serde_derive::*;
 
 pub use self::serialize::*;
 pub use self::uuid::*;
+pub use self::version::*;
 
+pub mod blob;
 pub mod serialize;
 pub mod uuid;
+pub mod version;
+
+#[cfg(test)]
+mod test;
@@ -1,16 +1,142 @@
 use serde::{Deserialize, Serialize};
+use serde_json::error::Category;
 use std::{
-    fs::File,
-    io::{BufReader, BufWriter},
-    path::PathBuf,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
 };
 
+pub const BINARY_FILE_EXTENSION: &str = "bin";
+pub const BINARY_FORMAT_VERSION: u32 = 1;
+const BINARY_MAGIC: &[u8; 4] = b"NRGB";
+const BINARY_HEADER_SIZE: usize = BINARY_MAGIC.len() + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerializationFormat {
+    Json,
+    // Little endian with a header made of magic and format version
+    Binary,
+}
+
+impl SerializationFormat {
+    pub fn from_path(filepath: &Path) -> Self {
+        match filepath.extension() {
+            Some(ext) if ext == BINARY_FILE_EXTENSION => Self::Binary,
+            _ => Self::Json,
+        }
+    }
+    // Binary data is recognized by its header whatever the extension of its file
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        if bytes.starts_with(BINARY_MAGIC) {
+            Self::Binary
+        } else {
+            Self::Json
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerializeErrorKind {
+    Io,
+    Syntax,
+    Data,
+    Version,
+    Migration,
+}
+
+#[derive(Debug)]
+pub struct SerializeError {
+    pub kind: SerializeErrorKind,
+    pub message: String,
+    pub path: Option<PathBuf>,
+    // Starting from 1, 0 when unknown as for binary data
+    pub line: usize,
+    pub column: usize,
+}
+
+pub type SerializeResult<T> = Result<T, SerializeError>;
+
+impl SerializeError {
+    pub fn new(kind: SerializeErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            path: None,
+            line: 0,
+            column: 0,
+        }
+    }
+    pub fn with_path(mut self, filepath: &Path) -> Self {
+        self.path = Some(filepath.to_path_buf());
+        self
+    }
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        if self.line > 0 {
+            write!(f, "{}:{}:", self.line, self.column)?;
+        }
+        if self.path.is_some() || self.line > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{:?} error: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+impl From<io::Error> for SerializeError {
+    fn from(error: io::Error) -> Self {
+        Self::new(SerializeErrorKind::Io, error.to_string().as_str())
+    }
+}
+
+impl From<serde_json::Error> for SerializeError {
+    fn from(error: serde_json::Error) -> Self {
+        let kind = match error.classify() {
+            Category::Io => SerializeErrorKind::Io,
+            Category::Syntax | Category::Eof => SerializeErrorKind::Syntax,
+            Category::Data => SerializeErrorKind::Data,
+        };
+        //Position is kept apart from the message
+        let message = error.to_string();
+        let message = message.split(" at line ").next().unwrap_or_default();
+        let mut result = Self::new(kind, message);
+        result.line = error.line();
+        result.column = error.column();
+        result
+    }
+}
+
+impl From<bincode::Error> for SerializeError {
+    fn from(error: bincode::Error) -> Self {
+        let kind = match error.as_ref() {
+            bincode::ErrorKind::Io(_) => SerializeErrorKind::Io,
+            _ => SerializeErrorKind::Data,
+        };
+        Self::new(kind, error.to_string().as_str())
+    }
+}
+
 #[inline]
 pub fn serialize<T>(data: &T) -> String
 where
     T: Serialize,
 {
-    serde_json::to_string(&data).unwrap()
+    try_serialize(data).unwrap()
+}
+
+#[inline]
+pub fn try_serialize<T>(data: &T) -> SerializeResult<String>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(serde_json::to_string(data)?)
 }
 
 #[inline]
@@ -18,34 +144,158 @@ pub fn deserialize<'a, T>(serialized_data: String) -> T
 where
     T: for<'de> Deserialize<'de>,
 {
-    serde_json::from_str(&serialized_data).unwrap()
+    try_deserialize(serialized_data.as_str()).unwrap()
+}
+
+#[inline]
+pub fn try_deserialize<T>(serialized_data: &str) -> SerializeResult<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    Ok(serde_json::from_str(serialized_data)?)
 }
 
+#[inline]
+pub fn serialize_to_bytes<T>(data: &T, format: SerializationFormat) -> Vec<u8>
+where
+    T: Serialize + ?Sized,
+{
+    try_serialize_to_bytes(data, format).unwrap()
+}
+
+pub fn try_serialize_to_bytes<T>(data: &T, format: SerializationFormat) -> SerializeResult<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut bytes = Vec::new();
+    write_to(&mut bytes, data, format)?;
+    Ok(bytes)
+}
+
+fn write_to<W, T>(mut writer: W, data: &T, format: SerializationFormat) -> SerializeResult<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    match format {
+        SerializationFormat::Json => serde_json::to_writer(writer, data)?,
+        SerializationFormat::Binary => {
+            writer.write_all(BINARY_MAGIC)?;
+            writer.write_all(&BINARY_FORMAT_VERSION.to_le_bytes())?;
+            bincode::serialize_into(writer, data)?;
+        }
+    }
+    Ok(())
+}
+
+// The format is chosen from the extension of the file
 #[inline]
 pub fn serialize_to_file<T>(data: &T, filepath: PathBuf)
 where
     T: Serialize + ?Sized,
 {
-    let file = File::create(filepath).unwrap();
-    let writer = BufWriter::new(file);
-    serde_json::to_writer(writer, &data).unwrap();
+    try_serialize_to_file(data, filepath.as_path()).unwrap()
 }
 
+#[inline]
+pub fn try_serialize_to_file<T>(data: &T, filepath: &Path) -> SerializeResult<()>
+where
+    T: Serialize + ?Sized,
+{
+    try_serialize_to_file_with_format(data, filepath, SerializationFormat::from_path(filepath))
+}
+
+#[inline]
+pub fn serialize_to_file_with_format<T>(data: &T, filepath: PathBuf, format: SerializationFormat)
+where
+    T: Serialize + ?Sized,
+{
+    try_serialize_to_file_with_format(data, filepath.as_path(), format).unwrap()
+}
+
+pub fn try_serialize_to_file_with_format<T>(
+    data: &T,
+    filepath: &Path,
+    format: SerializationFormat,
+) -> SerializeResult<()>
+where
+    T: Serialize + ?Sized,
+{
+    let mut writer = BufWriter::new(
+        File::create(filepath).map_err(|e| SerializeError::from(e).with_path(filepath))?,
+    );
+    write_to(&mut writer, data, format)
+        .and_then(|_| Ok(writer.flush()?))
+        .map_err(|e| e.with_path(filepath))
+}
+
+// Errors are only printed: data keeps its previous value
 #[inline]
 pub fn deserialize_from_file<'a, T>(data: &'a mut T, filepath: PathBuf)
 where
     T: for<'de> Deserialize<'de>,
 {
     if filepath.exists() && filepath.is_file() {
-        let file = File::open(filepath.clone()).unwrap();
-        let reader = BufReader::new(file);
-        if let Ok(result) = serde_json::from_reader(reader) {
-            *data = result;
-        } else {
-            eprintln!(
-                "Unable to deserialize file {}",
-                filepath.to_str().unwrap_or("InvalidPath")
-            );
+        match try_deserialize_from_file(filepath.as_path()) {
+            Ok(result) => *data = result,
+            Err(e) => eprintln!("Unable to deserialize file {}", e),
+        }
+    }
+}
+
+#[inline]
+pub fn try_deserialize_from_file<T>(filepath: &Path) -> SerializeResult<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    fs::read(filepath)
+        .map_err(SerializeError::from)
+        .and_then(|bytes| try_deserialize_from_bytes(bytes.as_slice()))
+        .map_err(|e| e.with_path(filepath))
+}
+
+#[inline]
+pub fn deserialize_from_bytes<T>(data: &mut T, bytes: &[u8]) -> bool
+where
+    T: for<'de> Deserialize<'de>,
+{
+    if let Ok(result) = try_deserialize_from_bytes(bytes) {
+        *data = result;
+        true
+    } else {
+        false
+    }
+}
+
+// The format is detected from the content
+pub fn try_deserialize_from_bytes<T>(bytes: &[u8]) -> SerializeResult<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    match SerializationFormat::from_bytes(bytes) {
+        SerializationFormat::Json => Ok(serde_json::from_slice(bytes)?),
+        SerializationFormat::Binary => {
+            check_binary_version(bytes)?;
+            Ok(bincode::deserialize(&bytes[BINARY_HEADER_SIZE..])?)
         }
     }
 }
+
+// Data written by a newer version could not be read correctly
+fn check_binary_version(bytes: &[u8]) -> SerializeResult<()> {
+    let version = bytes
+        .get(BINARY_MAGIC.len()..BINARY_HEADER_SIZE)
+        .map(|v| u32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+        .ok_or_else(|| SerializeError::new(SerializeErrorKind::Data, "Truncated binary header"))?;
+    if version > BINARY_FORMAT_VERSION {
+        return Err(SerializeError::new(
+            SerializeErrorKind::Version,
+            format!(
+                "Binary format version {} is newer than {}",
+                version, BINARY_FORMAT_VERSION
+            )
+            .as_str(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+struct Vertex {
+    pos: [f32; 3],
+    index: u32,
+}
+
+unsafe impl blob::Blob for Vertex {}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+struct TestData {
+    path: PathBuf,
+    #[serde(with = "crate::blob")]
+    vertices: Vec<Vertex>,
+    #[serde(with = "crate::blob")]
+    indices: Vec<u32>,
+    name: String,
+}
+
+fn test_data() -> TestData {
+    TestData {
+        path: PathBuf::from("models/box.mesh_data"),
+        vertices: (0..4)
+            .map(|i| Vertex {
+                pos: [i as f32, 0.5, -1.],
+                index: i,
+            })
+            .collect(),
+        indices: vec![0, 1, 2, 2, 3, 0],
+        name: "box".to_string(),
+    }
+}
+
+#[test]
+fn test_binary_serialization() {
+    let data = test_data();
+    let json = serialize_to_bytes(&data, SerializationFormat::Json);
+    let binary = serialize_to_bytes(&data, SerializationFormat::Binary);
+    assert!(binary.len() < json.len());
+    assert_eq!(
+        SerializationFormat::from_bytes(binary.as_slice()),
+        SerializationFormat::Binary
+    );
+    assert_eq!(
+        SerializationFormat::from_bytes(json.as_slice()),
+        SerializationFormat::Json
+    );
+
+    //Blobs are stored as little endian words right after their length
+    let indices: Vec<u8> = data.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+    assert!(binary
+        .windows(indices.len())
+        .any(|w| w == indices.as_slice()));
+
+    for bytes in [json, binary] {
+        let mut result = TestData::default();
+        assert!(deserialize_from_bytes(&mut result, bytes.as_slice()));
+        assert_eq!(result, data);
+    }
+
+    //Data written by a newer format version is refused
+    let mut binary = serialize_to_bytes(&data, SerializationFormat::Binary);
+    binary[4..8].copy_from_slice(&(BINARY_FORMAT_VERSION + 1).to_le_bytes());
+    let mut result = TestData::default();
+    assert!(!deserialize_from_bytes(&mut result, binary.as_slice()));
+    assert!(!deserialize_from_bytes(&mut result, b"NRGB"));
+    assert_eq!(result, TestData::default());
+}
+
+#[test]
+fn test_serialize_to_file_format() {
+    assert_eq!(
+        SerializationFormat::from_path(Path::new("mesh.bin")),
+        SerializationFormat::Binary
+    );
+    assert_eq!(
+        SerializationFormat::from_path(Path::new("mesh.mesh_data")),
+        SerializationFormat::Json
+    );
+
+    let data = test_data();
+    let folder = std::env::temp_dir().join("nrg_serialize_test");
+    std::fs::create_dir_all(&folder).unwrap();
+    let binary_path = folder.join("data.bin");
+    let explicit_path = folder.join("data.mesh_data");
+    serialize_to_file(&data, binary_path.clone());
+    serialize_to_file_with_format(&data, explicit_path.clone(), SerializationFormat::Binary);
+    for path in [binary_path, explicit_path] {
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(
+            SerializationFormat::from_bytes(bytes.as_slice()),
+            SerializationFormat::Binary
+        );
+        let mut result = TestData::default();
+        deserialize_from_file(&mut result, path);
+        assert_eq!(result, data);
+    }
+    std::fs::remove_dir_all(&folder).ok();
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Pipeline {
+    #[serde(default)]
+    version: u32,
+    vertex_shader: PathBuf,
+    cull: bool,
+}
+
+//Version 1 renamed "shader" in "vertex_shader" and version 2 added "cull"
+impl Versioned for Pipeline {
+    const VERSION: u32 = 2;
+
+    fn migrate(value: &mut SerializedValue, from_version: u32) -> Result<(), String> {
+        let object = value.as_object_mut().ok_or("Pipeline is not an object")?;
+        match from_version {
+            0 => {
+                let shader = object.remove("shader").ok_or("Missing shader")?;
+                object.insert("vertex_shader".to_string(), shader);
+            }
+            1 => {
+                object.insert("cull".to_string(), true.into());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Config {
+    #[serde(default)]
+    version: u32,
+    pipelines: Vec<Pipeline>,
+}
+
+impl Versioned for Config {
+    const VERSION: u32 = 1;
+
+    fn migrate_nested(value: &mut SerializedValue) -> SerializeResult<()> {
+        migrate_all::<Pipeline>(value, "pipelines")
+    }
+}
+
+#[test]
+fn test_versioned_migration() {
+    let pipeline: Pipeline =
+        try_deserialize_versioned(br#"{ "shader": "shaders/a.vert" }"#).unwrap();
+    assert_eq!(
+        pipeline,
+        Pipeline {
+            version: 2,
+            vertex_shader: PathBuf::from("shaders/a.vert"),
+            cull: true,
+        }
+    );
+    let pipeline: Pipeline = try_deserialize_versioned(
+        br#"{ "version": 1, "vertex_shader": "shaders/b.vert", "cull": false }"#,
+    )
+    .unwrap();
+    assert!(pipeline.cull);
+
+    let error = try_deserialize_versioned::<Pipeline>(br#"{ "version": 3 }"#).unwrap_err();
+    assert_eq!(error.kind, SerializeErrorKind::Version);
+    let error = try_deserialize_versioned::<Pipeline>(br#"{ "cull": true }"#).unwrap_err();
+    assert_eq!(error.kind, SerializeErrorKind::Migration);
+
+    //Nested versioned data is upgraded together with its container
+    let mut value: SerializedValue = serde_json::from_str(
+        r#"{ "pipelines": [{ "shader": "a.vert" }, { "version": 2, "vertex_shader": "b.vert", "cull": false }] }"#,
+    )
+    .unwrap();
+    migrate::<Config>(&mut value).unwrap();
+    let config: Config = serde_json::from_value(value).unwrap();
+    assert_eq!(config.version, 1);
+    assert_eq!(config.pipelines[0].vertex_shader, PathBuf::from("a.vert"));
+    assert!(config.pipelines[0].cull);
+    assert!(!config.pipelines[1].cull);
+}
+
+#[test]
+fn test_serialize_errors() {
+    let error =
+        try_deserialize::<Pipeline>("{\n  \"vertex_shader\": \"a.vert\",\n  \"cull\": 3\n}")
+            .unwrap_err();
+    assert_eq!(error.kind, SerializeErrorKind::Data);
+    assert_eq!(error.line, 3);
+    let error = try_deserialize::<Pipeline>("{\n  \"cull\": true,\n").unwrap_err();
+    assert_eq!(error.kind, SerializeErrorKind::Syntax);
+    assert_eq!(error.line, 3);
+
+    let folder = std::env::temp_dir().join("nrg_serialize_errors_test");
+    std::fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("broken.cfg");
+    std::fs::write(&path, "{\n  \"version\": 2,\n  \"cull\": tx\n}").unwrap();
+    let error = try_deserialize_versioned_from_file::<Pipeline>(&path).unwrap_err();
+    assert_eq!(error.path.as_deref(), Some(path.as_path()));
+    assert_eq!((error.kind, error.line), (SerializeErrorKind::Syntax, 3));
+    assert!(error.to_string().starts_with(path.to_str().unwrap()));
+
+    let missing = folder.join("missing.cfg");
+    let error = try_deserialize_from_file::<Pipeline>(&missing).unwrap_err();
+    assert_eq!(error.kind, SerializeErrorKind::Io);
+    assert!(try_serialize_to_file(&Vec::<u32>::new(), &folder.join("a/b.cfg")).is_err());
+    std::fs::remove_dir_all(&folder).ok();
+}
@@ -11,3 +11,7 @@ pub fn generate_random_uid() -> Uid {
 pub fn generate_uid_from_string(string: &str) -> Uid {
     Uuid::new_v5(&Uuid::NAMESPACE_URL, string.as_bytes())
 }
+#[inline]
+pub fn generate_uid_from_namespace(namespace: &Uid, string: &str) -> Uid {
+    Uuid::new_v5(namespace, string.as_bytes())
+}
@@ -0,0 +1,119 @@
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+use crate::{
+    try_deserialize_from_bytes, SerializationFormat, SerializeError, SerializeErrorKind,
+    SerializeResult,
+};
+
+pub type SerializedValue = serde_json::Value;
+
+pub const VERSION_FIELD: &str = "version";
+
+// Types whose layout changes over time: files written with an older version
+// are upgraded one version at a time before being deserialized.
+// Data written before the type was versioned has version 0
+pub trait Versioned {
+    const VERSION: u32;
+
+    // Upgrades data of the given version to the following one
+    fn migrate(_value: &mut SerializedValue, _from_version: u32) -> Result<(), String> {
+        Ok(())
+    }
+    // Types containing other versioned types have to upgrade them here
+    fn migrate_nested(_value: &mut SerializedValue) -> SerializeResult<()> {
+        Ok(())
+    }
+}
+
+pub fn version_of(value: &SerializedValue) -> u32 {
+    value
+        .get(VERSION_FIELD)
+        .and_then(|v| v.as_u64())
+        .map_or(0, |v| v as u32)
+}
+
+pub fn migrate<T>(value: &mut SerializedValue) -> SerializeResult<()>
+where
+    T: Versioned,
+{
+    let version = version_of(value);
+    if version > T::VERSION {
+        return Err(SerializeError::new(
+            SerializeErrorKind::Version,
+            format!(
+                "Data version {} is newer than {} of {}",
+                version,
+                T::VERSION,
+                std::any::type_name::<T>()
+            )
+            .as_str(),
+        ));
+    }
+    for from_version in version..T::VERSION {
+        T::migrate(value, from_version).map_err(|e| {
+            SerializeError::new(
+                SerializeErrorKind::Migration,
+                format!("From version {}: {}", from_version, e).as_str(),
+            )
+        })?;
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.insert(VERSION_FIELD.to_string(), T::VERSION.into());
+    }
+    T::migrate_nested(value)
+}
+
+// Migrates every value, or array of values, found with the key at any depth
+pub fn migrate_all<T>(value: &mut SerializedValue, key: &str) -> SerializeResult<()>
+where
+    T: Versioned,
+{
+    match value {
+        SerializedValue::Object(object) => {
+            for (k, v) in object.iter_mut() {
+                match v {
+                    SerializedValue::Object(_) if k == key => migrate::<T>(v)?,
+                    SerializedValue::Array(array) if k == key => {
+                        for v in array.iter_mut().filter(|v| v.is_object()) {
+                            migrate::<T>(v)?;
+                        }
+                    }
+                    _ => migrate_all::<T>(v, key)?,
+                }
+            }
+        }
+        SerializedValue::Array(array) => {
+            for v in array.iter_mut() {
+                migrate_all::<T>(v, key)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// Binary data has no migrations: it is always regenerated from its source
+pub fn try_deserialize_versioned<T>(bytes: &[u8]) -> SerializeResult<T>
+where
+    T: Versioned + for<'de> Deserialize<'de>,
+{
+    match SerializationFormat::from_bytes(bytes) {
+        SerializationFormat::Json => {
+            let mut value: SerializedValue = serde_json::from_slice(bytes)?;
+            migrate::<T>(&mut value)?;
+            Ok(serde_json::from_value(value)?)
+        }
+        SerializationFormat::Binary => try_deserialize_from_bytes(bytes),
+    }
+}
+
+pub fn try_deserialize_versioned_from_file<T>(filepath: &Path) -> SerializeResult<T>
+where
+    T: Versioned + for<'de> Deserialize<'de>,
+{
+    fs::read(filepath)
+        .map_err(SerializeError::from)
+        .and_then(|bytes| try_deserialize_versioned(bytes.as_slice()))
+        .map_err(|e| e.with_path(filepath))
+}
@@ -20,6 +20,8 @@ impl Default for VkData {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(crate = "nrg_serialize")]
 pub struct Config {
+    #[serde(default)]
+    version: u32,
     name: String,
     position: Vector2,
     width: u32,
@@ -32,6 +34,7 @@ pub struct Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: Self::VERSION,
             name: String::from("NRG"),
             position: Vector2::default_zero(),
             width: 1280,
@@ -43,6 +46,15 @@ impl Default for Config {
     }
 }
 
+// Pipelines are versioned on their own and upgraded together with the config
+impl Versioned for Config {
+    const VERSION: u32 = 1;
+
+    fn migrate_nested(value: &mut SerializedValue) -> SerializeResult<()> {
+        migrate_all::<PipelineData>(value, "pipelines")
+    }
+}
+
 impl Data for Config {}
 impl ConfigBase for Config {
     fn get_filename(&self) -> &'static str {
@@ -8,6 +8,16 @@ mod rendering_system;
 mod update_system;
 mod window_system;
 
+#[no_mangle]
+pub extern "C" fn get_plugin_abi() -> PluginAbi {
+    PluginAbi::current()
+}
+
+#[no_mangle]
+pub fn get_plugin_manifest() -> PluginManifest {
+    PluginManifest::new("nrg_window", env!("CARGO_PKG_VERSION"))
+}
+
 #[no_mangle]
 pub extern "C" fn create_plugin() -> PluginHolder {
     let window = main_window::MainWindow::default();
@@ -6,7 +6,7 @@ use nrg_core::*;
 use nrg_graphics::Renderer;
 use nrg_math::Vector2;
 use nrg_platform::Window;
-use nrg_resources::ConfigBase;
+use nrg_resources::{read_versioned_file, ConfigBase};
 use nrg_serialize::*;
 
 use crate::config::*;
@@ -44,7 +44,10 @@ unsafe impl Sync for MainWindow {}
 impl Plugin for MainWindow {
     fn prepare(&mut self, app: &mut App) {
         let path = self.config.get_filepath();
-        deserialize_from_file(&mut self.config, path);
+        match read_versioned_file(path.as_path()) {
+            Ok(config) => self.config = config,
+            Err(e) => eprintln!("Unable to load config {}", e),
+        }
 
         let window = {
             let pos = self.config.get_position();
@@ -44,10 +44,12 @@ impl System for RenderingSystem {
             return true;
         }
 
-        let (view, proj) = {
-            let view = *self.view.resource().get().view();
-            let proj = *self.view.resource().get().proj();
-            (view, proj)
+        let (view, proj) = match self.view.resource() {
+            Some(view) => {
+                let view = view.get();
+                (*view.view(), *view.proj())
+            }
+            None => return true,
         };
 
         let mut renderer = self.renderer.write().unwrap();
@@ -1,20 +1,21 @@
-use std::{
-    any::TypeId,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
-    thread,
-};
+use std::{any::TypeId, collections::HashSet};
 
 use crate::config::*;
 
 use nrg_core::*;
 use nrg_graphics::*;
 use nrg_messenger::{read_messages, MessageChannel, MessengerRw};
-use nrg_resources::{DataTypeResource, ResourceEvent, SharedData, SharedDataRw};
+use nrg_resources::{
+    ChangeTracker, DataTypeResource, Query, ResourceEvent, SharedData, SharedDataRw,
+};
 use nrg_serialize::INVALID_UID;
 
+//Unused resources are kept loaded until their type exceeds its budget
+const MESH_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+const MATERIAL_MEMORY_BUDGET: usize = 1024 * 1024;
+const TEXTURE_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+const FONT_MEMORY_BUDGET: usize = 32 * 1024 * 1024;
+
 pub struct UpdateSystem {
     id: SystemId,
     renderer: RendererRw,
@@ -22,6 +23,7 @@ pub struct UpdateSystem {
     job_handler: JobHandlerRw,
     config: Config,
     message_channel: MessageChannel,
+    change_tracker: ChangeTracker,
 }
 
 impl UpdateSystem {
@@ -45,6 +47,7 @@ impl UpdateSystem {
             job_handler,
             config: config.clone(),
             message_channel,
+            change_tracker: ChangeTracker::default(),
         }
     }
 }
@@ -60,6 +63,17 @@ impl System for UpdateSystem {
         false
     }
     fn init(&mut self) {
+        SharedData::set_memory_budget::<MeshInstance>(&self.shared_data, Some(MESH_MEMORY_BUDGET));
+        SharedData::set_memory_budget::<MaterialInstance>(
+            &self.shared_data,
+            Some(MATERIAL_MEMORY_BUDGET),
+        );
+        SharedData::set_memory_budget::<TextureInstance>(
+            &self.shared_data,
+            Some(TEXTURE_MEMORY_BUDGET),
+        );
+        SharedData::set_memory_budget::<FontInstance>(&self.shared_data, Some(FONT_MEMORY_BUDGET));
+
         for pipeline_data in self.config.get_pipelines().iter() {
             PipelineInstance::create_from_data(&self.shared_data, pipeline_data.clone());
         }
@@ -74,29 +88,14 @@ impl System for UpdateSystem {
         read_messages(self.message_channel.get_listener(), |msg| {
             if msg.type_id() == TypeId::of::<ResourceEvent>() {
                 let e = msg.as_any().downcast_ref::<ResourceEvent>().unwrap();
-                let ResourceEvent::Reload(path) = e;
-                if is_shader(path)
-                    && SharedData::has_resources_of_type::<PipelineInstance>(&self.shared_data)
-                {
-                    let pipelines = SharedData::get_resources_of_type::<PipelineInstance>(
-                        &self.shared_data,
-                    );
-                    for p in pipelines.iter() {
-                        p.resource().get_mut()
-                            .check_shaders_to_reload(path.to_str().unwrap().to_string());
-                    }
-                } else if is_texture(path)
-                    && SharedData::has_resources_of_type::<TextureInstance>(&self.shared_data)
-                {
-                    let textures =
-                        SharedData::get_resources_of_type::<TextureInstance>(&self.shared_data);
-                    for t in textures.iter() {
-                        if t.resource().get().path() == path.as_path() {
-                            t.resource().get_mut().invalidate();
-                        }
+                if let ResourceEvent::Reload(path) = e {
+                    if is_shader(path) {
+                        Query::<PipelineInstance>::default().for_each(&self.shared_data, |_, p| {
+                            p.get_mut()
+                                .check_shaders_to_reload(path.to_str().unwrap().to_string());
+                        });
                     }
                 }
-                
             }
         });
 
@@ -127,7 +126,17 @@ impl System for UpdateSystem {
             );
         }
 
-        let wait_count = Arc::new(AtomicUsize::new(0));
+        //Only meshes changed since the previous run are sent again to their pipeline
+        let last_run = self.change_tracker.update();
+        let mut changed_meshes = HashSet::new();
+        Query::<MeshInstance>::default()
+            .changed_since(last_run)
+            .for_each(&self.shared_data, |mesh, _| {
+                changed_meshes.insert(mesh.id());
+            });
+
+        let mut dependencies = Vec::new();
+        let mut visible_meshes = HashSet::new();
 
         if SharedData::has_resources_of_type::<MaterialInstance>(&self.shared_data) {
             let materials =
@@ -137,45 +146,73 @@ impl System for UpdateSystem {
                 .iter()
                 .enumerate()
                 .for_each(|(material_index, material_instance)| {
-                    if material_instance.resource().get().has_meshes() {
+                    //material could have been released in the meantime
+                    let material_resource = match material_instance.resource() {
+                        Some(material) => material,
+                        None => return,
+                    };
+                    let material = material_resource.get();
+                    if material.has_meshes() {
                         let mut diffuse_texture_id = INVALID_UID;
-                        let diffuse_color = material_instance.resource().get().diffuse_color();
-                        let outline_color = material_instance.resource().get().outline_color();
-                        let pipeline_id = material_instance.resource().get().pipeline().id();
-                        
-                        let (diffuse_texture_index, diffuse_layer_index) =
-                        if !material_instance.resource().get().has_diffuse_texture() {
-                            (INVALID_INDEX, INVALID_INDEX)
+                        let diffuse_color = material.diffuse_color();
+                        let outline_color = material.outline_color();
+                        let pipeline_id = material.pipeline().id();
+
+                        //A recreated pipeline or another texture needs every mesh to be sent again
+                        let is_material_changed = material_resource.is_changed_since(last_run)
+                            || material
+                                .pipeline()
+                                .resource()
+                                .is_some_and(|p| p.is_changed_since(last_run))
+                            || (material.has_diffuse_texture()
+                                && material
+                                    .diffuse_texture()
+                                    .resource()
+                                    .is_some_and(|t| t.is_changed_since(last_run)));
+
+                        //Missing textures are rendered as untextured
+                        let diffuse_texture = if material.has_diffuse_texture() {
+                            let diffuse_texture = material.diffuse_texture();
+                            diffuse_texture
+                                .resource()
+                                .filter(|t| t.get().texture_index() != INVALID_INDEX)
+                                .map(|t| (diffuse_texture.id(), t))
                         } else {
-                                let diffuse_texture = material_instance.resource().get().diffuse_texture();
-                                diffuse_texture_id = diffuse_texture.id();
-                                let (ti, li) = (
-                                    diffuse_texture.resource().get().texture_index() as _,
-                                    diffuse_texture.resource().get().layer_index() as _,
-                                );
-                                (ti, li)
-                            };
-
-                        material_instance
-                            .resource().get()
+                            None
+                        };
+                        let (diffuse_texture_index, diffuse_layer_index) = match diffuse_texture {
+                            Some((id, texture)) => {
+                                diffuse_texture_id = id;
+                                let texture = texture.get();
+                                (texture.texture_index() as _, texture.layer_index() as _)
+                            }
+                            None => (INVALID_INDEX, INVALID_INDEX),
+                        };
+
+                        material
                             .meshes()
                             .iter()
                             .enumerate()
                             .for_each(|(mesh_index, mesh_instance)| {
-                                if mesh_instance.resource().get().is_visible() {
-                                    let mesh_id = mesh_instance.id();
+                                let is_visible = mesh_instance
+                                    .resource()
+                                    .map_or(false, |mesh| mesh.get().is_visible());
+                                let mesh_id = mesh_instance.id();
+                                if is_visible {
+                                    visible_meshes.insert((pipeline_id, mesh_id));
+                                }
+                                if is_visible
+                                    && (is_material_changed || changed_meshes.contains(&mesh_id))
+                                {
                                     let shared_data = self.shared_data.clone();
                                     let r = self.renderer.clone();
-                                    let wait_count = wait_count.clone();
-
-                                    wait_count.fetch_add(1, Ordering::SeqCst);
 
                                     let job_name = format!(
                                         "PrepareMaterial [{}] with mesh [{}]",
                                         material_index, mesh_index
                                     );
 
-                                    self.job_handler.write().unwrap().add_job(
+                                    let handle = self.job_handler.write().unwrap().add_job(
                                         job_name.as_str(),
                                         move || {
                                             let mesh_instance = SharedData::get_resource::<
@@ -183,19 +220,30 @@ impl System for UpdateSystem {
                                             >(
                                                 &shared_data, mesh_id
                                             );
+                                            //mesh could have been released before the job ran
+                                            let mesh = match mesh_instance.resource() {
+                                                Some(mesh) => mesh,
+                                                None => return,
+                                            };
 
-                                            if !diffuse_texture_id.is_nil() {
+                                            //UVs converted for a previous texture are converted again
+                                            if is_material_changed {
+                                                mesh.get_mut().reset_uv_conversion();
+                                            }
+                                            //Writing every frame would mark the mesh as changed
+                                            let uv_converted = mesh.get().is_uv_converted();
+                                            if !uv_converted {
                                                 let renderer = r.read().unwrap();
-                                                let diffuse_texture = renderer
-                                                    .get_texture_handler()
-                                                    .get_texture(diffuse_texture_id);
-                                                mesh_instance.resource()
-                                                    .get_mut()
-                                                    .process_uv_for_texture(Some(diffuse_texture));
-                                            } else {
-                                                mesh_instance.resource()
-                                                    .get_mut()
-                                                    .process_uv_for_texture(None);
+                                                let diffuse_texture = if diffuse_texture_id.is_nil() {
+                                                    None
+                                                } else {
+                                                    Some(
+                                                        renderer
+                                                            .get_texture_handler()
+                                                            .get_texture(diffuse_texture_id),
+                                                    )
+                                                };
+                                                mesh.get_mut().process_uv_for_texture(diffuse_texture);
                                             }
                                             let mut renderer = r.write().unwrap();
                                             if let Some(pipeline) = renderer
@@ -204,7 +252,7 @@ impl System for UpdateSystem {
                                                 .find(|p| p.id() == pipeline_id)
                                             {
                                                 pipeline.add_mesh_instance(
-                                                    &mesh_instance.resource().get(),
+                                                    &mesh.get(),
                                                     diffuse_color,
                                                     diffuse_texture_index,
                                                     diffuse_layer_index,
@@ -213,10 +261,9 @@ impl System for UpdateSystem {
                                             } else {
                                                 eprintln!("Tyring to render with an unregistered pipeline {}", pipeline_id.to_simple().to_string());
                                             }
-
-                                            wait_count.fetch_sub(1, Ordering::SeqCst);
                                         },
                                     );
+                                    dependencies.push(handle.dependency());
                                 }
                             });
                     }
@@ -225,16 +272,22 @@ impl System for UpdateSystem {
 
         let renderer = self.renderer.clone();
         let job_name = "EndPreparation";
-        self.job_handler
-            .write()
-            .unwrap()
-            .add_job(job_name, move || {
-                while wait_count.load(Ordering::SeqCst) > 0 {
-                    thread::yield_now();
-                }
+        self.job_handler.write().unwrap().add_job_with_dependencies(
+            job_name,
+            JobPriority::Normal,
+            dependencies,
+            move || {
                 let mut r = renderer.write().unwrap();
+                //Hidden or released meshes are removed from their pipeline
+                r.get_pipelines().iter_mut().for_each(|p| {
+                    let pipeline_id = p.id();
+                    p.retain_mesh_instances(|mesh_id| {
+                        visible_meshes.contains(&(pipeline_id, *mesh_id))
+                    });
+                });
                 r.end_preparation();
-            });
+            },
+        );
 
         true
     }